@@ -0,0 +1,33 @@
+//! Streams a directory's files as NDJSON, one object per line as each file is
+//! ready: `cargo run --example stream_ndjson -- [PATH]`.
+
+use std::io::Write;
+use tokio_stream::StreamExt;
+
+#[tokio::main]
+async fn main() -> anyhow::Result<()> {
+    let root = std::env::args().nth(1).unwrap_or_else(|| ".".to_string());
+    let mut files = owo::stream(&owo::Options::new(root))?;
+    let mut stdout = std::io::stdout().lock();
+
+    while let Some(file) = files.next().await {
+        let line = match file {
+            Ok(file) => {
+                let (content, binary) = match file.content {
+                    owo::FileContent::Text(text) => (Some(text), false),
+                    owo::FileContent::Binary(_) => (None, true),
+                };
+                serde_json::json!({
+                    "path": file.path,
+                    "language": file.fence_tag,
+                    "size": file.size,
+                    "binary": binary,
+                    "content": content,
+                })
+            }
+            Err(e) => serde_json::json!({ "error": format!("{:#}", e) }),
+        };
+        writeln!(stdout, "{}", line)?;
+    }
+    Ok(())
+}
@@ -0,0 +1,58 @@
+//! Times the walk on one thread against the default, a thread per CPU, on a
+//! generated tree `WIDTH` directories wide and `DEPTH` deep with `FILES` files
+//! in each:
+//! `cargo run --release --example walk_bench -- [WIDTH] [DEPTH] [FILES]`.
+//! The tree is built in a temporary directory and removed afterwards.
+
+use std::path::Path;
+use std::time::Instant;
+
+const RUNS: usize = 5;
+
+fn main() -> Result<(), Box<dyn std::error::Error>> {
+    let arg = |n: usize, default: usize| std::env::args().nth(n).and_then(|arg| arg.parse().ok()).unwrap_or(default);
+    let (width, depth, files) = (arg(1, 8), arg(2, 4), arg(3, 20));
+    let root = std::env::temp_dir().join(format!("owo-walk-bench-{}", std::process::id()));
+    let _ = std::fs::remove_dir_all(&root);
+    let count = fixture(&root, width, depth, files)?;
+    println!("{} files in {}", count, root.display());
+
+    let mut walked = Vec::new();
+    for threads in [Some(1), None] {
+        let options = owo::Options::new(&root).walk_threads(threads);
+        // The first run warms the page cache.
+        owo::walk::walk(&options)?;
+        let mut times = Vec::new();
+        let mut paths = Vec::new();
+        for _ in 0..RUNS {
+            let start = Instant::now();
+            let walk = owo::walk::walk(&options)?;
+            times.push(start.elapsed());
+            paths = walk.files().map(Path::to_path_buf).collect();
+        }
+        times.sort();
+        let label = threads.map_or("default".to_string(), |threads| format!("{} thread", threads));
+        println!("{:>10}: median {:?}, best {:?}", label, times[RUNS / 2], times[0]);
+        walked.push(paths);
+    }
+    assert_eq!(walked[0], walked[1], "the walks found different files or orders");
+
+    std::fs::remove_dir_all(&root)?;
+    Ok(())
+}
+
+/// Builds the tree below `dir`, returning how many files it holds.
+fn fixture(dir: &Path, width: usize, depth: usize, files: usize) -> std::io::Result<usize> {
+    std::fs::create_dir_all(dir)?;
+    for file in 0..files {
+        std::fs::write(dir.join(format!("file{}.rs", file)), "fn main() {}\n")?;
+    }
+    let mut count = files;
+    if depth > 0 {
+        for sub in 0..width {
+            count += fixture(&dir.join(format!("dir{}", sub)), width, depth - 1, files)?;
+        }
+    }
+    Ok(count)
+}
+
@@ -0,0 +1,46 @@
+//! owo in the browser: files dropped on a page, packed without leaving the
+//! machine. Build with `wasm-pack build --target web` or
+//! `cargo build --target wasm32-unknown-unknown`.
+
+use wasm_bindgen::prelude::*;
+
+/// The files to pack, each a path relative to the root and its bytes.
+#[wasm_bindgen]
+#[derive(Default)]
+pub struct Files {
+    files: Vec<(String, Vec<u8>)>,
+}
+
+#[wasm_bindgen]
+impl Files {
+    #[wasm_bindgen(constructor)]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn add(&mut self, path: String, bytes: &[u8]) {
+        self.files.push((path, bytes.to_vec()));
+    }
+}
+
+impl Files {
+    fn iter(&self) -> impl Iterator<Item = (&str, &[u8])> {
+        self.files.iter().map(|(path, bytes)| (path.as_str(), bytes.as_slice()))
+    }
+}
+
+/// Packs `files` under `root` into Markdown with owo's defaults, including
+/// dotfiles when `dotfiles` is set and redacting secrets when `redact` is.
+#[wasm_bindgen]
+pub fn render(files: &Files, root: &str, dotfiles: bool, redact: bool) -> Result<String, JsError> {
+    let options = owo::Options::new(root).with_dotfiles(dotfiles).redact(redact);
+    owo::render_files(files.iter(), &options).map_err(|e| JsError::new(&e.to_string()))
+}
+
+/// The directory tree of what [`render`] would pack.
+#[wasm_bindgen]
+pub fn tree(files: &Files, root: &str, dotfiles: bool) -> Result<String, JsError> {
+    let options = owo::Options::new(root).with_dotfiles(dotfiles);
+    let bundle = owo::bundle_files(files.iter(), &options).map_err(|e| JsError::new(&e.to_string()))?;
+    Ok(owo::tree::render_bundle(&bundle, owo::tree::Annotations::default()))
+}
@@ -0,0 +1,210 @@
+//! Zip and tar archives packed in place, their entries standing in for a
+//! directory's files.
+
+use crate::paths::enclosed;
+use crate::{long_path, Error, FileContent, Result};
+use std::collections::BTreeMap;
+use std::fs::File;
+use std::io::{self, BufReader, Read};
+use std::path::{Component, Path, PathBuf};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+/// The file name endings read as archives, compared case-insensitively.
+pub const ARCHIVE_EXTENSIONS: &[&str] = &[".zip", ".tar", ".tar.gz", ".tgz", ".tar.zst", ".tzst"];
+
+/// Whether `path` is a file owo packs as an archive rather than a directory.
+pub fn is_archive(path: &Path) -> bool {
+    let name = path.file_name().map(|name| name.to_string_lossy().to_lowercase()).unwrap_or_default();
+    ARCHIVE_EXTENSIONS.iter().any(|extension| name.ends_with(extension)) && path.is_file()
+}
+
+/// One file or directory in an archive.
+#[derive(Debug, Clone)]
+pub struct ArchiveEntry {
+    pub is_dir: bool,
+    pub modified: Option<SystemTime>,
+    data: Vec<u8>,
+}
+
+impl ArchiveEntry {
+    /// The entry's size, uncompressed.
+    pub fn size(&self) -> u64 {
+        self.data.len() as u64
+    }
+
+    /// Up to `limit` bytes from the start of the entry.
+    pub(crate) fn head(&self, limit: u64) -> &[u8] {
+        &self.data[..self.data.len().min(limit as usize)]
+    }
+
+    /// The entry as text, or as binary when it isn't valid UTF-8, like a
+    /// file read from disk, with the number of invalid sequences `lossy`
+    /// replaced.
+    pub(crate) fn content(&self, lossy: bool) -> (FileContent, usize) {
+        FileContent::decode(self.data.clone(), lossy)
+    }
+}
+
+/// An archive's contents, read into memory, by path as walked: the
+/// archive's path joined with the entry's name. Archives inside it stay
+/// single binary files.
+#[derive(Debug, Clone)]
+pub struct Archive {
+    pub path: PathBuf,
+    entries: BTreeMap<PathBuf, ArchiveEntry>,
+    /// Entries left out, with the reason: links, and names that are absolute
+    /// or climb out with `..`.
+    pub skipped: Vec<(PathBuf, String)>,
+}
+
+impl Archive {
+    /// Reads the archive at `path`, picking the format by extension.
+    pub fn open(path: &Path) -> Result<Self> {
+        let name = path.file_name().map(|name| name.to_string_lossy().to_lowercase()).unwrap_or_default();
+        let archive = Self { path: path.to_path_buf(), entries: BTreeMap::new(), skipped: Vec::new() };
+        let read = || File::open(long_path(path)).map(BufReader::new);
+        let opened = if name.ends_with(".zip") {
+            read().and_then(|file| archive.read_zip(file))
+        } else if name.ends_with(".tar.gz") || name.ends_with(".tgz") {
+            read().and_then(|file| archive.read_tar(flate2::bufread::GzDecoder::new(file)))
+        } else if name.ends_with(".tar.zst") || name.ends_with(".tzst") {
+            read().and_then(|file| zstd::Decoder::with_buffer(file).and_then(|file| archive.read_tar(file)))
+        } else {
+            read().and_then(|file| archive.read_tar(file))
+        };
+        opened.map_err(|source| Error::Archive { path: path.to_path_buf(), source })
+    }
+
+    fn read_zip(mut self, file: BufReader<File>) -> io::Result<Self> {
+        let mut zip = zip::ZipArchive::new(file)?;
+        for index in 0..zip.len() {
+            let mut entry = zip.by_index(index)?;
+            let name = entry.name()?.into_owned();
+            let kind = if entry.is_symlink() { Err("symlink in archive") } else { Ok(entry.is_dir()) };
+            let mut data = Vec::new();
+            if kind == Ok(false) {
+                entry.read_to_end(&mut data)?;
+            }
+            self.add(Path::new(&name), kind, None, data);
+        }
+        Ok(self)
+    }
+
+    fn read_tar(mut self, reader: impl Read) -> io::Result<Self> {
+        let mut tar = tar::Archive::new(reader);
+        for entry in tar.entries()? {
+            let mut entry = entry?;
+            let name = entry.path()?.into_owned();
+            let kind = match entry.header().entry_type() {
+                tar::EntryType::Regular | tar::EntryType::Continuous => Ok(false),
+                tar::EntryType::Directory => Ok(true),
+                tar::EntryType::Symlink | tar::EntryType::Link => Err("link in archive"),
+                // Global pax headers and the like describe other entries.
+                _ => continue,
+            };
+            let modified = entry.header().mtime().ok().map(|secs| UNIX_EPOCH + Duration::from_secs(secs));
+            let mut data = Vec::new();
+            if kind == Ok(false) {
+                entry.read_to_end(&mut data)?;
+            }
+            self.add(&name, kind, modified, data);
+        }
+        Ok(self)
+    }
+
+    /// Adds an entry named `name`, a directory or file or else the reason
+    /// it's skipped. A later entry with the same name replaces an earlier
+    /// one, as when extracting.
+    fn add(&mut self, name: &Path, kind: std::result::Result<bool, &str>, modified: Option<SystemTime>, data: Vec<u8>) {
+        let Some(rel) = enclosed(name) else {
+            // Shown under the archive, so an absolute name doesn't read as
+            // a file on this machine.
+            let shown = name.components().filter(|part| !matches!(part, Component::RootDir | Component::Prefix(_)));
+            self.skipped.push((self.path.join(shown.collect::<PathBuf>()), "unsafe path in archive".to_string()));
+            return;
+        };
+        match kind {
+            Ok(is_dir) => {
+                self.entries.insert(self.path.join(rel), ArchiveEntry { is_dir, modified, data });
+            }
+            Err(reason) => self.skipped.push((self.path.join(rel), reason.to_string())),
+        }
+    }
+
+    /// Every entry in walk order: sorted by name within each directory,
+    /// a directory's entries right after it.
+    pub fn entries(&self) -> impl Iterator<Item = (&Path, &ArchiveEntry)> {
+        self.entries.iter().map(|(path, entry)| (path.as_path(), entry))
+    }
+
+    pub fn get(&self, path: &Path) -> Option<&ArchiveEntry> {
+        self.entries.get(path)
+    }
+
+    /// Keeps only the entries `keep` accepts.
+    pub(crate) fn retain(&mut self, mut keep: impl FnMut(&Path, &ArchiveEntry) -> bool) {
+        self.entries.retain(|path, entry| keep(path, entry));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Write;
+
+    /// Names that would land outside the directory an archive is extracted
+    /// to, and names that only look odd.
+    const NAMES: &[&str] = &["../x", "/etc/x", "a/../../x", "./b/c.txt", "ok.txt"];
+
+    fn zip(path: &Path) {
+        let mut zip = zip::ZipWriter::new(File::create(path).unwrap());
+        for name in NAMES {
+            zip.start_file(*name, zip::write::SimpleFileOptions::default()).unwrap();
+            zip.write_all(name.as_bytes()).unwrap();
+        }
+        zip.finish().unwrap();
+    }
+
+    fn tar(path: &Path) {
+        let mut tar = tar::Builder::new(File::create(path).unwrap());
+        for name in NAMES {
+            // The builder refuses to write `..` and absolute names, so
+            // they go straight into the header.
+            let mut header = tar::Header::new_old();
+            header.as_old_mut().name[..name.len()].copy_from_slice(name.as_bytes());
+            header.set_entry_type(tar::EntryType::Regular);
+            header.set_size(name.len() as u64);
+            header.set_cksum();
+            tar.append(&header, name.as_bytes()).unwrap();
+        }
+        tar.finish().unwrap();
+    }
+
+    #[test]
+    fn names_outside_the_archive_are_skipped_and_the_rest_normalized() {
+        let dir = tempfile::tempdir().unwrap();
+        for (file, write) in [("a.zip", zip as fn(&Path)), ("a.tar", tar)] {
+            let path = dir.path().join(file);
+            write(&path);
+            let archive = Archive::open(&path).unwrap();
+            let entries = archive.entries().map(|(name, entry)| (name.to_path_buf(), entry.data.clone()));
+            assert_eq!(
+                entries.collect::<Vec<_>>(),
+                [(path.join("b/c.txt"), b"./b/c.txt".to_vec()), (path.join("ok.txt"), b"ok.txt".to_vec())],
+                "{}",
+                file
+            );
+            let skipped = archive.skipped.iter().map(|(name, reason)| (name.clone(), reason.as_str()));
+            assert_eq!(
+                skipped.collect::<Vec<_>>(),
+                [
+                    (path.join("../x"), "unsafe path in archive"),
+                    (path.join("etc/x"), "unsafe path in archive"),
+                    (path.join("a/../../x"), "unsafe path in archive"),
+                ],
+                "{}",
+                file
+            );
+        }
+    }
+}
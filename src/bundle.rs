@@ -0,0 +1,571 @@
+use crate::grep::{Grep, GrepExclude};
+use crate::markdown::{code_span, GroupBy, MarkdownStyle};
+use crate::profiles::Profile;
+use crate::ranges::{self, LineRanges};
+use crate::redact::{Redactor, SecretReportEntry};
+use crate::summary::{human_size, Summary};
+use crate::{comments, generated, lang, lockfiles, transform, walk, Error, Options, Result};
+use globset::GlobSet;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::time::SystemTime;
+
+/// A file's content as it appears in a bundle.
+#[derive(Debug, Clone)]
+pub enum FileContent {
+    /// The text after every enabled transformation.
+    Text(String),
+    /// A file that isn't valid UTF-8, with its size in bytes.
+    Binary(usize),
+}
+
+impl FileContent {
+    /// `bytes` as text, or as binary when they aren't valid UTF-8, along with
+    /// the number of invalid sequences shown as U+FFFD. With `lossy`, bytes
+    /// that are almost text, with no NUL and at most one invalid sequence per
+    /// 16 bytes, are still text.
+    pub(crate) fn decode(bytes: Vec<u8>, lossy: bool) -> (Self, usize) {
+        let bytes = match String::from_utf8(bytes) {
+            Ok(text) => return (Self::Text(text), 0),
+            Err(e) => e.into_bytes(),
+        };
+        let invalid = bytes.utf8_chunks().filter(|chunk| !chunk.invalid().is_empty()).count();
+        if !lossy || bytes.contains(&0) || invalid > (bytes.len() / 16).max(1) {
+            return (Self::Binary(bytes.len()), 0);
+        }
+        (Self::Text(String::from_utf8_lossy(&bytes).into_owned()), invalid)
+    }
+}
+
+/// One file of a bundle.
+#[derive(Debug, Clone)]
+pub struct FileEntry {
+    /// The path as walked, starting with the root.
+    pub path: PathBuf,
+    /// The code fence's info string, usually the file extension.
+    pub fence_tag: Option<String>,
+    pub content: FileContent,
+    /// The file's size on disk, before any transformation.
+    pub size: u64,
+    pub modified: Option<SystemTime>,
+    /// The line ranges kept, 1-based and inclusive, when an
+    /// [`Options::only`] entry asked for some. Empty for the whole file.
+    pub lines: Vec<(usize, usize)>,
+    /// Whether the file changed while it was read, so the content may not
+    /// match any one state of it.
+    pub changed: bool,
+    /// How many invalid UTF-8 sequences were shown as U+FFFD, with
+    /// [`Options::lossy`].
+    pub invalid_utf8: usize,
+    /// Where the file can be viewed online, linked from its heading. Never
+    /// set by packing; callers that know the file's forge fill it in.
+    pub permalink: Option<String>,
+}
+
+/// Everything [`generate`] produced: the files in walk order, plus what was
+/// found and left out along the way.
+#[derive(Debug)]
+pub struct Bundle {
+    /// The directory that was walked.
+    pub root: PathBuf,
+    /// Text rendered verbatim before the files.
+    pub header: Option<String>,
+    pub files: Vec<FileEntry>,
+    /// Text rendered verbatim after the files.
+    pub footer: Option<String>,
+    /// Secrets found, sorted by path and line. Only collected with
+    /// [`Options::scan_secrets`].
+    pub findings: Vec<SecretReportEntry>,
+    pub summary: Summary,
+    /// Profiles detected from marker files, with the marker for each.
+    pub detected_profiles: Vec<(&'static Profile, String)>,
+    /// The profiles whose ignore patterns were applied.
+    pub profiles: Vec<&'static Profile>,
+    /// The layout [`markdown`](Self::markdown) renders with.
+    pub style: MarkdownStyle,
+    /// Files and directories left out because they couldn't be read, as
+    /// [`Error::Read`] and [`Error::Walk`], and filters that failed, as
+    /// [`Error::FilterFailed`].
+    pub failures: Vec<Error>,
+    /// User-supplied ignore patterns that matched nothing; see
+    /// [`Walk::unmatched_patterns`](crate::walk::Walk::unmatched_patterns).
+    pub unmatched_patterns: Vec<String>,
+    /// Only globs that matched nothing; see
+    /// [`Walk::unmatched_only`](crate::walk::Walk::unmatched_only).
+    pub unmatched_only: Vec<String>,
+}
+
+impl Bundle {
+    /// The whole bundle as a Markdown document.
+    pub fn markdown(&self) -> String {
+        let mut out = String::new();
+        if let Some(header) = &self.header {
+            out.push_str(header);
+            if !header.ends_with('\n') {
+                out.push('\n');
+            }
+        }
+        for (heading, files) in self.groups() {
+            if let Some(heading) = heading {
+                out.push_str(&format!("\n# {}\n", heading));
+            }
+            for file in files {
+                out.push_str(&file.markdown(&self.style));
+            }
+        }
+        if let Some(footer) = &self.footer {
+            out.push('\n');
+            out.push_str(footer);
+            if !footer.ends_with('\n') {
+                out.push('\n');
+            }
+        }
+        out
+    }
+
+    /// The files as [`MarkdownStyle::group_by`] groups them, each group with
+    /// its heading, like `` Directory: `./src` ``, `Language: rs` or `Other`.
+    /// Files are in path order within a group. Without grouping, it's one
+    /// group of every file in walk order, with no heading.
+    pub fn groups(&self) -> Vec<(Option<String>, Vec<&FileEntry>)> {
+        let heading = |file: &FileEntry| match (self.style.group_by, &file.fence_tag) {
+            (GroupBy::None, _) => None,
+            (GroupBy::Dir, _) => Some(format!("Directory: {}", code_span(&file.path.parent()?.display().to_string()))),
+            (GroupBy::Language, Some(tag)) => Some(format!("Language: {}", tag)),
+            (GroupBy::Language, None) => Some("Other".to_string()),
+        };
+        let mut files = self.files.iter().collect::<Vec<_>>();
+        match self.style.group_by {
+            GroupBy::None => {}
+            GroupBy::Dir => files.sort_by(|a, b| a.path.parent().cmp(&b.path.parent()).then(a.path.cmp(&b.path))),
+            GroupBy::Language => files.sort_by(|a, b| {
+                let tag = |file: &FileEntry| (file.fence_tag.is_none(), file.fence_tag.clone());
+                tag(a).cmp(&tag(b)).then(a.path.cmp(&b.path))
+            }),
+        }
+        let mut groups: Vec<(Option<String>, Vec<&FileEntry>)> = Vec::new();
+        for file in files {
+            let heading = heading(file);
+            match groups.last_mut() {
+                Some((last, files)) if *last == heading => files.push(file),
+                _ => groups.push((heading, vec![file])),
+            }
+        }
+        groups
+    }
+
+    /// Splits the files by their directory `depth` levels below the root, in
+    /// walk order, as `(dir, bundle)` pairs. Files fewer than `depth` levels
+    /// deep go by the directory they're in, and files at the root under an
+    /// empty name, so they stay apart from a directory named `root`. Each
+    /// part keeps the header, footer and style; findings, summary and
+    /// failures stay with `self`.
+    pub fn split_by_dir(&self, depth: usize) -> Vec<(String, Bundle)> {
+        let mut parts: Vec<(String, Bundle)> = Vec::new();
+        for file in &self.files {
+            let rel = file.path.strip_prefix(&self.root).unwrap_or(&file.path);
+            let dirs = rel
+                .parent()
+                .into_iter()
+                .flat_map(Path::components)
+                .filter_map(|component| match component {
+                    std::path::Component::Normal(name) => Some(name.to_string_lossy()),
+                    _ => None,
+                })
+                .take(depth)
+                .collect::<Vec<_>>();
+            let dir = dirs.join("/");
+            let index = match parts.iter().position(|(name, _)| *name == dir) {
+                Some(index) => index,
+                None => {
+                    parts.push((dir, self.part()));
+                    parts.len() - 1
+                }
+            };
+            parts[index].1.files.push(file.clone());
+        }
+        parts
+    }
+
+    /// An empty bundle with the same root, header, footer and style.
+    fn part(&self) -> Bundle {
+        Bundle::from_files(self.root.clone(), self.header.clone(), Vec::new(), self.footer.clone(), self.style.clone())
+    }
+
+    /// The bundle with as many files from the start, in
+    /// [`groups`](Self::groups) order, as `render` fits in `limit` bytes, cut
+    /// at a section boundary, and a note of how many were left out before the
+    /// footer. Returns it and how many files were
+    /// omitted, or `None` when not even the header and footer fit.
+    pub fn truncated(&self, limit: u64, render: impl Fn(&Bundle) -> String) -> Option<(Bundle, usize)> {
+        let total = self.files.len();
+        let ordered = self.groups().into_iter().flat_map(|(_, files)| files).collect::<Vec<_>>();
+        let keep = |kept: usize| {
+            let omitted = total - kept;
+            let note = format!("[Truncated: {} of {} files omitted to stay under {}]", omitted, total, human_size(limit));
+            let footer = match &self.footer {
+                Some(footer) => format!("{}\n\n{}", note, footer),
+                None => note,
+            };
+            let files = ordered[..kept].iter().map(|&file| file.clone()).collect();
+            Bundle::from_files(self.root.clone(), self.header.clone(), files, Some(footer), self.style.clone())
+        };
+        let fits = |kept| render(&keep(kept)).len() as u64 <= limit;
+        if !fits(0) {
+            return None;
+        }
+        // Every file kept adds to the size, so the cut can be bisected.
+        let (mut low, mut high) = (0, total);
+        while low < high {
+            let mid = (low + high).div_ceil(2);
+            if fits(mid) {
+                low = mid;
+            } else {
+                high = mid - 1;
+            }
+        }
+        Some((keep(low), total - low))
+    }
+
+    /// A bundle of just these files, with nothing found or left out.
+    pub(crate) fn from_files(
+        root: PathBuf,
+        header: Option<String>,
+        files: Vec<FileEntry>,
+        footer: Option<String>,
+        style: MarkdownStyle,
+    ) -> Bundle {
+        Bundle {
+            root,
+            header,
+            files,
+            footer,
+            findings: Vec::new(),
+            summary: Summary::default(),
+            detected_profiles: Vec::new(),
+            profiles: Vec::new(),
+            style,
+            failures: Vec::new(),
+            unmatched_patterns: Vec::new(),
+            unmatched_only: Vec::new(),
+        }
+    }
+
+    /// Moves every path from below [`root`](Self::root) to below `root`, as
+    /// when the walk ran over a temporary copy the bundle shouldn't mention.
+    /// Failures keep the paths they were reported with.
+    pub fn rebase(&mut self, root: &Path) {
+        let old = std::mem::replace(&mut self.root, root.to_path_buf());
+        let rebase = |path: &mut PathBuf| {
+            if let Ok(rel) = path.strip_prefix(&old) {
+                *path = root.join(rel);
+            }
+        };
+        self.files.iter_mut().for_each(|file| rebase(&mut file.path));
+        self.findings.iter_mut().for_each(|finding| rebase(&mut finding.path));
+        let summary = &mut self.summary;
+        summary.skipped.iter_mut().for_each(|(path, _)| rebase(path));
+        summary.redactions.iter_mut().for_each(|(path, _)| rebase(path));
+        summary.grep_matches.iter_mut().for_each(|(path, _)| rebase(path));
+        if let Some(timings) = &mut summary.timings {
+            timings.reads.iter_mut().for_each(|read| rebase(&mut read.path));
+        }
+        let (old, new) = (old.display().to_string(), root.display().to_string());
+        for warning in &mut summary.warnings {
+            if let Some(rest) = warning.strip_prefix(&old) {
+                *warning = format!("{}{}", new, rest);
+            }
+        }
+    }
+
+    /// The whole bundle as pretty-printed JSON: the header, footer and every
+    /// file's path, language, size and content (`null` for binary files).
+    /// Files are in [`groups`](Self::groups) order.
+    pub fn json(&self) -> String {
+        let files = self
+            .groups()
+            .into_iter()
+            .flat_map(|(_, files)| files)
+            .map(|file| {
+                let (content, binary) = match &file.content {
+                    FileContent::Text(text) => (Some(text.as_str()), false),
+                    FileContent::Binary(_) => (None, true),
+                };
+                serde_json::json!({
+                    "path": file.path,
+                    "language": file.fence_tag,
+                    "size": file.size,
+                    "binary": binary,
+                    "content": content,
+                    "lines": (!file.lines.is_empty()).then_some(&file.lines),
+                    "changed": file.changed,
+                    "invalid_utf8": file.invalid_utf8,
+                    "permalink": file.permalink,
+                })
+            })
+            .collect::<Vec<_>>();
+        let bundle = serde_json::json!({ "header": self.header, "files": files, "footer": self.footer });
+        serde_json::to_string_pretty(&bundle).expect("JSON values always serialize") + "\n"
+    }
+}
+
+/// A text file [`Transformer::select`] kept, on its way to
+/// [`Transformer::finish`]. Filter commands run in between.
+pub(crate) struct Selected {
+    pub(crate) path: PathBuf,
+    fence_tag: Option<String>,
+    pub(crate) content: String,
+    size: u64,
+    modified: Option<SystemTime>,
+    lines: Vec<(usize, usize)>,
+}
+
+/// What [`Transformer::select`] made of a file.
+pub(crate) enum Step {
+    /// Skipped, or finished without further transformation.
+    Done(Option<FileEntry>),
+    /// Text to transform further.
+    Text(Selected),
+}
+
+/// The per-file transformations and the state they report into, shared by
+/// every file of a run. Nothing here touches the file system, so files held
+/// in memory go through the same steps as files read from disk.
+#[derive(Clone)]
+pub(crate) struct Transformer {
+    pub(crate) options: Arc<Options>,
+    redactor: Option<Arc<Redactor>>,
+    grep: Option<Arc<Grep>>,
+    grep_exclude: Option<Arc<GrepExclude>>,
+    ranges: Option<Arc<LineRanges>>,
+    /// The only globs, whose files are never judged generated.
+    only: Option<Arc<GlobSet>>,
+    pub(crate) summary: Arc<Mutex<Summary>>,
+    pub(crate) findings: Arc<Mutex<Vec<SecretReportEntry>>>,
+    /// Non-fatal problems that didn't cost a file, like failed filters.
+    pub(crate) failures: Arc<Mutex<Vec<Error>>>,
+}
+
+impl Transformer {
+    pub(crate) fn new(options: &Options) -> Result<Self> {
+        let redactor = if options.redacts() || options.scan_secrets {
+            Some(Arc::new(Redactor::new(
+                &options.redact_patterns,
+                options.redact_entropy,
+                &options.secret_allow,
+            )?))
+        } else {
+            None
+        };
+        Ok(Self {
+            options: Arc::new(options.clone()),
+            redactor,
+            grep: Grep::new(&options.grep, options.grep_context)?.map(Arc::new),
+            grep_exclude: GrepExclude::new(&options.grep_exclude, options.grep_exclude_full)?.map(Arc::new),
+            ranges: LineRanges::new(&options.only)?.map(Arc::new),
+            only: walk::only_globs(options)?.map(Arc::new),
+            summary: Arc::default(),
+            findings: Arc::default(),
+            failures: Arc::default(),
+        })
+    }
+
+    /// Whether [`excludes_head`](Self::excludes_head) wants the start of
+    /// each file before the rest is read.
+    pub(crate) fn checks_head(&self) -> bool {
+        self.grep_exclude.as_ref().is_some_and(|exclude| !exclude.full)
+    }
+
+    /// Whether `--grep-exclude` drops the file by its first bytes, `head`.
+    /// A dropped file is recorded as skipped.
+    pub(crate) fn excludes_head(&self, path: &Path, head: &[u8]) -> bool {
+        let exclude = self.grep_exclude.as_ref().filter(|exclude| !exclude.full);
+        match exclude.and_then(|exclude| exclude.fired(&String::from_utf8_lossy(head))) {
+            Some(pattern) => {
+                self.skip_grep_excluded(path.to_path_buf(), pattern);
+                true
+            }
+            None => false,
+        }
+    }
+
+    fn skip_grep_excluded(&self, path: PathBuf, pattern: &str) {
+        let reason = format!("grep-exclude: pattern '{}'", pattern);
+        self.summary.lock().unwrap().skipped.push((path, reason));
+    }
+
+    /// Decides whether a file that's been read is packed, and applies what
+    /// comes before filter commands: `--grep` selection, lockfile summaries,
+    /// line ranges, screening a file cut to them first, and generated-content
+    /// detection.
+    pub(crate) fn select(&self, path: PathBuf, size: u64, modified: Option<SystemTime>, read: FileContent) -> Step {
+        let options = &self.options;
+        if let Some(exclude) = self.grep_exclude.as_ref().filter(|exclude| exclude.full)
+            && let FileContent::Text(text) = &read
+            && let Some(pattern) = exclude.fired(text)
+        {
+            self.skip_grep_excluded(path, pattern);
+            return Step::Done(None);
+        }
+        if let Some(grep) = &self.grep {
+            let FileContent::Text(text) = &read else {
+                return Step::Done(None);
+            };
+            match grep.count(text) {
+                0 => return Step::Done(None),
+                count => self.summary.lock().unwrap().grep_matches.push((path.clone(), count)),
+            }
+        }
+        let fence_tag = lang::fence_tag(&path);
+        let mut lines = Vec::new();
+        let mut content = match read {
+            FileContent::Text(content) if options.summarizes_lockfiles() && lockfiles::is_lockfile(&path) => {
+                let content = match lockfiles::dependency_names(&path, &content) {
+                    Some(names) => format!(
+                        "[Lockfile summary: {} dependencies]\n{}",
+                        names.len(),
+                        names.join("\n")
+                    ),
+                    None => format!("[Lockfile: {} bytes]", content.len()),
+                };
+                let fence_tag = Some("text".to_string());
+                let content = FileContent::Text(content);
+                return Step::Done(Some(FileEntry {
+                    path,
+                    fence_tag,
+                    content,
+                    size,
+                    modified,
+                    lines,
+                    changed: false,
+                    invalid_utf8: 0,
+                    permalink: None,
+                }));
+            }
+            FileContent::Text(content) => content,
+            content @ FileContent::Binary(_) => {
+                return Step::Done(Some(FileEntry {
+                    path,
+                    fence_tag,
+                    content,
+                    size,
+                    modified,
+                    lines,
+                    changed: false,
+                    invalid_utf8: 0,
+                    permalink: None,
+                }));
+            }
+        };
+        let rel = path.strip_prefix(&options.root).unwrap_or(&path);
+        if let Some(wanted) = self.ranges.as_ref().and_then(|ranges| ranges.for_path(rel)) {
+            let selection = ranges::resolve(&content, wanted);
+            let warnings = selection.clamped.iter().map(|message| format!("{}: {}", path.display(), message));
+            self.summary.lock().unwrap().warnings.extend(warnings);
+            // Screened whole, so a secret whose start is cut off is still
+            // found and findings keep the file's line numbers.
+            let screened = self.screen(&path, content, Some(&selection.lines));
+            content = ranges::excerpt(&screened, &selection.lines);
+            lines = selection.lines;
+        }
+        let named = self.only.as_ref().is_some_and(|only| only.is_match(rel));
+        if options.skip_generated
+            && !named
+            && let Some(reason) = generated::detect_by_content(&content)
+        {
+            self.summary.lock().unwrap().skipped.push((path, format!("generated: {}", reason)));
+            return Step::Done(None);
+        }
+        Step::Text(Selected { path, fence_tag, content, size, modified, lines })
+    }
+
+    /// Strips ANSI escapes, first so the other steps see the text as a
+    /// terminal shows it, then scans for and redacts secrets. With `within`,
+    /// the line ranges the file is about to be cut to, only findings on those
+    /// lines count, and redaction keeps every line in place for the cut.
+    fn screen(&self, path: &Path, mut content: String, within: Option<&[(usize, usize)]>) -> String {
+        let options = &self.options;
+        if options.strip_ansi
+            && let Some(stripped) = transform::strip_ansi(&content)
+        {
+            self.summary.lock().unwrap().ansi_stripped += 1;
+            content = stripped;
+        }
+        let Some(redactor) = self.redactor.as_ref().filter(|r| !r.allows_path(path)) else {
+            return content;
+        };
+        if options.scan_secrets {
+            let found = redactor.scan(path, &content, within);
+            self.findings.lock().unwrap().extend(found);
+        }
+        if options.redacts() {
+            let (redacted, count) = match within {
+                Some(within) => redactor.redact_lines(&content, within),
+                None => redactor.redact(&content),
+            };
+            if count > 0 {
+                self.summary.lock().unwrap().redactions.push((path.to_path_buf(), count));
+            }
+            content = redacted;
+        }
+        content
+    }
+
+    /// Applies what comes after filter commands: ANSI stripping, redaction
+    /// and secret scanning, unless the file was cut to line ranges, comment
+    /// and whitespace transformations, `--grep-context` excerpts and wrapping.
+    pub(crate) fn finish(&self, file: Selected) -> FileEntry {
+        let options = &self.options;
+        let Selected { path, fence_tag, mut content, size, modified, lines } = file;
+        // A file cut to line ranges was screened before the cut.
+        if lines.is_empty() {
+            content = self.screen(&path, content, None);
+        }
+        let syntax = fence_tag.as_deref().and_then(comments::syntax_for);
+        if options.strip_license_headers
+            && let Some(stripped) = syntax.and_then(|syntax| comments::strip_license_header(&content, syntax))
+        {
+            self.summary.lock().unwrap().license_headers_stripped += 1;
+            content = stripped;
+        }
+        if options.strip_comments && let Some(syntax) = syntax {
+            content = comments::strip_comments(&content, syntax, options.keep_doc_comments);
+        }
+        if let Some(width) = options.expand_tabs
+            && !transform::is_makefile(fence_tag.as_deref())
+        {
+            content = transform::expand_leading_tabs(&content, width);
+        }
+        if options.dedent {
+            content = transform::dedent(&content);
+        }
+        if options.trim_trailing_whitespace {
+            content = transform::trim_trailing_whitespace(&content);
+        }
+        if let Some(max) = options.squeeze_blank_lines {
+            let (squeezed, removed) = transform::squeeze_blank_lines(&content, max);
+            self.summary.lock().unwrap().blank_lines_removed += removed;
+            content = squeezed;
+        }
+        if let Some(excerpt) = self.grep.as_ref().and_then(|grep| grep.excerpt(&content)) {
+            content = excerpt;
+        }
+        if let Some(width) = options.wrap {
+            let tab = options.expand_tabs.unwrap_or(8);
+            let (wrapped, lines) = transform::wrap_lines(&content, width, &options.wrap_marker, tab);
+            self.summary.lock().unwrap().lines_wrapped += lines;
+            content = wrapped;
+        }
+        FileEntry {
+            path,
+            fence_tag,
+            content: FileContent::Text(content),
+            size,
+            modified,
+            lines,
+            changed: false,
+            invalid_utf8: 0,
+            permalink: None,
+        }
+    }
+}
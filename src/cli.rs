@@ -0,0 +1,1071 @@
+use clap::{Arg, ArgAction, ArgMatches, Command, ValueHint};
+use owo::Options;
+use std::time::Duration;
+
+/// Names of the subcommands. An invocation whose first argument isn't one of
+/// these (or a root flag) runs `pack`, so `owo -o out.md .` keeps working.
+const SUBCOMMANDS: &[&str] = &["pack", "tree", "stats", "explain", "diff", "merge", "init", "completions"];
+
+/// Root-level flags that must not be routed to the default subcommand.
+const ROOT_FLAGS: &[&str] = &["-h", "--help", "-V", "--version", "--generate-man"];
+
+/// Example invocations, shown by `--help` and in the man page.
+pub const EXAMPLES: &[&str] = &[
+    "owo -o content.md",
+    "owo pack -I \"obj|bin|build|dist\" -o content.md -w",
+    "owo --profile rust --profile node -o content.md",
+    "owo --profile node -I \"obj|bin|build|dist|!coverage\" -o content.md",
+    "owo --redact -o content.md",
+    "owo --fail-on-secret --secret-report secrets.json -o content.md",
+    "owo --strip-comments --keep-doc-comments -o content.md",
+    "owo --strip-license-headers -o content.md",
+    "owo --skip-generated -o content.md",
+    "owo --lockfile-summary -o content.md",
+    "owo --squeeze-blank-lines=2 --trim-trailing-whitespace -o content.md",
+    "owo --expand-tabs=2 --dedent -o content.md",
+    "owo --filter-cmd '*.sql:sqlformat --reindent -' -o content.md",
+    "owo --header-file prompt.md --footer-text \"Questions? See the wiki.\" -o content.md",
+    "owo --collapsible=summary -o content.md",
+    "owo --group-by language -o content.md",
+    "owo --permalinks -o review.md",
+    "owo --list -v -I \"obj|bin|tests\"",
+    "owo --list --print0 | xargs -0 wc -l",
+    "owo --only 'src/**' --only Cargo.toml -o content.md",
+    "owo --only src/big.rs:120-240,300- -o focus.md",
+    "git format-patch -1 --stdout | owo --from-patch - -o review.md",
+    "owo --no-tests -o overview.md",
+    "owo --owner @org/payments-team -o payments.md",
+    "owo --grep 'FeatureFlag::NewCheckout' --grep-context 5 -o checkout.md",
+    "owo --grep-exclude '@generated|DO NOT EDIT' -o content.md",
+    "owo --interactive -o content.md",
+    "owo -o context.md -o context.json",
+    "owo -o snapshots.db --db-prune 5",
+    "owo --split-by-dir=2 -o 'bundles/{dir}.md'",
+    "owo --config ci/owo.toml",
+    "owo release.tar.gz -o release.md",
+    "owo https://github.com/foo/bar --rev v1.2.0 -o bar.md",
+    "owo tree --sizes src",
+    "owo tree --tree-annotations size,tokens",
+    "owo tree --tree-format mermaid -o structure.md",
+    "owo stats --format csv > languages.csv",
+    "owo explain node_modules/left-pad/index.js --json",
+    "owo diff monday.md today.md --stat",
+    "owo merge bundles/*.md -o combined.md --provenance",
+    "owo completions zsh > ~/.zfunc/_owo",
+];
+
+/// Help sections beyond the option list, shown by `--help` and in the man page.
+pub const HELP_SECTIONS: &[(&str, &str)] = &[
+    (
+        "Configuration",
+        "Options can also be set in owo.toml at the traversal root, or in
+~/.config/owo/config.toml for user defaults, using the option names in
+snake_case (ignore = \"obj|bin\", with_dotfiles = true, profile = [\"rust\"]).
+Command-line flags override owo.toml, which overrides the user config.",
+    ),
+    (
+        "Environment",
+        "Every option can be set through an OWO_<NAME> variable, named after its
+snake_case config key (OWO_IGNORE, OWO_OUTPUT, OWO_WITH_DOTFILES=1).
+Environment variables override config files; flags override both.",
+    ),
+    (
+        "Exit status",
+        "0  success
+1  any other error, e.g. an output file that can't be written
+2  bad arguments: unknown flags, invalid values or patterns, a missing PATH,
+   or ignore patterns or --only globs that match nothing under --strict-patterns
+3  files or directories that couldn't be read, or failed filters, under --strict",
+    ),
+];
+
+fn after_help() -> String {
+    let indent = |text: &str| text.lines().map(|line| format!("  {}\n", line)).collect::<String>();
+    let mut help = format!("Examples:\n{}", indent(&EXAMPLES.join("\n")));
+    for (title, body) in HELP_SECTIONS {
+        help.push_str(&format!("\n{}:\n{}", title, indent(body)));
+    }
+    help
+}
+
+/// Options shared by every subcommand that walks a directory: where to walk
+/// and what to leave out.
+pub fn common_args() -> Vec<Arg> {
+    vec![
+        Arg::new("ignore")
+            .short('I')
+            .long("ignore")
+            .value_name("PATTERNS")
+            .help("Ignore files/directories matching these .gitignore-style patterns (pipe-separated, a leading / anchors to PATH, a trailing / matches only directories, a leading ! re-includes)")
+            .default_value(owo::DEFAULT_IGNORE),
+        Arg::new("with_dotfiles")
+            .short('w')
+            .long("with-dotfiles")
+            .action(ArgAction::SetTrue)
+            .help("Include dotfiles (hidden files and directories, and on Windows ones marked hidden or system)"),
+        Arg::new("include_vcs_internals")
+            .long("include-vcs-internals")
+            .action(ArgAction::SetTrue)
+            .help("Walk into .git, .hg and .svn, always skipped otherwise, leaving them to --ignore and the dotfile rule"),
+        Arg::new("keep_dotfile")
+            .long("keep-dotfile")
+            .value_name("GLOB")
+            .action(ArgAction::Append)
+            .help("Keep hidden files or directories matching GLOB while the rest stay out (repeatable): a name like .gitignore at any depth, or a path like .github/workflows from PATH"),
+        Arg::new("sensitive")
+            .long("sensitive")
+            .value_name("GLOB")
+            .action(ArgAction::Append)
+            .help("Also withhold files matching GLOB, like key and credentials files are (repeatable): a name at any depth, or a path with a / from PATH"),
+        Arg::new("allow_sensitive")
+            .long("allow-sensitive")
+            .value_name("GLOB")
+            .num_args(0..=1)
+            .require_equals(true)
+            .default_missing_value("*")
+            .action(ArgAction::Append)
+            .help("Pack the key and credentials files matching GLOB (repeatable), or all of them without one; they're withheld otherwise"),
+        Arg::new("verbose")
+            .short('v')
+            .long("verbose")
+            .action(ArgAction::SetTrue)
+            .help("Print the effective configuration, patterns and other diagnostics to stderr"),
+        Arg::new("config")
+            .long("config")
+            .value_name("FILE")
+            .value_hint(ValueHint::FilePath)
+            .conflicts_with("no_config")
+            .help("Read options from this file instead of owo.toml and the user config"),
+        Arg::new("no_config")
+            .long("no-config")
+            .action(ArgAction::SetTrue)
+            .help("Don't read owo.toml or the user config file"),
+        Arg::new("profile")
+            .long("profile")
+            .value_name("NAME")
+            .action(ArgAction::Append)
+            .value_parser(clap::builder::PossibleValuesParser::new(owo::profiles::names()))
+            .help("Add the ignore patterns of an ecosystem profile (repeatable, replaces detection)"),
+        Arg::new("no_auto_profile")
+            .long("no-auto-profile")
+            .action(ArgAction::SetTrue)
+            .help("Don't apply profiles detected from marker files (Cargo.toml, package.json, ...)"),
+        Arg::new("no_tests")
+            .long("no-tests")
+            .action(ArgAction::SetTrue)
+            .overrides_with("tests_only")
+            .help("Leave out test files and directories (tests/, __tests__/, *_test.go, *.spec.ts, ...)"),
+        Arg::new("tests_only")
+            .long("tests-only")
+            .action(ArgAction::SetTrue)
+            .overrides_with("no_tests")
+            .help("Keep only test files and directories"),
+        Arg::new("owner")
+            .long("owner")
+            .value_name("OWNER")
+            .action(ArgAction::Append)
+            .help("Keep only files CODEOWNERS assigns to this owner, like @org/team (repeatable)"),
+        Arg::new("owner_unowned")
+            .long("owner-unowned")
+            .action(ArgAction::SetTrue)
+            .help("Also keep files without a CODEOWNERS entry; on its own, keep only those"),
+        Arg::new("quiet")
+            .short('q')
+            .long("quiet")
+            .action(ArgAction::SetTrue)
+            .help("Print only warnings and errors, no summary"),
+        Arg::new("color")
+            .long("color")
+            .value_name("WHEN")
+            .value_parser(["auto", "always", "never"])
+            .default_value("auto")
+            .help("Color the status output on stderr: auto (a terminal without NO_COLOR), always or never"),
+        Arg::new("strict")
+            .long("strict")
+            .action(ArgAction::SetTrue)
+            .help("Exit with status 3, writing no output, if any file or directory can't be read, a filter fails or a file changes while read"),
+        Arg::new("read_timeout")
+            .long("read-timeout")
+            .value_name("SECS")
+            .value_parser(clap::value_parser!(u64).range(1..))
+            .help("Count a file that takes longer than SECS to read as unreadable, instead of waiting on it [default: 60 with --strict]"),
+        Arg::new("jobs")
+            .short('j')
+            .long("jobs")
+            .value_name("N")
+            .value_parser(clap::value_parser!(u64).range(1..))
+            .help("Read up to N files at once [default: twice the CPU count]"),
+        Arg::new("walk_threads")
+            .long("walk-threads")
+            .value_name("N")
+            .value_parser(clap::value_parser!(u64).range(1..))
+            .help("List directories on N threads while walking [default: the CPU count, up to 12]"),
+        Arg::new("sequential")
+            .long("sequential")
+            .action(ArgAction::SetTrue)
+            .conflicts_with_all(["jobs", "walk_threads"])
+            .help("Read one file at a time, in walk order, and walk on one thread, for network mounts and spinning disks; same as --jobs 1 --walk-threads 1"),
+        Arg::new("on_change")
+            .long("on-change")
+            .value_name("ACTION")
+            .value_parser(["reread", "mark"])
+            .default_value("reread")
+            .help("When a file changes between the walk and the read, or while it's read: read it once more, marking it only if it changes again, or just mark its section. --strict makes a marked file a failure"),
+        Arg::new("strict_patterns")
+            .long("strict-patterns")
+            .action(ArgAction::SetTrue)
+            .help("Exit with status 2, writing no output, if an --ignore pattern or --only glob matches nothing"),
+        Arg::new("skip_lockfiles")
+            .long("skip-lockfiles")
+            .action(ArgAction::SetTrue)
+            .overrides_with("include_lockfiles")
+            .hide(true)
+            .help("Skip lockfiles (the default)"),
+        Arg::new("include_lockfiles")
+            .long("include-lockfiles")
+            .action(ArgAction::SetTrue)
+            .overrides_with("skip_lockfiles")
+            .help("Include lockfiles (Cargo.lock, package-lock.json, ...), skipped by default"),
+        Arg::new("directory")
+            .value_name("PATH")
+            .value_hint(ValueHint::AnyPath)
+            .help("Directory to traverse, or a .zip, .tar, .tar.gz or .tar.zst archive to read in place")
+            .default_value("."),
+    ]
+}
+
+/// The walk and filter options parsed from [`common_args`].
+pub struct CommonArgs {
+    pub ignore: String,
+    pub with_dotfiles: bool,
+    pub keep_dotfiles: Vec<String>,
+    pub sensitive: Vec<String>,
+    pub allow_sensitive: Vec<String>,
+    pub include_vcs_internals: bool,
+    pub verbose: bool,
+    pub quiet: bool,
+    pub profiles: Vec<String>,
+    pub auto_profile: bool,
+    pub skip_lockfiles: bool,
+    pub strict: bool,
+    pub on_change: owo::OnChange,
+    pub jobs: Option<usize>,
+    pub walk_threads: Option<usize>,
+    pub read_timeout: Option<Duration>,
+    pub tests: owo::TestFiles,
+    pub owners: Vec<String>,
+    pub owner_unowned: bool,
+    pub strict_patterns: bool,
+    pub directory: String,
+}
+
+impl CommonArgs {
+    pub fn from_matches(matches: &ArgMatches) -> Self {
+        Self {
+            ignore: matches.get_one::<String>("ignore").unwrap().clone(),
+            with_dotfiles: matches.get_flag("with_dotfiles"),
+            keep_dotfiles: strings(matches, "keep_dotfile"),
+            sensitive: strings(matches, "sensitive"),
+            allow_sensitive: strings(matches, "allow_sensitive"),
+            include_vcs_internals: matches.get_flag("include_vcs_internals"),
+            verbose: matches.get_flag("verbose"),
+            quiet: matches.get_flag("quiet"),
+            profiles: strings(matches, "profile"),
+            auto_profile: !matches.get_flag("no_auto_profile"),
+            skip_lockfiles: !matches.get_flag("include_lockfiles"),
+            strict: matches.get_flag("strict"),
+            on_change: match matches.get_one::<String>("on_change").unwrap().as_str() {
+                "mark" => owo::OnChange::Mark,
+                _ => owo::OnChange::Reread,
+            },
+            jobs: match matches.get_flag("sequential") {
+                true => Some(1),
+                false => matches.get_one::<u64>("jobs").map(|&jobs| jobs as usize),
+            },
+            walk_threads: match matches.get_flag("sequential") {
+                true => Some(1),
+                false => matches.get_one::<u64>("walk_threads").map(|&threads| threads as usize),
+            },
+            read_timeout: matches
+                .get_one::<u64>("read_timeout")
+                .copied()
+                .or(matches.get_flag("strict").then_some(60))
+                .map(Duration::from_secs),
+            tests: match (matches.get_flag("no_tests"), matches.get_flag("tests_only")) {
+                (true, _) => owo::TestFiles::Skip,
+                (_, true) => owo::TestFiles::Only,
+                _ => owo::TestFiles::Include,
+            },
+            owners: strings(matches, "owner"),
+            owner_unowned: matches.get_flag("owner_unowned"),
+            strict_patterns: matches.get_flag("strict_patterns"),
+            directory: matches.get_one::<String>("directory").unwrap().clone(),
+        }
+    }
+
+    /// Library options for walking with these arguments.
+    pub fn options(&self) -> Options {
+        let options = Options::new(&self.directory);
+        // Left unset when unchanged, so explanations can tell the default apart.
+        let options = match self.ignore.as_str() {
+            owo::DEFAULT_IGNORE => options,
+            ignore => options.ignore(ignore.split('|')),
+        };
+        options
+            .with_dotfiles(self.with_dotfiles)
+            .keep_dotfiles(self.keep_dotfiles.iter().cloned())
+            .sensitive(self.sensitive.iter().cloned())
+            .allow_sensitive(self.allow_sensitive.iter().cloned())
+            .include_vcs_internals(self.include_vcs_internals)
+            .profiles(self.profiles.iter().cloned())
+            .auto_profile(self.auto_profile)
+            .include_lockfiles(!self.skip_lockfiles)
+            .tests(self.tests)
+            .owners(self.owners.clone())
+            .include_unowned(self.owner_unowned)
+            .on_change(self.on_change)
+            .jobs(self.jobs)
+            .walk_threads(self.walk_threads)
+            .read_timeout(self.read_timeout)
+    }
+}
+
+/// Parses a size like `1024`, `500KB`, `1.5 GB` or `2MiB`. Units count in
+/// 1024s, as sizes are shown.
+fn parse_size(size: &str) -> Result<u64, String> {
+    let size = size.trim();
+    let split = size.find(|c: char| !c.is_ascii_digit() && c != '.').unwrap_or(size.len());
+    let (number, unit) = size.split_at(split);
+    let number = number.parse::<f64>().map_err(|_| format!("'{}' isn't a size, like 500KB or 1.5GB", size))?;
+    let scale: u64 = match unit.trim().to_ascii_lowercase().as_str() {
+        "" | "b" => 1,
+        "k" | "kb" | "kib" => 1 << 10,
+        "m" | "mb" | "mib" => 1 << 20,
+        "g" | "gb" | "gib" => 1 << 30,
+        "t" | "tb" | "tib" => 1 << 40,
+        unit => return Err(format!("unknown unit '{}'; use B, KB, MB, GB or TB", unit)),
+    };
+    Ok((number * scale as f64) as u64)
+}
+
+/// What an output file holds.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum OutputFormat {
+    Markdown,
+    Json,
+    /// A SQLite database that every run is appended to.
+    Sqlite,
+}
+
+/// A `--output` value, `FILE[:FORMAT]`.
+#[derive(Debug, Clone)]
+pub struct OutputTarget {
+    pub path: String,
+    pub format: OutputFormat,
+}
+
+impl OutputTarget {
+    /// Parses `FILE[:FORMAT]`, inferring the format from the extension when
+    /// it's omitted. A suffix that looks like a path, like the `\x` of
+    /// `C:\x`, is part of the file name.
+    fn parse(spec: &str) -> Result<Self, String> {
+        let format = |name: &str| match name {
+            "markdown" | "md" => Some(OutputFormat::Markdown),
+            "json" => Some(OutputFormat::Json),
+            "sqlite" | "db" | "sqlite3" => Some(OutputFormat::Sqlite),
+            _ => None,
+        };
+        if let Some((path, suffix)) = spec.rsplit_once(':')
+            && !path.is_empty()
+            && !suffix.is_empty()
+            && suffix.chars().all(|c| c.is_ascii_alphanumeric())
+        {
+            let format = format(suffix).ok_or_else(|| format!("unknown format '{}' (expected markdown, json or sqlite)", suffix))?;
+            return Ok(Self { path: path.to_string(), format });
+        }
+        let extension = std::path::Path::new(spec).extension().and_then(|e| e.to_str()).unwrap_or("");
+        let format = format(&extension.to_ascii_lowercase()).unwrap_or(OutputFormat::Markdown);
+        Ok(Self { path: spec.to_string(), format })
+    }
+}
+
+/// All values of a repeatable option, empty when it wasn't given.
+pub fn strings(matches: &ArgMatches, id: &str) -> Vec<String> {
+    matches
+        .get_many::<String>(id)
+        .map(|values| values.cloned().collect())
+        .unwrap_or_default()
+}
+
+pub fn pack_command() -> Command {
+    Command::new("pack")
+        .about("Write file contents to a single markdown file (the default command)")
+        .args(common_args())
+        .arg(
+            Arg::new("output")
+                .short('o')
+                .long("output")
+                .value_name("FILE[:FORMAT]")
+                .value_hint(ValueHint::FilePath)
+                .action(ArgAction::Append)
+                .value_parser(OutputTarget::parse)
+                .help("Output file (repeatable); FORMAT is markdown, json or sqlite, inferred from the extension when omitted")
+                .required(true)
+        )
+        .arg(
+            Arg::new("split_by_dir")
+                .long("split-by-dir")
+                .value_name("DEPTH")
+                .num_args(0..=1)
+                .require_equals(true)
+                .default_missing_value("1")
+                .value_parser(clap::value_parser!(u64).range(1..))
+                .help("Write one bundle per directory DEPTH levels below PATH, to each --output with {dir} replaced, plus an index [default: 1]")
+        )
+        .arg(
+            Arg::new("max_output_size")
+                .long("max-output-size")
+                .value_name("SIZE")
+                .value_parser(parse_size)
+                .help("Refuse to write a Markdown or JSON output, or with --split-by-dir any one bundle, larger than SIZE, like 500KB or 1.5GB")
+        )
+        .arg(
+            Arg::new("on_oversize")
+                .long("on-oversize")
+                .value_name("ACTION")
+                .value_parser(["error", "truncate"])
+                .default_value("error")
+                .requires("max_output_size")
+                .help("Over --max-output-size, fail before writing anything, or keep the files that fit and note how many were omitted")
+        )
+        .arg(
+            Arg::new("db_prune")
+                .long("db-prune")
+                .value_name("N")
+                .value_parser(clap::value_parser!(u64).range(1..))
+                .help("With a sqlite output, keep only the latest N runs in the database")
+        )
+        .arg(
+            Arg::new("timings")
+                .long("timings")
+                .action(ArgAction::SetTrue)
+                .conflicts_with_all(["list", "check_patterns"])
+                .help("Report how long the walk, reads, rendering and writing took, with read percentiles and the slowest files")
+        )
+        .arg(
+            Arg::new("timings_json")
+                .long("timings-json")
+                .value_name("FILE")
+                .value_hint(ValueHint::FilePath)
+                .conflicts_with_all(["list", "check_patterns"])
+                .help("Write the --timings report to this file as JSON")
+        )
+        .arg(
+            Arg::new("list")
+                .long("list")
+                .action(ArgAction::SetTrue)
+                .help("Print the files that would be packed, one per line, without reading them (sizes with -v)")
+        )
+        .arg(
+            Arg::new("check_patterns")
+                .long("check-patterns")
+                .action(ArgAction::SetTrue)
+                .conflicts_with("list")
+                .help("Validate the ignore patterns and print each with its source and the glob it matches as, without reading or writing anything")
+        )
+        .arg(
+            Arg::new("print0")
+                .long("print0")
+                .action(ArgAction::SetTrue)
+                .requires("list")
+                .help("With --list, end each path with a NUL byte instead of a newline, for xargs -0")
+        )
+        .arg(
+            Arg::new("json")
+                .long("json")
+                .action(ArgAction::SetTrue)
+                .requires("list")
+                .conflicts_with("print0")
+                .help("With --list, print a JSON array of objects with each file's path, size and language")
+        )
+        .arg(
+            Arg::new("only")
+                .long("only")
+                .value_name("GLOB")
+                .action(ArgAction::Append)
+                .help("Pack only files whose path relative to PATH matches this glob (repeatable); FILE:120-240 keeps only those lines")
+        )
+        .arg(
+            Arg::new("from_patch")
+                .long("from-patch")
+                .value_name("FILE")
+                .value_hint(ValueHint::FilePath)
+                .conflicts_with("interactive")
+                .help("Pack the whole of every file this unified diff touches, read from FILE or - for stdin, or of those --only also matches; paths it names that aren't in PATH are listed")
+        )
+        .arg(
+            Arg::new("grep")
+                .long("grep")
+                .value_name("REGEX")
+                .action(ArgAction::Append)
+                .conflicts_with("list")
+                .help("Pack only files whose content matches this regex (repeatable, any may match)")
+        )
+        .arg(
+            Arg::new("grep_context")
+                .long("grep-context")
+                .value_name("N")
+                .requires("grep")
+                .value_parser(clap::value_parser!(usize))
+                .help("With --grep, render only matching lines and N lines around each")
+        )
+        .arg(
+            Arg::new("grep_exclude")
+                .long("grep-exclude")
+                .value_name("REGEX")
+                .action(ArgAction::Append)
+                .conflicts_with("list")
+                .help("Skip files whose first 64 KiB match this regex, even if --grep matches (repeatable)")
+        )
+        .arg(
+            Arg::new("grep_exclude_full")
+                .long("grep-exclude-full")
+                .action(ArgAction::SetTrue)
+                .requires("grep_exclude")
+                .help("Search whole text files for --grep-exclude patterns, not just the first 64 KiB")
+        )
+        .arg(
+            Arg::new("interactive")
+                .long("interactive")
+                .action(ArgAction::SetTrue)
+                .conflicts_with("list")
+                .help("Pick the files to pack from a checklist; prints the selection as --only flags")
+        )
+        .arg(
+            Arg::new("remote")
+                .long("remote")
+                .value_name("URL")
+                .value_hint(ValueHint::Url)
+                .help("Pack a shallow clone of this git repository instead of PATH; a URL given as PATH does the same")
+        )
+        .arg(
+            Arg::new("rev")
+                .long("rev")
+                .value_name("REF")
+                .help("With a git URL, the branch or tag to clone instead of the default branch")
+        )
+        .arg(
+            Arg::new("redact")
+                .long("redact")
+                .action(ArgAction::SetTrue)
+                .help("Replace secrets (keys, tokens, passwords) with redaction markers")
+        )
+        .arg(
+            Arg::new("redact_pattern")
+                .long("redact-pattern")
+                .value_name("REGEX")
+                .action(ArgAction::Append)
+                .help("Additional secret pattern to redact (repeatable, implies --redact)")
+        )
+        .arg(
+            Arg::new("redact_entropy")
+                .long("redact-entropy")
+                .action(ArgAction::SetTrue)
+                .help("Also redact high-entropy strings (implies --redact)")
+        )
+        .arg(
+            Arg::new("fail_on_secret")
+                .long("fail-on-secret")
+                .action(ArgAction::SetTrue)
+                .help("Exit with an error, writing no output, if any secret is found")
+        )
+        .arg(
+            Arg::new("secret_allow")
+                .long("secret-allow")
+                .value_name("REGEX")
+                .action(ArgAction::Append)
+                .help("Ignore secrets (or whole files, by path) matching this pattern (repeatable)")
+        )
+        .arg(
+            Arg::new("secret_report")
+                .long("secret-report")
+                .value_name("FILE")
+                .value_hint(ValueHint::FilePath)
+                .help("Write all secret findings to this file as JSON")
+        )
+        .arg(
+            Arg::new("strip_comments")
+                .long("strip-comments")
+                .action(ArgAction::SetTrue)
+                .help("Remove comments from languages with known comment syntax")
+        )
+        .arg(
+            Arg::new("keep_doc_comments")
+                .long("keep-doc-comments")
+                .action(ArgAction::SetTrue)
+                .help("Keep doc comments (///, //!, /** */) when stripping comments")
+        )
+        .arg(
+            Arg::new("skip_generated")
+                .long("skip-generated")
+                .action(ArgAction::SetTrue)
+                .help("Skip minified and generated files (by suffix, marker or density)")
+        )
+        .arg(
+            Arg::new("include_generated")
+                .long("include-generated")
+                .action(ArgAction::SetTrue)
+                .help("Include generated files even when --skip-generated is set")
+        )
+        .arg(
+            Arg::new("lockfile_summary")
+                .long("lockfile-summary")
+                .action(ArgAction::SetTrue)
+                .help("List only the dependency names recorded in each lockfile")
+        )
+        .arg(
+            Arg::new("strip_license_headers")
+                .long("strip-license-headers")
+                .action(ArgAction::SetTrue)
+                .help("Replace a leading license comment with a one-line placeholder")
+        )
+        .arg(
+            Arg::new("squeeze_blank_lines")
+                .long("squeeze-blank-lines")
+                .value_name("N")
+                .num_args(0..=1)
+                .require_equals(true)
+                .default_missing_value("1")
+                .value_parser(clap::value_parser!(usize))
+                .help("Collapse runs of more than N blank lines down to N [default: 1]")
+        )
+        .arg(
+            Arg::new("trim_trailing_whitespace")
+                .long("trim-trailing-whitespace")
+                .action(ArgAction::SetTrue)
+                .help("Remove trailing spaces and tabs from every line")
+        )
+        .arg(
+            Arg::new("strip_ansi")
+                .long("strip-ansi")
+                .action(ArgAction::SetTrue)
+                .help("Remove ANSI escape sequences, like colors and window titles, from text files")
+        )
+        .arg(
+            Arg::new("wrap")
+                .long("wrap")
+                .value_name("COLS")
+                .value_parser(clap::value_parser!(u64).range(1..))
+                .help("Wrap lines wider than COLS display columns, at whitespace where possible, ending each wrapped segment with --wrap-marker")
+        )
+        .arg(
+            Arg::new("wrap_marker")
+                .long("wrap-marker")
+                .value_name("TEXT")
+                .default_value("↩")
+                .requires("wrap")
+                .help("What ends each segment of a wrapped line, like ' \\'")
+        )
+        .arg(
+            Arg::new("expand_tabs")
+                .long("expand-tabs")
+                .value_name("WIDTH")
+                .num_args(0..=1)
+                .require_equals(true)
+                .default_missing_value("4")
+                .value_parser(clap::value_parser!(usize))
+                .help("Expand leading tabs to spaces, except in Makefiles [default: 4]")
+        )
+        .arg(
+            Arg::new("lossy")
+                .long("lossy")
+                .action(ArgAction::SetTrue)
+                .help("Pack files that are almost UTF-8 (no NUL bytes, at most one bad sequence per 16 bytes) as text, bad sequences shown as \u{FFFD}, instead of as binary")
+        )
+        .arg(
+            Arg::new("dedent")
+                .long("dedent")
+                .action(ArgAction::SetTrue)
+                .help("Strip the longest common leading whitespace from each file")
+        )
+        .arg(
+            Arg::new("filter_cmd")
+                .long("filter-cmd")
+                .value_name("GLOB:COMMAND")
+                .action(ArgAction::Append)
+                .help("Pipe files matching GLOB through COMMAND and pack its stdout instead (repeatable)")
+        )
+        .arg(
+            Arg::new("filter_timeout")
+                .long("filter-timeout")
+                .value_name("SECS")
+                .value_parser(clap::value_parser!(u64))
+                .default_value("10")
+                .help("Kill a filter command after this many seconds and keep the original content")
+        )
+        .arg(
+            Arg::new("filter_max_output")
+                .long("filter-max-output")
+                .value_name("BYTES")
+                .value_parser(clap::value_parser!(usize))
+                .default_value("16777216")
+                .help("Reject filter output larger than this and keep the original content")
+        )
+        .arg(
+            Arg::new("header_file")
+                .long("header-file")
+                .value_name("FILE")
+                .value_hint(ValueHint::FilePath)
+                .conflicts_with("header_text")
+                .help("Place this file's contents verbatim before the first file")
+        )
+        .arg(
+            Arg::new("header_text")
+                .long("header-text")
+                .value_name("TEXT")
+                .help("Place this text verbatim before the first file")
+        )
+        .arg(
+            Arg::new("footer_file")
+                .long("footer-file")
+                .value_name("FILE")
+                .value_hint(ValueHint::FilePath)
+                .conflicts_with("footer_text")
+                .help("Place this file's contents verbatim after the last file")
+        )
+        .arg(
+            Arg::new("footer_text")
+                .long("footer-text")
+                .value_name("TEXT")
+                .help("Place this text verbatim after the last file")
+        )
+        .arg(
+            Arg::new("collapsible")
+                .long("collapsible")
+                .value_name("MODE")
+                .num_args(0..=1)
+                .require_equals(true)
+                .default_missing_value("heading")
+                .value_parser(["heading", "summary"])
+                .help("Wrap each file in a <details> block, below its heading or with the summary line replacing it [default: heading]")
+        )
+        .arg(
+            Arg::new("group_by")
+                .long("group-by")
+                .value_name("GROUPING")
+                .value_parser(["none", "dir", "language"])
+                .default_value("none")
+                .help("Put the files under a top-level heading per directory or per language (fence tag), sorted by path within each; files without a language go under Other at the end")
+        )
+        .arg(
+            Arg::new("permalinks")
+                .long("permalinks")
+                .action(ArgAction::SetTrue)
+                .conflicts_with_all(["list", "check_patterns"])
+                .help("Link each file's heading to the file at the HEAD commit on the GitHub, GitLab or Bitbucket repository origin points at")
+        )
+        .arg(
+            Arg::new("permalink_base")
+                .long("permalink-base")
+                .value_name("URL")
+                .value_hint(ValueHint::Url)
+                .conflicts_with_all(["list", "check_patterns"])
+                .help("Link to the repository at this URL instead of origin's, like https://git.example.com/org/repo for a self-hosted forge; implies --permalinks")
+        )
+        .after_help(after_help())
+        .mut_args(with_env)
+}
+
+pub fn tree_command() -> Command {
+    Command::new("tree")
+        .about("Print the directory tree a pack would cover, without reading any file")
+        .args(common_args())
+        .arg(
+            Arg::new("tree_output")
+                .short('o')
+                .long("output")
+                .value_name("FILE")
+                .value_hint(ValueHint::FilePath)
+                .help("Write the tree to this file instead of stdout")
+        )
+        .arg(
+            Arg::new("sizes")
+                .long("sizes")
+                .action(ArgAction::SetTrue)
+                .help("Annotate files with their size on disk, and directories with their file count and total size")
+        )
+        .arg(
+            Arg::new("tree_annotations")
+                .long("tree-annotations")
+                .value_name("COLUMNS")
+                .value_parser(["size", "tokens", "lines"])
+                .value_delimiter(',')
+                .action(ArgAction::Append)
+                .conflicts_with("sizes")
+                .help("Read the files as a pack would and annotate each with these columns of what it holds as packed, directories with their totals")
+        )
+        .arg(
+            Arg::new("bytes")
+                .long("bytes")
+                .action(ArgAction::SetTrue)
+                .help("Show sizes as exact byte counts instead of KB and MB")
+        )
+        .arg(
+            Arg::new("tree_format")
+                .long("tree-format")
+                .value_name("FORMAT")
+                .value_parser(["text", "mermaid"])
+                .default_value("text")
+                .help("Render the tree as text, or as a Mermaid graph for GitHub and Obsidian")
+        )
+        .arg(
+            Arg::new("max_nodes")
+                .long("max-nodes")
+                .value_name("N")
+                .value_parser(clap::value_parser!(usize))
+                .default_value("500")
+                .help("Cap on the nodes of a Mermaid graph; the rest is summarized with a warning")
+        )
+        .mut_args(with_env)
+}
+
+pub fn stats_command() -> Command {
+    Command::new("stats")
+        .about("Count files, lines and bytes per language, without writing a bundle")
+        .args(common_args())
+        .arg(
+            Arg::new("only")
+                .long("only")
+                .value_name("GLOB")
+                .action(ArgAction::Append)
+                .help("Count only files whose path relative to PATH matches this glob (repeatable)")
+        )
+        .arg(
+            Arg::new("skip_generated")
+                .long("skip-generated")
+                .action(ArgAction::SetTrue)
+                .help("Skip minified and generated files (by suffix, marker or density)")
+        )
+        .arg(
+            Arg::new("stats_format")
+                .long("format")
+                .value_name("FORMAT")
+                .value_parser(["table", "json", "csv"])
+                .default_value("table")
+                .help("Print an aligned table, JSON or CSV")
+        )
+        .mut_args(with_env)
+}
+
+pub fn explain_command() -> Command {
+    Command::new("explain")
+        .about("Explain why a path would be included in a pack or left out")
+        .args(common_args())
+        .arg(
+            Arg::new("target")
+                .index(1)
+                .required(true)
+                .value_name("FILE")
+                .value_hint(ValueHint::AnyPath)
+                .help("File or directory to explain, relative to the current directory")
+        )
+        .mut_arg("directory", |arg| arg.index(2))
+        .arg(
+            Arg::new("only")
+                .long("only")
+                .value_name("GLOB")
+                .action(ArgAction::Append)
+                .help("Explain as if only files matching this glob were packed (repeatable)")
+        )
+        .arg(
+            Arg::new("skip_generated")
+                .long("skip-generated")
+                .action(ArgAction::SetTrue)
+                .help("Explain as if minified and generated files were skipped")
+        )
+        .arg(
+            Arg::new("lockfile_summary")
+                .long("lockfile-summary")
+                .action(ArgAction::SetTrue)
+                .help("Explain as if lockfiles were summarized")
+        )
+        .arg(
+            Arg::new("json")
+                .long("json")
+                .action(ArgAction::SetTrue)
+                .help("Print the decision chain as JSON")
+        )
+        .mut_args(with_env)
+}
+
+pub fn diff_command() -> Command {
+    Command::new("diff")
+        .about("Show which files changed between two bundles")
+        .arg(
+            Arg::new("old")
+                .required(true)
+                .value_name("OLD")
+                .value_hint(ValueHint::FilePath)
+                .help("The earlier bundle, Markdown or JSON")
+        )
+        .arg(
+            Arg::new("new")
+                .required(true)
+                .value_name("NEW")
+                .value_hint(ValueHint::FilePath)
+                .help("The later bundle, Markdown or JSON")
+        )
+        .arg(
+            Arg::new("stat")
+                .long("stat")
+                .action(ArgAction::SetTrue)
+                .help("Show lines added and removed per modified file")
+        )
+        .arg(
+            Arg::new("content")
+                .long("content")
+                .action(ArgAction::SetTrue)
+                .help("Show a unified diff of each modified file")
+        )
+        .arg(
+            Arg::new("json")
+                .long("json")
+                .action(ArgAction::SetTrue)
+                .help("Print the differences as JSON")
+        )
+        .arg(
+            Arg::new("color")
+                .long("color")
+                .value_name("WHEN")
+                .value_parser(["auto", "always", "never"])
+                .default_value("auto")
+                .help("Color the output: auto (a terminal without NO_COLOR), always or never")
+        )
+}
+
+pub fn merge_command() -> Command {
+    Command::new("merge")
+        .about("Combine several bundles into one")
+        .arg(
+            Arg::new("inputs")
+                .required(true)
+                .num_args(2..)
+                .value_name("BUNDLE")
+                .value_hint(ValueHint::FilePath)
+                .help("The bundles to combine, in order; all Markdown or all JSON")
+        )
+        .arg(
+            Arg::new("output")
+                .short('o')
+                .long("output")
+                .required(true)
+                .value_name("FILE")
+                .value_hint(ValueHint::FilePath)
+                .help("The file where the combined bundle is saved")
+        )
+        .arg(
+            Arg::new("on_conflict")
+                .long("on-conflict")
+                .value_name("WHICH")
+                .value_parser(["error", "first", "last"])
+                .default_value("last")
+                .help("When bundles share a path: fail, or keep the first or the last section")
+        )
+        .arg(
+            Arg::new("provenance")
+                .long("provenance")
+                .action(ArgAction::SetTrue)
+                .help("Note the input each file came from, as an HTML comment or a JSON source field")
+        )
+}
+
+pub fn init_command() -> Command {
+    Command::new("init")
+        .about("Write a commented owo.toml, and an .owoignore with the detected profiles' patterns, for a project")
+        .arg(
+            Arg::new("yes")
+                .short('y')
+                .long("yes")
+                .action(ArgAction::SetTrue)
+                .help("Take the default answer to every question instead of asking, as when stdin isn't a terminal")
+        )
+        .arg(
+            Arg::new("force")
+                .long("force")
+                .action(ArgAction::SetTrue)
+                .help("Overwrite an existing owo.toml or .owoignore")
+        )
+        .arg(
+            Arg::new("directory")
+                .value_name("PATH")
+                .value_hint(ValueHint::DirPath)
+                .default_value(".")
+                .help("The project directory to set up")
+        )
+}
+
+pub fn cli() -> Command {
+    Command::new("owo")
+        .version("0.1.0")
+        .author("xOphiuchus")
+        .about("Like tree but outputs file contents to a single markdown file")
+        .long_about(
+            "Walks a directory, honoring .gitignore and owo's own ignore patterns, and writes \
+             every file's contents into a single Markdown document, one fenced code block per \
+             file. Useful for handing a whole codebase to an LLM as context.",
+        )
+        .override_usage("owo [pack] [OPTIONS] [PATH]\n       owo <COMMAND>")
+        .arg(
+            Arg::new("generate_man")
+                .long("generate-man")
+                .action(ArgAction::SetTrue)
+                .hide(true)
+                .help("Print the owo(1) man page in roff format")
+        )
+        .subcommand(pack_command())
+        .subcommand(tree_command())
+        .subcommand(stats_command())
+        .subcommand(explain_command())
+        .subcommand(diff_command())
+        .subcommand(merge_command())
+        .subcommand(init_command())
+        .subcommand(
+            Command::new("completions")
+                .about("Print a shell completion script")
+                .arg(
+                    Arg::new("shell")
+                        .required(true)
+                        .value_parser(clap::value_parser!(clap_complete::Shell))
+                        .help("Shell to generate completions for")
+                )
+        )
+        .disable_help_subcommand(true)
+        .after_help(after_help())
+}
+
+/// Inserts the default `pack` subcommand when the first argument isn't a
+/// subcommand name or a root flag.
+pub fn normalize_args(mut args: Vec<String>) -> Vec<String> {
+    let routes_to_pack = args.get(1).is_some_and(|first| {
+        !SUBCOMMANDS.contains(&first.as_str()) && !ROOT_FLAGS.contains(&first.as_str())
+    });
+    if routes_to_pack {
+        args.insert(1, "pack".to_string());
+    }
+    args
+}
+
+/// Gives every option an `OWO_<NAME>` environment variable, e.g. `OWO_IGNORE`
+/// or `OWO_WITH_DOTFILES`. Flags accept `1`/`true`/`yes` and `0`/`false`/`no`.
+fn with_env(arg: Arg) -> Arg {
+    if arg.is_positional() || arg.is_hide_set() {
+        return arg;
+    }
+    let name = format!("OWO_{}", arg.get_id().as_str().to_ascii_uppercase());
+    let arg = arg.env(name);
+    if matches!(arg.get_action(), ArgAction::SetTrue) {
+        arg.value_parser(clap::builder::BoolishValueParser::new())
+    } else {
+        arg
+    }
+}
@@ -0,0 +1,213 @@
+//! GitHub `CODEOWNERS` files: which owners a path resolves to.
+//!
+//! The patterns look like gitignore patterns but don't behave like them:
+//! there's no `!` negation or `[...]` class, a trailing `*` matches only the
+//! files directly in a directory, and only the last matching line counts.
+
+use crate::{Error, Result};
+use globset::{GlobBuilder, GlobSet, GlobSetBuilder};
+use std::path::{Path, PathBuf};
+
+/// Where GitHub looks for the file, in order, relative to the repository root.
+pub const CODEOWNERS_LOCATIONS: &[&str] = &[".github/CODEOWNERS", "CODEOWNERS", "docs/CODEOWNERS"];
+
+/// One line of a `CODEOWNERS` file.
+struct Rule {
+    matcher: GlobSet,
+    /// Empty for a pattern listed without owners, which leaves its paths
+    /// unowned.
+    owners: Vec<String>,
+}
+
+/// A parsed `CODEOWNERS` file.
+pub struct CodeOwners {
+    /// The file it was read from.
+    pub path: PathBuf,
+    /// The walked root, relative to the directory the patterns are
+    /// relative to.
+    prefix: PathBuf,
+    rules: Vec<Rule>,
+    /// Lines GitHub would reject, with their line numbers. They are skipped.
+    pub invalid: Vec<(usize, String)>,
+}
+
+impl CodeOwners {
+    /// Finds and parses the `CODEOWNERS` file for `root`: the first of
+    /// [`CODEOWNERS_LOCATIONS`] in `root` or the nearest parent that has one.
+    pub fn find(root: &Path) -> Result<Self> {
+        let root = root.canonicalize().map_err(|_| Error::RootNotFound(root.to_path_buf()))?;
+        let (base, path) = root
+            .ancestors()
+            .find_map(|dir| {
+                CODEOWNERS_LOCATIONS
+                    .iter()
+                    .map(|location| dir.join(location))
+                    .find(|path| path.is_file())
+                    .map(|path| (dir.to_path_buf(), path))
+            })
+            .ok_or_else(|| Error::NoCodeOwners(root.clone()))?;
+        let text = std::fs::read_to_string(&path).map_err(|source| Error::Read { path: path.clone(), source })?;
+        let prefix = root.strip_prefix(&base).unwrap_or(Path::new("")).to_path_buf();
+        Ok(Self::parse(path, prefix, &text))
+    }
+
+    /// Parses the `text` of the file at `path`, for a root at `prefix` below
+    /// the directory the patterns are relative to.
+    fn parse(path: PathBuf, prefix: PathBuf, text: &str) -> Self {
+        let mut rules = Vec::new();
+        let mut invalid = Vec::new();
+        for (index, line) in text.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            // An unescaped `#` starts a comment anywhere on the line.
+            let line = match line.find(" #") {
+                Some(end) => &line[..end],
+                None => line,
+            };
+            let mut fields = line.split_whitespace();
+            let pattern = fields.next().unwrap_or_default();
+            match matcher(pattern) {
+                Some(matcher) => rules.push(Rule { matcher, owners: fields.map(str::to_string).collect() }),
+                None => invalid.push((index + 1, pattern.to_string())),
+            }
+        }
+        Self { path, prefix, rules, invalid }
+    }
+
+    /// The owners of the file at `rel`, relative to the walked root: those
+    /// of the last line matching it, or none.
+    pub fn owners(&self, rel: &Path) -> &[String] {
+        let path = self.prefix.join(rel);
+        self.rules
+            .iter()
+            .rev()
+            .find(|rule| rule.matcher.is_match(&path))
+            .map_or(&[], |rule| rule.owners.as_slice())
+    }
+
+    /// Whether the file at `rel` is owned by one of `wanted` or, with
+    /// `unowned`, by nobody. Owners compare without case, and a wanted owner
+    /// may leave out the leading `@`.
+    pub fn selects(&self, rel: &Path, wanted: &[String], unowned: bool) -> bool {
+        let owners = self.owners(rel);
+        if owners.is_empty() {
+            return unowned;
+        }
+        owners.iter().any(|owner| {
+            wanted
+                .iter()
+                .any(|wanted| owner.trim_start_matches('@').eq_ignore_ascii_case(wanted.trim_start_matches('@')))
+        })
+    }
+}
+
+/// A glob for one `CODEOWNERS` pattern, matched against paths relative to
+/// the repository root, or `None` for a pattern GitHub doesn't accept.
+fn matcher(pattern: &str) -> Option<GlobSet> {
+    if pattern.starts_with('!') || pattern.contains('[') || pattern.contains(']') {
+        return None;
+    }
+    let pattern = pattern.replace("\\#", "#").replace('{', "[{]").replace('}', "[}]");
+    let dir_only = pattern.ends_with('/');
+    let trimmed = pattern.trim_end_matches('/');
+    // As in gitignore, a slash anywhere but at the end anchors the pattern to
+    // the root; otherwise it matches at any depth.
+    let anchored = trimmed.contains('/');
+    let trimmed = trimmed.trim_start_matches('/');
+    if trimmed.is_empty() {
+        return None;
+    }
+    let glob = match anchored || trimmed.starts_with("**") {
+        true => trimmed.to_string(),
+        false => format!("**/{}", trimmed),
+    };
+    // A directory's owners own everything below it, except that `docs/*`
+    // stops at the files directly in `docs`.
+    let globs = if dir_only {
+        vec![format!("{}/**", glob)]
+    } else if trimmed.ends_with("/*") || trimmed == "*" {
+        vec![glob]
+    } else {
+        vec![format!("{}/**", glob), glob]
+    };
+    let mut set = GlobSetBuilder::new();
+    for glob in globs {
+        set.add(GlobBuilder::new(&glob).literal_separator(true).build().ok()?);
+    }
+    set.build().ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn owners(text: &str, path: &str) -> Vec<String> {
+        CodeOwners::parse(PathBuf::new(), PathBuf::new(), text).owners(Path::new(path)).to_vec()
+    }
+
+    /// The examples from GitHub's CODEOWNERS documentation, one rule set at a
+    /// time: the rules, a path, and the owners it resolves to.
+    #[test]
+    fn github_examples() {
+        let cases: &[(&str, &str, &[&str])] = &[
+            ("*       @global-owner1 @global-owner2", "src/deep/main.rs", &["@global-owner1", "@global-owner2"]),
+            ("* @global-owner1\n*.js    @js-owner #This is an inline comment.", "web/app.js", &["@js-owner"]),
+            ("* @global-owner1\n*.js    @js-owner #This is an inline comment.", "web/app.ts", &["@global-owner1"]),
+            ("*.go docs@example.com", "cmd/main.go", &["docs@example.com"]),
+            ("*.txt @octo-org/octocats", "notes/todo.txt", &["@octo-org/octocats"]),
+            ("/build/logs/ @doctocat", "build/logs/out.log", &["@doctocat"]),
+            ("/build/logs/ @doctocat", "build/logs/2024/out.log", &["@doctocat"]),
+            ("/build/logs/ @doctocat", "src/build/logs/out.log", &[]),
+            ("docs/*  docs@example.com", "docs/getting-started.md", &["docs@example.com"]),
+            ("docs/*  docs@example.com", "docs/build-app/troubleshooting.md", &[]),
+            ("apps/ @octocat", "apps/web/index.js", &["@octocat"]),
+            ("apps/ @octocat", "services/apps/api.go", &["@octocat"]),
+            ("apps/ @octocat", "apps.md", &[]),
+            ("/docs/ @doctocat", "docs/guide/intro.md", &["@doctocat"]),
+            ("/docs/ @doctocat", "site/docs/intro.md", &[]),
+            ("/scripts/ @doctocat @octocat", "scripts/deploy.sh", &["@doctocat", "@octocat"]),
+            ("**/logs @octocat", "build/logs/a.log", &["@octocat"]),
+            ("**/logs @octocat", "deeply/nested/logs/b.log", &["@octocat"]),
+            ("**/logs @octocat", "logs/c.log", &["@octocat"]),
+            ("/apps/ @octocat\n/apps/github", "apps/web/index.js", &["@octocat"]),
+            ("/apps/ @octocat\n/apps/github", "apps/github/index.js", &[]),
+            ("/apps/ @octocat\n/apps/github @doctocat", "apps/github/index.js", &["@doctocat"]),
+        ];
+        for (text, path, expected) in cases {
+            assert_eq!(owners(text, path), *expected, "{} under {:?}", path, text);
+        }
+    }
+
+    #[test]
+    fn last_match_wins_across_the_whole_file() {
+        let text = "# Default owners\n* @global\n\n/build/logs/ @doctocat\n**/logs @octocat\n";
+        assert_eq!(owners(text, "build/logs/out.log"), ["@octocat"]);
+        assert_eq!(owners(text, "README.md"), ["@global"]);
+    }
+
+    #[test]
+    fn rejects_what_github_rejects() {
+        let codeowners = CodeOwners::parse(PathBuf::new(), PathBuf::new(), "!*.js @a\n*.[ch] @b\n*.rs @c\n");
+        assert_eq!(codeowners.invalid, [(1, "!*.js".to_string()), (2, "*.[ch]".to_string())]);
+        assert_eq!(codeowners.owners(Path::new("main.c")), [] as [String; 0]);
+        assert_eq!(codeowners.owners(Path::new("main.rs")), ["@c"]);
+    }
+
+    #[test]
+    fn escaped_hash_and_relative_roots() {
+        assert_eq!(owners("\\#notes.md @a", "#notes.md"), ["@a"]);
+        let codeowners = CodeOwners::parse(PathBuf::new(), PathBuf::from("crates/core"), "/crates/core/ @core\n");
+        assert_eq!(codeowners.owners(Path::new("src/lib.rs")), ["@core"]);
+    }
+
+    #[test]
+    fn selects_owners_without_case_or_at_sign() {
+        let codeowners = CodeOwners::parse(PathBuf::new(), PathBuf::new(), "*.rs @Org/Payments\n/vendor/\n");
+        let wanted = ["org/payments".to_string()];
+        assert!(codeowners.selects(Path::new("src/pay.rs"), &wanted, false));
+        assert!(!codeowners.selects(Path::new("vendor/x.c"), &wanted, false));
+        assert!(codeowners.selects(Path::new("vendor/x.c"), &wanted, true));
+    }
+}
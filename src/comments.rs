@@ -0,0 +1,791 @@
+/// How a language spells a string literal, so comment markers inside one are
+/// left alone.
+struct StringRule {
+    open: &'static str,
+    close: &'static str,
+    escapes: bool,
+    multiline: bool,
+    /// A `'` that only opens a literal when it looks like `'x'` or `'\n'`,
+    /// which keeps Rust lifetimes from being read as strings.
+    char_literal: bool,
+    /// A doubled closing quote stands for the quote itself, as in C#'s
+    /// `@"say ""hi"""`.
+    doubled: bool,
+}
+
+/// The comment and string syntax of one language family.
+pub struct Syntax {
+    line: &'static [&'static str],
+    block: &'static [(&'static str, &'static str)],
+    strings: &'static [StringRule],
+    nested_blocks: bool,
+    raw_strings: bool,
+    /// Line comments and quotes only start after whitespace or at the start of
+    /// a line, as with `#` in shell (`$#`) and quotes in YAML (`don't`).
+    needs_boundary: bool,
+    /// A `/` where an operand goes opens a regex literal, as in JavaScript.
+    regex_literals: bool,
+    /// `<<WORD` starts a heredoc whose body is kept as it is, as in shell.
+    heredocs: bool,
+}
+
+const fn string(open: &'static str, close: &'static str, escapes: bool, multiline: bool) -> StringRule {
+    StringRule {
+        open,
+        close,
+        escapes,
+        multiline,
+        char_literal: false,
+        doubled: false,
+    }
+}
+
+const fn verbatim(open: &'static str) -> StringRule {
+    StringRule {
+        open,
+        close: "\"",
+        escapes: false,
+        multiline: true,
+        char_literal: false,
+        doubled: true,
+    }
+}
+
+const CHAR_LITERAL: StringRule = StringRule {
+    open: "'",
+    close: "'",
+    escapes: true,
+    multiline: false,
+    char_literal: true,
+    doubled: false,
+};
+
+/// A `"` string that ends with its line; escapes cover a line continuation.
+const DOUBLE: StringRule = string("\"", "\"", true, false);
+const DOUBLE_MULTILINE: StringRule = string("\"", "\"", true, true);
+const SINGLE: StringRule = string("'", "'", true, false);
+const SINGLE_RAW: StringRule = string("'", "'", false, true);
+const TRIPLE_DOUBLE: StringRule = string("\"\"\"", "\"\"\"", true, true);
+const TRIPLE_SINGLE: StringRule = string("'''", "'''", true, true);
+const TRIPLE_RAW: StringRule = string("\"\"\"", "\"\"\"", false, true);
+const C_BLOCK: (&str, &str) = ("/*", "*/");
+
+const C_LIKE: Syntax = Syntax {
+    line: &["//"],
+    block: &[C_BLOCK],
+    strings: &[DOUBLE, CHAR_LITERAL],
+    nested_blocks: false,
+    raw_strings: false,
+    needs_boundary: false,
+    regex_literals: false,
+    heredocs: false,
+};
+
+const RUST: Syntax = Syntax {
+    line: &["//"],
+    block: &[C_BLOCK],
+    strings: &[DOUBLE_MULTILINE, CHAR_LITERAL],
+    nested_blocks: true,
+    raw_strings: true,
+    needs_boundary: false,
+    regex_literals: false,
+    heredocs: false,
+};
+
+const GO: Syntax = Syntax {
+    line: &["//"],
+    block: &[C_BLOCK],
+    strings: &[DOUBLE, CHAR_LITERAL, string("`", "`", false, true)],
+    nested_blocks: false,
+    raw_strings: false,
+    needs_boundary: false,
+    regex_literals: false,
+    heredocs: false,
+};
+
+const JVM_MODERN: Syntax = Syntax {
+    line: &["//"],
+    block: &[C_BLOCK],
+    strings: &[TRIPLE_DOUBLE, DOUBLE, CHAR_LITERAL],
+    nested_blocks: true,
+    raw_strings: false,
+    needs_boundary: false,
+    regex_literals: false,
+    heredocs: false,
+};
+
+const CSHARP: Syntax = Syntax {
+    strings: &[TRIPLE_RAW, verbatim("@\""), verbatim("$@\""), verbatim("@$\""), DOUBLE, CHAR_LITERAL],
+    ..C_LIKE
+};
+
+const JAVASCRIPT: Syntax = Syntax {
+    line: &["//"],
+    block: &[C_BLOCK],
+    strings: &[DOUBLE, SINGLE, string("`", "`", true, true)],
+    nested_blocks: false,
+    raw_strings: false,
+    needs_boundary: false,
+    regex_literals: true,
+    heredocs: false,
+};
+
+const CSS: Syntax = Syntax {
+    line: &[],
+    block: &[C_BLOCK],
+    strings: &[DOUBLE, SINGLE],
+    nested_blocks: false,
+    raw_strings: false,
+    needs_boundary: false,
+    regex_literals: false,
+    heredocs: false,
+};
+
+const SCSS: Syntax = Syntax {
+    line: &["//"],
+    ..CSS
+};
+
+const PHP: Syntax = Syntax {
+    line: &["//", "#"],
+    block: &[C_BLOCK],
+    strings: &[DOUBLE_MULTILINE, SINGLE],
+    nested_blocks: false,
+    raw_strings: false,
+    needs_boundary: false,
+    regex_literals: false,
+    heredocs: false,
+};
+
+const PYTHON: Syntax = Syntax {
+    line: &["#"],
+    block: &[],
+    strings: &[TRIPLE_DOUBLE, TRIPLE_SINGLE, DOUBLE, SINGLE],
+    nested_blocks: false,
+    raw_strings: false,
+    needs_boundary: false,
+    regex_literals: false,
+    heredocs: false,
+};
+
+const RUBY: Syntax = Syntax {
+    line: &["#"],
+    block: &[],
+    strings: &[DOUBLE_MULTILINE, SINGLE],
+    nested_blocks: false,
+    raw_strings: false,
+    needs_boundary: false,
+    regex_literals: false,
+    heredocs: false,
+};
+
+const SHELL: Syntax = Syntax {
+    line: &["#"],
+    block: &[],
+    strings: &[DOUBLE_MULTILINE, SINGLE_RAW],
+    nested_blocks: false,
+    raw_strings: false,
+    needs_boundary: true,
+    regex_literals: false,
+    heredocs: true,
+};
+
+const TOML: Syntax = Syntax {
+    line: &["#"],
+    block: &[],
+    strings: &[
+        TRIPLE_DOUBLE,
+        string("'''", "'''", false, true),
+        DOUBLE,
+        SINGLE_RAW,
+    ],
+    nested_blocks: false,
+    raw_strings: false,
+    needs_boundary: false,
+    regex_literals: false,
+    heredocs: false,
+};
+
+const YAML: Syntax = Syntax {
+    line: &["#"],
+    block: &[],
+    strings: &[DOUBLE_MULTILINE, SINGLE_RAW],
+    nested_blocks: false,
+    raw_strings: false,
+    needs_boundary: true,
+    regex_literals: false,
+    heredocs: false,
+};
+
+const MARKUP: Syntax = Syntax {
+    line: &[],
+    block: &[("<!--", "-->")],
+    strings: &[],
+    nested_blocks: false,
+    raw_strings: false,
+    needs_boundary: false,
+    regex_literals: false,
+    heredocs: false,
+};
+
+const SQL: Syntax = Syntax {
+    line: &["--"],
+    block: &[C_BLOCK],
+    strings: &[string("'", "'", false, true), string("\"", "\"", false, true)],
+    nested_blocks: false,
+    raw_strings: false,
+    needs_boundary: false,
+    regex_literals: false,
+    heredocs: false,
+};
+
+const LUA: Syntax = Syntax {
+    line: &["--"],
+    block: &[("--[[", "]]")],
+    strings: &[string("[[", "]]", false, true), DOUBLE, SINGLE],
+    nested_blocks: false,
+    raw_strings: false,
+    needs_boundary: false,
+    regex_literals: false,
+    heredocs: false,
+};
+
+const HASKELL: Syntax = Syntax {
+    line: &["--"],
+    block: &[("{-", "-}")],
+    strings: &[DOUBLE],
+    nested_blocks: true,
+    raw_strings: false,
+    needs_boundary: false,
+    regex_literals: false,
+    heredocs: false,
+};
+
+/// Looks up the comment syntax for a fence tag, as produced by
+/// [`crate::lang::fence_tag`]. Languages without a rule return `None` and are
+/// passed through untouched.
+pub fn syntax_for(tag: &str) -> Option<&'static Syntax> {
+    let syntax = match tag.to_ascii_lowercase().as_str() {
+        "rs" => &RUST,
+        "c" | "h" | "cc" | "cpp" | "cxx" | "hpp" | "hh" | "hxx" | "java" | "dart" | "zig"
+        | "groovy" | "gradle" | "proto" | "m" | "mm" | "jsonc" => &C_LIKE,
+        "cs" => &CSHARP,
+        "kt" | "kts" | "scala" | "swift" => &JVM_MODERN,
+        "go" => &GO,
+        "js" | "jsx" | "mjs" | "cjs" | "ts" | "tsx" | "mts" | "cts" => &JAVASCRIPT,
+        "css" => &CSS,
+        "scss" | "less" => &SCSS,
+        "php" => &PHP,
+        "py" | "pyi" | "pyw" => &PYTHON,
+        "rb" | "ruby" | "rake" | "gemspec" => &RUBY,
+        "sh" | "bash" | "zsh" | "fish" | "ksh" | "makefile" | "mk" | "dockerfile" | "cmake" | "r"
+        | "pl" | "pm" | "ex" | "exs" | "jl" | "tf" | "hcl" | "nix" | "conf" => &SHELL,
+        "toml" => &TOML,
+        "yaml" | "yml" => &YAML,
+        "html" | "htm" | "xhtml" | "xml" | "svg" | "xsl" | "xsd" | "vue" | "csproj" | "props"
+        | "targets" | "plist" => &MARKUP,
+        "sql" => &SQL,
+        "lua" => &LUA,
+        "hs" => &HASKELL,
+        _ => return None,
+    };
+    Some(syntax)
+}
+
+fn is_doc_line(comment: &str) -> bool {
+    (comment.starts_with("///") && !comment.starts_with("////")) || comment.starts_with("//!")
+}
+
+fn is_doc_block(comment: &str) -> bool {
+    (comment.starts_with("/**") && !comment.starts_with("/**/") && !comment.starts_with("/***"))
+        || comment.starts_with("/*!")
+}
+
+fn char_len(content: &str, at: usize) -> usize {
+    content[at..].chars().next().map_or(1, char::len_utf8)
+}
+
+fn at_boundary(line: &str) -> bool {
+    line.chars().last().is_none_or(char::is_whitespace)
+}
+
+fn is_ident(c: char) -> bool {
+    c.is_alphanumeric() || c == '_'
+}
+
+fn string_end(content: &str, start: usize, rule: &StringRule) -> usize {
+    let bytes = content.as_bytes();
+    let mut i = start;
+    while i < content.len() {
+        if rule.escapes && bytes[i] == b'\\' {
+            i += 1;
+            if i < content.len() {
+                i += char_len(content, i);
+            }
+            continue;
+        }
+        if rule.doubled && content[i..].starts_with(&rule.close.repeat(2)) {
+            i += rule.close.len() * 2;
+            continue;
+        }
+        if content[i..].starts_with(rule.close) {
+            return i + rule.close.len();
+        }
+        if !rule.multiline && bytes[i] == b'\n' {
+            return i;
+        }
+        i += char_len(content, i);
+    }
+    content.len()
+}
+
+fn block_end(content: &str, start: usize, open: &str, close: &str, nested: bool) -> usize {
+    let mut depth = 1;
+    let mut i = start + open.len();
+    while i < content.len() {
+        if content[i..].starts_with(close) {
+            depth -= 1;
+            i += close.len();
+            if depth == 0 {
+                return i;
+            }
+        } else if nested && content[i..].starts_with(open) {
+            depth += 1;
+            i += open.len();
+        } else {
+            i += char_len(content, i);
+        }
+    }
+    content.len()
+}
+
+/// Length of a Rust raw string (`r"…"`, `br#"…"#`) starting at `rest`, if any.
+fn raw_string_len(rest: &str) -> Option<usize> {
+    let prefix = if rest.starts_with("br") { 2 } else if rest.starts_with('r') { 1 } else { return None };
+    let hashes = rest[prefix..].bytes().take_while(|&b| b == b'#').count();
+    if !rest[prefix + hashes..].starts_with('"') {
+        return None;
+    }
+    let terminator = format!("\"{}", "#".repeat(hashes));
+    let body = prefix + hashes + 1;
+    Some(
+        rest[body..]
+            .find(&terminator)
+            .map_or(rest.len(), |end| body + end + terminator.len()),
+    )
+}
+
+fn opens_char_literal(rest: &str) -> bool {
+    let mut chars = rest.chars().skip(1);
+    match chars.next() {
+        Some('\\') => true,
+        Some(_) => chars.next() == Some('\''),
+        None => false,
+    }
+}
+
+/// Words after which a `/` starts a regex literal rather than a division.
+const REGEX_KEYWORDS: &[&str] = &[
+    "return", "typeof", "instanceof", "in", "of", "new", "delete", "void", "throw", "case", "do", "else", "yield",
+    "await",
+];
+
+/// Whether a `/` after `line`, or after `out` when `line` is blank, sits
+/// where an operand goes: after an operator, an opening bracket or a keyword
+/// like `return`, but not after a name, a number or a closing bracket.
+fn expects_operand(out: &str, line: &str) -> bool {
+    let before = match line.trim_end() {
+        "" => out.trim_end(),
+        trimmed => trimmed,
+    };
+    match before.chars().last() {
+        None => true,
+        Some(c) if is_ident(c) || c == '$' => {
+            let word = before.rsplit(|c: char| !is_ident(c) && c != '$').next().unwrap_or_default();
+            REGEX_KEYWORDS.contains(&word)
+        }
+        Some(c) => !matches!(c, ')' | ']' | '"' | '\'' | '`'),
+    }
+}
+
+/// Length of a regex literal like `/\/\//g` starting at `rest`, or `None`
+/// when it doesn't close on its line.
+fn regex_len(rest: &str) -> Option<usize> {
+    let mut in_class = false;
+    let mut chars = rest.char_indices().skip(1);
+    while let Some((i, c)) = chars.next() {
+        match c {
+            '\n' => return None,
+            '\\' => match chars.next() {
+                None | Some((_, '\n')) => return None,
+                Some(_) => {}
+            },
+            '[' => in_class = true,
+            ']' => in_class = false,
+            '/' if !in_class => {
+                let flags = rest[i + 1..].chars().take_while(|&c| is_ident(c)).map(char::len_utf8).sum::<usize>();
+                return Some(i + 1 + flags);
+            }
+            _ => {}
+        }
+    }
+    None
+}
+
+/// A heredoc opened on the current line, waiting for its body.
+struct Heredoc {
+    delimiter: String,
+    /// `<<-`, which lets the body and the delimiter be indented with tabs.
+    strip_tabs: bool,
+}
+
+/// Parses `<<EOF`, `<<-EOF`, `<< 'EOF'` or `<<"EOF"` at `rest`, returning
+/// its length and the heredoc. `<<<` here-strings and shifts like `1<<2`
+/// aren't heredocs.
+fn heredoc(rest: &str) -> Option<(usize, Heredoc)> {
+    let after = rest.strip_prefix("<<")?;
+    if after.starts_with('<') {
+        return None;
+    }
+    let strip_tabs = after.starts_with('-');
+    let after = &after[strip_tabs as usize..];
+    let word = after.trim_start_matches([' ', '\t']);
+    let (delimiter, len) = match word.chars().next()? {
+        quote @ ('\'' | '"') => {
+            let close = word[1..].find(quote)?;
+            (word[1..=close].to_string(), close + 2)
+        }
+        c => {
+            let bare = word.strip_prefix('\\').unwrap_or(word);
+            if !(c == '\\' || c.is_alphabetic() || c == '_') {
+                return None;
+            }
+            let name = bare.chars().take_while(|&c| is_ident(c)).collect::<String>();
+            if name.is_empty() {
+                return None;
+            }
+            (name.clone(), word.len() - bare.len() + name.len())
+        }
+    };
+    let consumed = rest.len() - word.len() + len;
+    Some((consumed, Heredoc { delimiter, strip_tabs }))
+}
+
+/// The end of the heredoc bodies starting at `start`, one after another,
+/// each running through its delimiter line.
+fn heredoc_end(content: &str, start: usize, heredocs: &[Heredoc]) -> usize {
+    let mut i = start;
+    for heredoc in heredocs {
+        while i < content.len() {
+            let end = content[i..].find('\n').map_or(content.len(), |n| i + n + 1);
+            let line = content[i..end].trim_end_matches(['\n', '\r']);
+            let line = if heredoc.strip_tabs { line.trim_start_matches('\t') } else { line };
+            i = end;
+            if line == heredoc.delimiter {
+                break;
+            }
+        }
+    }
+    i
+}
+
+/// Removes line and block comments from `content` while leaving string
+/// literals intact. Lines left empty by the removal are dropped entirely;
+/// lines that were already blank are kept.
+pub fn strip_comments(content: &str, syntax: &Syntax, keep_doc_comments: bool) -> String {
+    let mut out = String::with_capacity(content.len());
+    let mut line = String::new();
+    let mut line_had_comment = false;
+    let mut heredocs = Vec::new();
+    let mut i = 0;
+
+    let flush = |out: &mut String, line: &mut String, had_comment: &mut bool, newline: bool| {
+        if *had_comment {
+            let trimmed = line.trim_end();
+            if !trimmed.trim_start().is_empty() {
+                out.push_str(trimmed);
+                if newline {
+                    out.push('\n');
+                }
+            }
+        } else {
+            out.push_str(line);
+            if newline {
+                out.push('\n');
+            }
+        }
+        line.clear();
+        *had_comment = false;
+    };
+
+    // Shebangs look like comments to `#` languages but must survive.
+    if content.starts_with("#!") && syntax.line.contains(&"#") {
+        let end = content.find('\n').unwrap_or(content.len());
+        line.push_str(&content[..end]);
+        i = end;
+    }
+
+    while i < content.len() {
+        let rest = &content[i..];
+
+        if rest.starts_with('\n') {
+            flush(&mut out, &mut line, &mut line_had_comment, true);
+            i += 1;
+            if !heredocs.is_empty() {
+                let end = heredoc_end(content, i, &heredocs);
+                out.push_str(&content[i..end]);
+                heredocs.clear();
+                i = end;
+            }
+            continue;
+        }
+
+        if syntax.heredocs
+            && let Some((len, heredoc)) = heredoc(rest)
+        {
+            line.push_str(&rest[..len]);
+            heredocs.push(heredoc);
+            i += len;
+            continue;
+        }
+
+        if syntax.raw_strings
+            && !line.chars().last().is_some_and(is_ident)
+            && let Some(len) = raw_string_len(rest)
+        {
+            line.push_str(&rest[..len]);
+            i += len;
+            continue;
+        }
+
+        if let Some(&(open, close)) = syntax.block.iter().find(|(open, _)| rest.starts_with(open)) {
+            let end = block_end(content, i, open, close, syntax.nested_blocks);
+            let comment = &content[i..end];
+            if keep_doc_comments && is_doc_block(comment) {
+                line.push_str(comment);
+            } else {
+                line_had_comment = true;
+                let next = content[end..].chars().next();
+                if line.chars().last().is_some_and(is_ident) && next.is_some_and(is_ident) {
+                    // `int/**/x` must not become `intx`.
+                    line.push(' ');
+                } else if line.trim().is_empty() {
+                    let spaces = content[end..]
+                        .bytes()
+                        .take_while(|&b| b == b' ' || b == b'\t')
+                        .count();
+                    i = end + spaces;
+                    continue;
+                }
+            }
+            i = end;
+            continue;
+        }
+
+        if syntax.line.iter().any(|marker| rest.starts_with(marker))
+            && (!syntax.needs_boundary || at_boundary(&line))
+        {
+            let end = rest.find('\n').map_or(content.len(), |n| i + n);
+            let comment = &content[i..end];
+            if keep_doc_comments && is_doc_line(comment) {
+                line.push_str(comment);
+            } else {
+                line_had_comment = true;
+            }
+            i = end;
+            continue;
+        }
+
+        let opened = syntax.strings.iter().find(|rule| {
+            rest.starts_with(rule.open)
+                && (!rule.char_literal || opens_char_literal(rest))
+                && (!syntax.needs_boundary || at_boundary(&line) || line.ends_with([':', '[', '{', ',', '(', '=']))
+        });
+        if let Some(rule) = opened {
+            let end = string_end(content, i + rule.open.len(), rule);
+            line.push_str(&content[i..end]);
+            i = end;
+            continue;
+        }
+
+        if syntax.regex_literals
+            && rest.starts_with('/')
+            && expects_operand(&out, &line)
+            && let Some(len) = regex_len(rest)
+        {
+            line.push_str(&rest[..len]);
+            i += len;
+            continue;
+        }
+
+        let len = char_len(content, i);
+        line.push_str(&rest[..len]);
+        i += len;
+    }
+
+    flush(&mut out, &mut line, &mut line_had_comment, false);
+    out
+}
+
+const LICENSE_MARKERS: &[&str] = &[
+    "spdx-license-identifier",
+    "licensed under",
+    "copyright (c)",
+    "copyright ©",
+    "permission is hereby granted",
+    "gnu general public license",
+    "mozilla public license",
+    "all rights reserved",
+];
+
+/// Finds the first comment block of a file, skipping a shebang and leading
+/// blank lines. Consecutive line comments form one block. Returns `None` when
+/// the file doesn't open with a comment or the comment is unterminated.
+fn leading_comment(content: &str, syntax: &Syntax) -> Option<(usize, usize)> {
+    let mut start = 0;
+    if content.starts_with("#!") {
+        start = content.find('\n')? + 1;
+    }
+    start += content[start..].len() - content[start..].trim_start().len();
+    let rest = &content[start..];
+
+    if let Some(&(open, close)) = syntax.block.iter().find(|(open, _)| rest.starts_with(open)) {
+        let end = block_end(content, start, open, close, syntax.nested_blocks);
+        if !content[..end].ends_with(close) {
+            return None;
+        }
+        return Some((start, end));
+    }
+
+    let mut end = start;
+    for line in content[start..].split_inclusive('\n') {
+        let trimmed = line.trim_start();
+        if !syntax.line.iter().any(|marker| trimmed.starts_with(marker)) {
+            break;
+        }
+        end += line.len();
+    }
+    let end = start + content[start..end].trim_end_matches(['\r', '\n']).len();
+    (end > start).then_some((start, end))
+}
+
+/// Replaces a leading license banner with a one-line placeholder comment.
+///
+/// Only the first comment block is considered, and it must mention a license
+/// marker (SPDX tag, "Licensed under", "Copyright (c)", ...). Doc comments and
+/// files that are nothing but the comment are left alone as ambiguous.
+/// Returns `None` when nothing was stripped.
+pub fn strip_license_header(content: &str, syntax: &Syntax) -> Option<String> {
+    let (start, end) = leading_comment(content, syntax)?;
+    let comment = &content[start..end];
+
+    if is_doc_block(comment) || is_doc_line(comment) || content[end..].trim().is_empty() {
+        return None;
+    }
+
+    let lowered = comment.to_lowercase();
+    if !LICENSE_MARKERS.iter().any(|marker| lowered.contains(marker)) {
+        return None;
+    }
+
+    let placeholder = match (syntax.block.first(), syntax.line.first()) {
+        (Some((open, close)), _) => format!("{} license header omitted {}", open, close),
+        (None, Some(marker)) => format!("{} license header omitted", marker),
+        (None, None) => return None,
+    };
+
+    Some(format!("{}{}{}", &content[..start], placeholder, &content[end..]))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn strip(tag: &str, content: &str) -> String {
+        strip_comments(content, syntax_for(tag).unwrap(), false)
+    }
+
+    #[test]
+    fn comments_go_and_strings_stay_per_language() {
+        for (tag, content, expected) in [
+            ("rs", "let s = \"// not\"; // gone\n", "let s = \"// not\";\n"),
+            ("rs", "fn f<'a>(x: &'a str) {} // gone\n", "fn f<'a>(x: &'a str) {}\n"),
+            ("rs", "let r = r#\"/* \"kept\" */\"#; /* gone */\n", "let r = r#\"/* \"kept\" */\"#;\n"),
+            ("rs", "/* outer /* inner */ still */ x\n", "x\n"),
+            ("rs", "/// doc\nfn f() {}\n", "fn f() {}\n"),
+            ("c", "int/**/x = '/'; // gone\n", "int x = '/';\n"),
+            ("go", "s := `// kept\n/* kept */` // gone\n", "s := `// kept\n/* kept */`\n"),
+            ("py", "x = '# kept'  # gone\n\"\"\"\n# kept\n\"\"\"\n", "x = '# kept'\n\"\"\"\n# kept\n\"\"\"\n"),
+            ("sh", "echo $# \"# kept\" # gone\n", "echo $# \"# kept\"\n"),
+            ("yaml", "title: don't # gone\n", "title: don't\n"),
+            ("sql", "select '-- kept' -- gone\n", "select '-- kept'\n"),
+            ("lua", "--[[ gone ]] x = \"--\"\n", "x = \"--\"\n"),
+            ("hs", "{- a {- b -} c -} x -- gone\n", "x\n"),
+            ("html", "<p><!-- gone --></p>\n", "<p></p>\n"),
+            ("css", "a { content: \"/*\"; } /* gone */\n", "a { content: \"/*\"; }\n"),
+        ] {
+            assert_eq!(strip(tag, content), expected, "{}: {:?}", tag, content);
+        }
+    }
+
+    #[test]
+    fn regex_literals_are_not_comments() {
+        for (content, expected) in [
+            ("path.replace(/\\/\\//g, \"/\"); // collapse\n", "path.replace(/\\/\\//g, \"/\");\n"),
+            ("const re = /[/*]+/;\n/* gone */\n", "const re = /[/*]+/;\n"),
+            ("if (ok) return /\"/.test(s); // gone\n", "if (ok) return /\"/.test(s);\n"),
+            ("const half = total / 2; // gone\n", "const half = total / 2;\n"),
+            ("const r = (a) / (b) / c; // gone\n", "const r = (a) / (b) / c;\n"),
+        ] {
+            assert_eq!(strip("ts", content), expected, "{:?}", content);
+        }
+    }
+
+    #[test]
+    fn csharp_verbatim_strings_end_at_their_quote() {
+        for (content, expected) in [
+            ("var dir = @\"C:\\temp\\\";\n// gone\nvar x = 1;\n", "var dir = @\"C:\\temp\\\";\nvar x = 1;\n"),
+            ("var q = @\"say \"\"// hi\"\"\"; // gone\n", "var q = @\"say \"\"// hi\"\"\";\n"),
+            ("var i = $@\"{a}\\\"; // gone\n", "var i = $@\"{a}\\\";\n"),
+            ("var raw = \"\"\"\n// kept\n\"\"\"; // gone\n", "var raw = \"\"\"\n// kept\n\"\"\";\n"),
+        ] {
+            assert_eq!(strip("cs", content), expected, "{:?}", content);
+        }
+    }
+
+    #[test]
+    fn an_unclosed_quote_ends_with_its_line() {
+        let content = "char *s = \"broken;\n// gone\nint x;\n";
+        assert_eq!(strip("c", content), "char *s = \"broken;\nint x;\n");
+        // Rust strings do run across lines.
+        assert_eq!(strip("rs", "let s = \"a\n// kept\";\n"), "let s = \"a\n// kept\";\n");
+    }
+
+    #[test]
+    fn heredoc_bodies_are_data() {
+        for (content, expected) in [
+            (
+                "cat <<EOF # gone\n# this line is data\nEOF\n# gone\necho\n",
+                "cat <<EOF\n# this line is data\nEOF\necho\n",
+            ),
+            ("cat <<-'END'\n\t# kept\n\tEND\n# gone\n", "cat <<-'END'\n\t# kept\n\tEND\n"),
+            ("a <<A; b <<\"B\"\n# a\nA\n# b\nB\n# gone\n", "a <<A; b <<\"B\"\n# a\nA\n# b\nB\n"),
+            ("cat <<< '# kept' # gone\n", "cat <<< '# kept'\n"),
+            ("echo $((1<<2)) # gone\n", "echo $((1<<2))\n"),
+        ] {
+            assert_eq!(strip("sh", content), expected, "{:?}", content);
+        }
+    }
+
+    #[test]
+    fn license_headers_become_a_placeholder() {
+        let syntax = syntax_for("rs").unwrap();
+        let content = "// Copyright (c) 2024 Someone\n// Licensed under MIT\n\nfn main() {}\n";
+        assert_eq!(
+            strip_license_header(content, syntax).as_deref(),
+            Some("/* license header omitted */\n\nfn main() {}\n")
+        );
+        assert_eq!(strip_license_header("// just a note\nfn main() {}\n", syntax), None);
+        assert_eq!(strip_license_header("//! Copyright (c) crate docs\nfn main() {}\n", syntax), None);
+    }
+}
@@ -0,0 +1,172 @@
+use anyhow::{Context, Result};
+use clap::parser::ValueSource;
+use clap::{ArgAction, ArgMatches, Command};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+/// The per-project config file, looked up at the traversal root.
+pub const PROJECT_CONFIG: &str = "owo.toml";
+
+/// Options that only make sense on the command line.
+const CLI_ONLY: &[&str] = &["directory", "config", "no_config"];
+
+/// A parsed config file. Keys are the CLI option names in snake_case
+/// (`with_dotfiles`, `redact_pattern`), or their kebab-case long names.
+pub struct ConfigFile {
+    pub path: PathBuf,
+    table: toml::Table,
+}
+
+impl ConfigFile {
+    pub fn load(path: &Path) -> Result<Self> {
+        let text = std::fs::read_to_string(path)
+            .with_context(|| format!("Failed to read config file {}", path.display()))?;
+        let table = text
+            .parse::<toml::Table>()
+            .with_context(|| format!("Failed to parse config file {}", path.display()))?;
+        Ok(Self {
+            path: path.to_path_buf(),
+            table,
+        })
+    }
+}
+
+/// The line, from 1, of the config file at `path` that sets the option `id`,
+/// by its snake_case or kebab-case key.
+pub fn key_line(path: &Path, id: &str) -> Option<usize> {
+    let text = std::fs::read_to_string(path).ok()?;
+    let keys = [id.to_string(), id.replace('_', "-")];
+    let index = text.lines().position(|line| {
+        let line = line.trim_start().trim_start_matches('"');
+        keys.iter().any(|key| {
+            line.strip_prefix(key.as_str())
+                .is_some_and(|rest| rest.trim_start_matches('"').trim_start().starts_with('='))
+        })
+    })?;
+    Some(index + 1)
+}
+
+/// `~/.config/owo/config.toml`, honoring `XDG_CONFIG_HOME` and falling back to
+/// `%APPDATA%` on Windows.
+pub fn user_config_path() -> Option<PathBuf> {
+    let base = std::env::var_os("XDG_CONFIG_HOME")
+        .map(PathBuf::from)
+        .or_else(|| std::env::var_os("HOME").map(|home| PathBuf::from(home).join(".config")))
+        .or_else(|| std::env::var_os("APPDATA").map(PathBuf::from))?;
+    Some(base.join("owo").join("config.toml"))
+}
+
+/// Finds the config files that apply to a run rooted at `root`, lowest
+/// precedence first: the user config, then the project's `owo.toml`.
+pub fn discover(root: &Path) -> Result<Vec<ConfigFile>> {
+    let candidates = [user_config_path(), Some(root.join(PROJECT_CONFIG))];
+    candidates
+        .into_iter()
+        .flatten()
+        .filter(|path| path.is_file())
+        .map(|path| ConfigFile::load(&path))
+        .collect()
+}
+
+fn value_to_strings(value: &toml::Value) -> Option<Vec<String>> {
+    match value {
+        toml::Value::String(s) => Some(vec![s.clone()]),
+        toml::Value::Integer(i) => Some(vec![i.to_string()]),
+        toml::Value::Float(f) => Some(vec![f.to_string()]),
+        toml::Value::Array(items) => items
+            .iter()
+            .map(|item| value_to_strings(item).and_then(|mut v| v.pop()))
+            .collect(),
+        _ => None,
+    }
+}
+
+/// Translates config layers into `--long=value` arguments for every option
+/// the command line or environment didn't already set, so clap validates config values
+/// exactly like flags. Higher layers win key by key.
+///
+/// Returns the arguments and, for each option id they set, the file it came
+/// from. Keys no subcommand of `cli` understands produce a warning rather than
+/// an error; keys meant for another subcommand than `cmd` are skipped.
+pub fn to_args(
+    cli: &Command,
+    cmd: &Command,
+    layers: &[ConfigFile],
+    already_set: impl Fn(&str) -> bool,
+) -> Result<(Vec<String>, HashMap<String, PathBuf>)> {
+    let has_key = |cmd: &Command, id: &str| {
+        cmd.get_arguments().any(|arg| arg.get_id().as_str() == id && arg.get_long().is_some())
+    };
+    let mut merged: HashMap<String, (&toml::Value, &Path)> = HashMap::new();
+    for layer in layers {
+        for (key, value) in &layer.table {
+            let id = key.replace('-', "_");
+            let known = cli.get_subcommands().any(|sub| has_key(sub, &id));
+            if !known || CLI_ONLY.contains(&id.as_str()) {
+                eprintln!("warning: unknown key '{}' in {}", key, layer.path.display());
+                continue;
+            }
+            if has_key(cmd, &id) {
+                merged.insert(id, (value, &layer.path));
+            }
+        }
+    }
+
+    let mut args = Vec::new();
+    let mut sources = HashMap::new();
+    let mut ids = merged.keys().cloned().collect::<Vec<_>>();
+    ids.sort();
+
+    for id in ids {
+        if already_set(&id) {
+            continue;
+        }
+        let (value, path) = merged[&id];
+        let arg = cmd
+            .get_arguments()
+            .find(|arg| arg.get_id().as_str() == id)
+            .expect("key was checked against the command");
+        let long = arg.get_long().expect("key was checked against the command");
+
+        if matches!(arg.get_action(), ArgAction::SetTrue) {
+            let Some(enabled) = value.as_bool() else {
+                anyhow::bail!("'{}' in {} must be true or false", id, path.display());
+            };
+            if enabled {
+                args.push(format!("--{}", long));
+                sources.insert(id, path.to_path_buf());
+            }
+            continue;
+        }
+
+        let values = value_to_strings(value)
+            .with_context(|| format!("'{}' in {} must be a string, number or list", id, path.display()))?;
+        for value in values {
+            args.push(format!("--{}={}", long, value));
+        }
+        sources.insert(id, path.to_path_buf());
+    }
+
+    Ok((args, sources))
+}
+
+/// Prints every option's effective value and where it came from.
+pub fn print_effective(cmd: &Command, matches: &ArgMatches, config_sources: &HashMap<String, PathBuf>) {
+    eprintln!("effective configuration:");
+    for arg in cmd.get_arguments() {
+        let id = arg.get_id().as_str();
+        let Some(values) = matches.get_raw(id) else {
+            continue;
+        };
+        let values = values.map(|v| v.to_string_lossy()).collect::<Vec<_>>().join(", ");
+        let source = match (config_sources.get(id), matches.value_source(id)) {
+            (Some(path), _) => format!("config {}", path.display()),
+            (None, Some(ValueSource::DefaultValue)) => "default".to_string(),
+            (None, Some(ValueSource::EnvVariable)) => {
+                format!("env {}", arg.get_env().unwrap_or_default().to_string_lossy())
+            }
+            _ => "flag".to_string(),
+        };
+        eprintln!("  {} = {} ({})", id, values, source);
+    }
+}
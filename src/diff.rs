@@ -0,0 +1,112 @@
+//! File-level differences between two bundles.
+
+use crate::parse::{ParsedBundle, ParsedFile};
+use crate::FileContent;
+use serde::Serialize;
+use similar::{ChangeTag, TextDiff};
+use std::collections::BTreeMap;
+
+/// A file whose content differs between the bundles.
+#[derive(Debug, Clone, Serialize)]
+pub struct Modified {
+    pub path: String,
+    pub lines_added: usize,
+    pub lines_removed: usize,
+    /// A unified diff of the contents, when asked for.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub diff: Option<String>,
+}
+
+/// What changed from one bundle to the other, with paths sorted.
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct BundleDiff {
+    pub added: Vec<String>,
+    pub removed: Vec<String>,
+    pub modified: Vec<Modified>,
+    /// Ways the bundles were written differently that make some differences
+    /// unreliable, like line ranges in only one of them.
+    pub warnings: Vec<String>,
+}
+
+/// The path without a leading `./`, so bundles of `.` and of `src/..`
+/// line up.
+fn key(file: &ParsedFile) -> &str {
+    file.path.strip_prefix("./").unwrap_or(&file.path)
+}
+
+/// The content as compared: Markdown bundles drop trailing whitespace, so
+/// neither side keeps it.
+fn normalized(file: &ParsedFile) -> String {
+    text(file).map(|text| format!("{}\n", text.trim_end())).unwrap_or_default()
+}
+
+fn text(file: &ParsedFile) -> Option<&str> {
+    match &file.content {
+        FileContent::Text(text) => Some(text),
+        FileContent::Binary(_) => None,
+    }
+}
+
+/// Whether any file was cut down, to line ranges or `--grep-context` hunks.
+fn has_excerpts(bundle: &ParsedBundle) -> bool {
+    let omission = |line: &str| line.starts_with("[... ") && line.ends_with(" omitted ...]");
+    bundle
+        .files
+        .iter()
+        .any(|file| !file.lines.is_empty() || text(file).is_some_and(|text| text.lines().any(omission)))
+}
+
+fn has_redactions(bundle: &ParsedBundle) -> bool {
+    bundle.files.iter().any(|file| text(file).is_some_and(|text| text.contains("«REDACTED:")))
+}
+
+/// Compares `old` with `new`. With `content`, each modified file carries a
+/// unified diff.
+pub fn diff(old: &ParsedBundle, new: &ParsedBundle, content: bool) -> BundleDiff {
+    let old_files = old.files.iter().map(|file| (key(file), file)).collect::<BTreeMap<_, _>>();
+    let new_files = new.files.iter().map(|file| (key(file), file)).collect::<BTreeMap<_, _>>();
+    let mut result = BundleDiff::default();
+
+    for (path, old_file) in &old_files {
+        let Some(new_file) = new_files.get(path) else {
+            result.removed.push(path.to_string());
+            continue;
+        };
+        let (before, after) = (normalized(old_file), normalized(new_file));
+        if before == after && text(old_file).is_some() == text(new_file).is_some() {
+            continue;
+        }
+        let text_diff = TextDiff::from_lines(&before, &after);
+        let count = |tag| text_diff.iter_all_changes().filter(|change| change.tag() == tag).count();
+        let diff = content.then(|| {
+            text_diff
+                .unified_diff()
+                .header(&format!("a/{}", path), &format!("b/{}", path))
+                .to_string()
+        });
+        result.modified.push(Modified {
+            path: path.to_string(),
+            lines_added: count(ChangeTag::Insert),
+            lines_removed: count(ChangeTag::Delete),
+            diff,
+        });
+        if old_file.lines != new_file.lines {
+            result.warnings.push(format!("{}: the bundles kept different line ranges", path));
+        }
+    }
+    result.added = new_files.keys().filter(|path| !old_files.contains_key(*path)).map(|path| path.to_string()).collect();
+
+    if !old_files.is_empty() && !new_files.is_empty() && !old_files.keys().any(|path| new_files.contains_key(path)) {
+        result.warnings.push("the bundles share no paths; were they packed from different roots?".to_string());
+    }
+    if has_excerpts(old) != has_excerpts(new) {
+        result.warnings.push(
+            "only one bundle cuts files to line ranges or --grep-context hunks; those files compare different slices"
+                .to_string(),
+        );
+    }
+    if has_redactions(old) != has_redactions(new) {
+        result.warnings.push("only one bundle has redacted secrets; redaction markers show up as changes".to_string());
+    }
+    result
+}
@@ -0,0 +1,138 @@
+use std::io;
+use std::path::PathBuf;
+
+/// Everything that can go wrong while walking and packing. Messages leave the
+/// underlying cause to [`std::error::Error::source`].
+#[derive(Debug, thiserror::Error)]
+pub enum Error {
+    /// The directory to walk doesn't exist.
+    #[error("{} does not exist", .0.display())]
+    RootNotFound(PathBuf),
+    /// An ignore pattern isn't a valid glob. `position` counts from 1 among
+    /// the patterns of `origin`: `default`, `--ignore` or `profile <name>`.
+    #[error("Invalid ignore pattern '{pattern}' (#{position} of {origin})")]
+    Pattern {
+        pattern: String,
+        position: usize,
+        origin: String,
+        source: ignore::Error,
+    },
+    /// An only glob isn't a valid glob.
+    #[error("Invalid only pattern '{pattern}'")]
+    Only {
+        pattern: String,
+        source: globset::Error,
+    },
+    /// A keep-dotfile pattern isn't a valid glob.
+    #[error("Invalid keep-dotfile pattern '{pattern}'")]
+    KeepDotfile {
+        pattern: String,
+        source: globset::Error,
+    },
+    /// A sensitive-file or allow-sensitive glob isn't valid.
+    #[error("Invalid sensitive-file pattern '{pattern}'")]
+    Sensitive {
+        pattern: String,
+        source: globset::Error,
+    },
+    /// A line range suffix on an only entry is malformed.
+    #[error("Invalid line range in '{spec}': {reason}")]
+    LineRange { spec: String, reason: String },
+    /// A redaction or secret allow pattern isn't a valid regex.
+    #[error("Invalid {kind} pattern '{pattern}'")]
+    Regex {
+        /// `redaction` or `secret allow`.
+        kind: &'static str,
+        pattern: String,
+        source: regex::Error,
+    },
+    /// A `--filter-cmd` isn't `<glob>:<command>` with a valid glob.
+    #[error("Invalid filter '{spec}': {reason}")]
+    Filter { spec: String, reason: String },
+    /// A filter command failed, timed out or produced too much output. The
+    /// file keeps its unfiltered content.
+    #[error("Filter '{command}' failed for {}: {reason}; kept the original content", path.display())]
+    FilterFailed {
+        path: PathBuf,
+        command: String,
+        reason: String,
+    },
+    /// A path to explain lies outside the directory to walk.
+    #[error("{} is not under {}", path.display(), root.display())]
+    NotUnderRoot { path: PathBuf, root: PathBuf },
+    /// User-supplied ignore patterns or only globs matched nothing, with
+    /// `--strict-patterns`.
+    #[error("{} pattern(s) did not match any files or directories", .0.len())]
+    UnmatchedPatterns(Vec<String>),
+    /// Owners were given but there's no `CODEOWNERS` file to resolve them.
+    #[error("No CODEOWNERS file in {} or a parent (looked in .github/, the root and docs/)", .0.display())]
+    NoCodeOwners(PathBuf),
+    /// No built-in profile has this name.
+    #[error("Unknown profile '{0}'")]
+    UnknownProfile(String),
+    /// A bundle being read back isn't in a shape owo writes.
+    #[error("Invalid bundle at line {line}: {reason}")]
+    Parse { line: usize, reason: String },
+    /// Bundles to merge mix Markdown and JSON.
+    #[error("Can't merge {first} and {other}: one is Markdown and the other JSON")]
+    MergeFormats { first: String, other: String },
+    /// Two bundles to merge have the same file, with `--on-conflict error`.
+    #[error("{path} is in both {first} and {other}")]
+    MergeConflict { path: String, first: String, other: String },
+    /// A directory entry couldn't be listed, e.g. for lack of permission.
+    #[error("Failed to walk the directory")]
+    Walk(#[from] ignore::Error),
+    /// A file couldn't be read.
+    #[error("Failed to read {}", path.display())]
+    Read { path: PathBuf, source: io::Error },
+    /// An archive to pack couldn't be opened or its entries read.
+    #[error("Failed to read the archive {}", path.display())]
+    Archive { path: PathBuf, source: io::Error },
+    /// A file kept changing while it was read, so its section may not match
+    /// any one state of it. It's packed and marked.
+    #[error("{} changed while it was being read", path.display())]
+    Changed { path: PathBuf },
+    /// An output file couldn't be written.
+    #[error("Failed to write {}", path.display())]
+    Output { path: PathBuf, source: io::Error },
+    /// Some files or directories couldn't be read, filters failed, or files
+    /// changed while they were read; the rest was packed.
+    #[error("{} path(s) could not be read or filtered, or changed while read", .0.len())]
+    PartialFailure(Vec<Error>),
+}
+
+impl Error {
+    /// The I/O error kind behind a read or output failure.
+    pub fn io_kind(&self) -> Option<io::ErrorKind> {
+        match self {
+            Error::Read { source, .. } | Error::Archive { source, .. } | Error::Output { source, .. } => {
+                Some(source.kind())
+            }
+            Error::Walk(e) => e.io_error().map(io::Error::kind),
+            _ => None,
+        }
+    }
+
+    /// Whether the error comes from bad input rather than the file system:
+    /// a missing root, an invalid pattern or an unknown profile.
+    pub fn is_usage(&self) -> bool {
+        matches!(
+            self,
+            Error::RootNotFound(_)
+                | Error::Pattern { .. }
+                | Error::Only { .. }
+                | Error::Sensitive { .. }
+                | Error::LineRange { .. }
+                | Error::Regex { .. }
+                | Error::Filter { .. }
+                | Error::NotUnderRoot { .. }
+                | Error::UnmatchedPatterns(_)
+                | Error::NoCodeOwners(_)
+                | Error::MergeFormats { .. }
+                | Error::UnknownProfile(_)
+        )
+    }
+}
+
+/// A `Result` with [`Error`] as the error type.
+pub type Result<T> = std::result::Result<T, Error>;
@@ -0,0 +1,323 @@
+//! Why a path is or isn't packed, rule by rule.
+
+use crate::profiles;
+use crate::sensitive::Sensitive;
+use crate::testfiles::TestMatcher;
+use crate::walk::{self, Patterns, OWOIGNORE};
+use crate::{Error, Options, Result, TestFiles};
+use ignore::gitignore::{Gitignore, Glob};
+use ignore::Match;
+use serde::Serialize;
+use std::path::{Path, PathBuf};
+
+/// One rule that had a say about a path, in the order the walk applies them.
+#[derive(Debug, Clone, Serialize)]
+pub struct Step {
+    /// `vcs`, `ignore pattern`, `re-include`, `gitignore`, `dotfiles`,
+    /// `insert`, `only`, `tests`, `owner`, `sensitive`, `lockfile` or
+    /// `generated`.
+    pub rule: &'static str,
+    /// Whether the rule leaves the path out. False for rules that let it back
+    /// in, like a re-include or a gitignore `!` line, and for the ignore
+    /// pattern they override.
+    pub excludes: bool,
+    /// The path the rule applied to: the path itself or a parent directory.
+    pub applies_to: PathBuf,
+    /// The pattern as written, for pattern rules.
+    pub pattern: Option<String>,
+    /// Where the rule comes from: `default`, `--ignore`, `profile rust`,
+    /// `src/.gitignore:3` or a flag.
+    pub source: String,
+}
+
+/// The decision chain for one path, from [`explain`].
+#[derive(Debug, Clone, Serialize)]
+pub struct Explanation {
+    /// The path relative to the root.
+    pub path: PathBuf,
+    pub exists: bool,
+    /// Every rule that matched the path or a parent directory.
+    pub steps: Vec<Step>,
+    /// Whether a pack would include the path. Checks that need the file's
+    /// contents, like binary or generated-content detection, aren't covered.
+    pub included: bool,
+}
+
+/// Explains why `path` would be included in or left out of a walk with
+/// `options`. Relative paths are taken from the current directory, like
+/// any path argument.
+pub fn explain(options: &Options, path: &Path) -> Result<Explanation> {
+    let root = options.root.canonicalize().map_err(|_| Error::RootNotFound(options.root.clone()))?;
+    let absolute = path
+        .canonicalize()
+        .or_else(|_| std::path::absolute(path))
+        .map_err(|source| Error::Read { path: path.to_path_buf(), source })?;
+    let rel = absolute
+        .strip_prefix(&root)
+        .map_err(|_| Error::NotUnderRoot { path: path.to_path_buf(), root: options.root.clone() })?
+        .to_path_buf();
+
+    let (_, profiles) = walk::resolve_profiles(options, || profiles::detect(&options.root))?;
+    let patterns = Patterns::new(options, &profiles)?;
+    let ignores = IgnoreFiles::new(&root);
+    let exists = absolute.exists();
+    let mut steps = Vec::new();
+
+    // The walk never descends into an excluded directory, so every parent
+    // gets the same checks as the path, stopping at the first exclusion.
+    let chain = rel.ancestors().collect::<Vec<_>>();
+    for rel_path in chain.into_iter().rev().filter(|p| !p.as_os_str().is_empty()) {
+        let is_dir = rel_path != rel || absolute.is_dir();
+        let walked = options.root.join(rel_path);
+        let before = steps.len();
+        if walk::is_vcs_internals(rel_path) {
+            steps.push(Step {
+                rule: "vcs",
+                excludes: !options.include_vcs_internals,
+                applies_to: rel_path.to_path_buf(),
+                pattern: None,
+                source: match options.include_vcs_internals {
+                    true => "--include-vcs-internals",
+                    false => "always; pass --include-vcs-internals",
+                }
+                .to_string(),
+            });
+        }
+        let (exclude, reinclude) = patterns.matched(&walked, is_dir);
+        if let Some(exclude) = exclude {
+            steps.push(Step {
+                rule: "ignore pattern",
+                excludes: reinclude.is_none(),
+                applies_to: rel_path.to_path_buf(),
+                pattern: Some(exclude.pattern),
+                source: exclude.source,
+            });
+        }
+        if let Some(reinclude) = reinclude {
+            steps.push(Step {
+                rule: "re-include",
+                excludes: false,
+                applies_to: rel_path.to_path_buf(),
+                pattern: Some(reinclude.pattern),
+                source: reinclude.source,
+            });
+        }
+        if let Some((glob, source)) = ignores.matched(&root.join(rel_path), is_dir) {
+            steps.push(Step {
+                rule: "gitignore",
+                excludes: !glob.is_whitelist(),
+                applies_to: rel_path.to_path_buf(),
+                pattern: Some(glob.original().to_string()),
+                source,
+            });
+        }
+        if steps[before..].iter().any(|step| step.excludes) {
+            break;
+        }
+    }
+
+    let dotfiles = walk::Dotfiles::new(options)?;
+    let hidden = dotfiles.hidden(&rel);
+    // The first hidden component nothing keeps, or else the first one.
+    if let Some((name, kept)) = hidden.iter().find(|(_, kept)| kept.is_none()).or(hidden.first()) {
+        let (excludes, pattern, source) = match kept {
+            _ if options.with_dotfiles => (false, None, "--with-dotfiles"),
+            Some(pattern) => (false, Some(pattern.to_string()), "--keep-dotfile"),
+            None => (true, None, "hidden; pass --with-dotfiles or --keep-dotfile"),
+        };
+        steps.push(Step {
+            rule: "dotfiles",
+            excludes,
+            applies_to: rel.ancestors().find(|p| p.ends_with(name)).unwrap_or(&rel).to_path_buf(),
+            pattern,
+            source: source.to_string(),
+        });
+    }
+
+    // On Windows, the first entry from the root down marked hidden or system
+    // counts as a dotfile too.
+    #[cfg(feature = "fs")]
+    if hidden.is_empty()
+        && let Some(marked) = rel
+            .ancestors()
+            .filter(|p| !p.as_os_str().is_empty())
+            .collect::<Vec<_>>()
+            .into_iter()
+            .rev()
+            .find(|p| walk::marked_hidden(|| root.join(p).symlink_metadata().ok()))
+    {
+        let kept = dotfiles.keeps_marked(marked);
+        let (excludes, source) = match kept {
+            _ if options.with_dotfiles => (false, "--with-dotfiles"),
+            Some(_) => (false, "--keep-dotfile"),
+            None => (true, "hidden or system attribute; pass --with-dotfiles or --keep-dotfile"),
+        };
+        steps.push(Step {
+            rule: "dotfiles",
+            excludes,
+            applies_to: marked.to_path_buf(),
+            pattern: kept.map(str::to_string),
+            source: source.to_string(),
+        });
+    }
+
+    if !absolute.is_dir() {
+        if walk::is_insert(&walk::insert_files(options), &absolute) {
+            steps.push(Step {
+                rule: "insert",
+                excludes: true,
+                applies_to: rel.clone(),
+                pattern: None,
+                source: "--header-file/--footer-file".to_string(),
+            });
+        }
+        let only = walk::only_globs(options)?;
+        let named = match only.map(|only| only.matches(&rel)) {
+            Some(matched) => {
+                // The first glob that matches, as written.
+                let pattern = matched.first().map(|&index| options.only[index].clone());
+                steps.push(Step {
+                    rule: "only",
+                    excludes: pattern.is_none(),
+                    applies_to: rel.clone(),
+                    source: match pattern {
+                        Some(_) => "--only".to_string(),
+                        None => "no --only glob matches".to_string(),
+                    },
+                    pattern,
+                });
+                !matched.is_empty()
+            }
+            None => false,
+        };
+        if options.tests != TestFiles::Include && !named {
+            let is_test = TestMatcher::new(&options.root)?.is_test(&options.root.join(&rel));
+            if is_test != (options.tests == TestFiles::Only) {
+                steps.push(Step {
+                    rule: "tests",
+                    excludes: true,
+                    applies_to: rel.clone(),
+                    pattern: None,
+                    source: if is_test { "test code; --no-tests" } else { "not test code; --tests-only" }.to_string(),
+                });
+            }
+        }
+        if let Some(codeowners) = walk::code_owners(options)? {
+            let owners = codeowners.owners(&rel);
+            let source = match (owners.is_empty(), options.include_unowned) {
+                (true, true) => "no owner; --owner-unowned".to_string(),
+                (true, false) => "no owner; pass --owner-unowned".to_string(),
+                (false, _) => format!("owned by {}", owners.join(" ")),
+            };
+            steps.push(Step {
+                rule: "owner",
+                excludes: !codeowners.selects(&rel, &options.owners, options.include_unowned),
+                applies_to: rel.clone(),
+                pattern: None,
+                source,
+            });
+        }
+        let sensitive = Sensitive::new(options)?;
+        // Only a regular file is read for its tokens; a FIFO would block.
+        let read = || absolute.is_file().then(|| std::fs::read(&absolute).ok()).flatten();
+        if let Some(pattern) = sensitive.matched(&rel, read) {
+            let allowed = sensitive.allows(&rel);
+            steps.push(Step {
+                rule: "sensitive",
+                excludes: allowed.is_none(),
+                applies_to: rel.clone(),
+                pattern: Some(pattern),
+                source: match allowed {
+                    Some(glob) => format!("--allow-sensitive={}", glob),
+                    None => "key or credentials file; pass --allow-sensitive".to_string(),
+                },
+            });
+        }
+        let size = || absolute.metadata().map(|m| m.len()).unwrap_or(0);
+        if let Some(reason) = walk::skip_reason(options, &absolute, named, size) {
+            let (rule, hint) = match reason.starts_with("lockfile") {
+                true => ("lockfile", "pass --include-lockfiles"),
+                false => ("generated", "--skip-generated"),
+            };
+            steps.push(Step {
+                rule,
+                excludes: true,
+                applies_to: rel.clone(),
+                pattern: None,
+                source: format!("{}; {}", reason, hint),
+            });
+        }
+    }
+
+    let included = exists && !steps.iter().any(|step| step.excludes);
+    Ok(Explanation { path: rel, exists, steps, included })
+}
+
+/// The ignore files the walker reads for a root: `.owoignore`, `.ignore` and
+/// `.gitignore` in every directory from the file system root down, the
+/// repository's `.git/info/exclude` and the global gitignore.
+struct IgnoreFiles {
+    root: PathBuf,
+    exclude: Option<Gitignore>,
+    global: Gitignore,
+}
+
+impl IgnoreFiles {
+    fn new(root: &Path) -> Self {
+        let exclude = root
+            .ancestors()
+            .map(|dir| dir.join(".git/info/exclude"))
+            .find(|path| path.is_file())
+            .map(|path| Gitignore::new(path).0);
+        Self { root: root.to_path_buf(), exclude, global: Gitignore::global().0 }
+    }
+
+    /// The glob that decides `path`, with its file and line. As in the
+    /// walker, `.owoignore` files beat `.ignore` files, which beat
+    /// `.gitignore` files, the exclude file and then the global gitignore;
+    /// deeper files beat shallower ones.
+    fn matched(&self, path: &Path, is_dir: bool) -> Option<(Glob, String)> {
+        let dirs = path.parent()?.ancestors().collect::<Vec<_>>();
+        for name in [OWOIGNORE, ".ignore", ".gitignore"] {
+            for dir in &dirs {
+                let file = dir.join(name);
+                if !file.is_file() {
+                    continue;
+                }
+                if let Some(found) = self.decide(&Gitignore::new(&file).0, path, is_dir) {
+                    return Some(found);
+                }
+            }
+        }
+        self.exclude
+            .iter()
+            .chain([&self.global])
+            .find_map(|gitignore| self.decide(gitignore, path, is_dir))
+    }
+
+    fn decide(&self, gitignore: &Gitignore, path: &Path, is_dir: bool) -> Option<(Glob, String)> {
+        match gitignore.matched(path, is_dir) {
+            Match::Ignore(glob) | Match::Whitelist(glob) => Some((glob.clone(), self.location(glob))),
+            Match::None => None,
+        }
+    }
+
+    /// `file:line` for a glob, relative to the root when the file is inside it.
+    fn location(&self, glob: &Glob) -> String {
+        let Some(file) = glob.from() else {
+            return "gitignore".to_string();
+        };
+        let line = std::fs::read_to_string(file).ok().and_then(|text| {
+            text.lines()
+                .enumerate()
+                .filter(|(_, line)| line.trim_end() == glob.original())
+                .last()
+                .map(|(index, _)| index + 1)
+        });
+        let shown = file.strip_prefix(&self.root).unwrap_or(file).display();
+        match line {
+            Some(line) => format!("{}:{}", shown, line),
+            None => shown.to_string(),
+        }
+    }
+}
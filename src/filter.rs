@@ -0,0 +1,119 @@
+use crate::{Error, Result};
+use globset::{Glob, GlobMatcher};
+use std::path::Path;
+use std::process::Stdio;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::process::Command;
+use tokio::sync::Semaphore;
+
+/// An external command that file contents matching a glob are piped through,
+/// parsed from `<glob>:<command>`.
+#[derive(Debug)]
+struct Filter {
+    glob: GlobMatcher,
+    command: String,
+}
+
+/// The `--filter-cmd` filters of a run, with the limits they run under.
+#[derive(Debug)]
+pub struct Filters {
+    filters: Vec<Filter>,
+    timeout: Duration,
+    max_output: usize,
+    /// Caps how many commands run at once, however many files match.
+    slots: Arc<Semaphore>,
+}
+
+impl Filters {
+    pub fn new(specs: &[String], timeout: Duration, max_output: usize) -> Result<Self> {
+        let filters = specs
+            .iter()
+            .map(|spec| {
+                let invalid = |reason: String| Error::Filter { spec: spec.clone(), reason };
+                let (glob, command) = spec
+                    .split_once(':')
+                    .filter(|(glob, command)| !glob.is_empty() && !command.trim().is_empty())
+                    .ok_or_else(|| invalid("expected <glob>:<command>".to_string()))?;
+                let glob = Glob::new(glob).map_err(|e| invalid(e.to_string()))?.compile_matcher();
+                Ok(Filter { glob, command: command.to_string() })
+            })
+            .collect::<Result<Vec<_>>>()?;
+        Ok(Self {
+            filters,
+            timeout,
+            max_output,
+            slots: Arc::new(Semaphore::new(num_cpus::get())),
+        })
+    }
+
+    /// The command for the first filter whose glob matches `rel`, the path
+    /// relative to the root, or its file name.
+    pub fn command_for(&self, rel: &Path) -> Option<&str> {
+        let name = rel.file_name().map(Path::new);
+        self.filters
+            .iter()
+            .find(|filter| filter.glob.is_match(rel) || name.is_some_and(|name| filter.glob.is_match(name)))
+            .map(|filter| filter.command.as_str())
+    }
+
+    /// Pipes `content` through `command` in the platform shell and returns
+    /// its stdout, or why it couldn't be used.
+    pub async fn run(&self, command: &str, content: &str) -> std::result::Result<String, String> {
+        let _slot = self.slots.acquire().await.map_err(|e| e.to_string())?;
+        let mut child = shell(command)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::null())
+            .kill_on_drop(true)
+            .spawn()
+            .map_err(|e| format!("failed to start: {}", e))?;
+
+        let mut stdin = child.stdin.take().expect("stdin is piped");
+        let input = content.as_bytes().to_vec();
+        // Written on its own task so a command that fills its stdout pipe
+        // before reading all of stdin can't deadlock us.
+        let writer = tokio::spawn(async move {
+            let _ = stdin.write_all(&input).await;
+        });
+        let mut stdout = child.stdout.take().expect("stdout is piped");
+        let max_output = self.max_output;
+
+        let run = async {
+            let mut output = Vec::new();
+            (&mut stdout)
+                .take(max_output as u64 + 1)
+                .read_to_end(&mut output)
+                .await
+                .map_err(|e| e.to_string())?;
+            if output.len() > max_output {
+                return Err(format!("output exceeded {} bytes", max_output));
+            }
+            let status = child.wait().await.map_err(|e| e.to_string())?;
+            if !status.success() {
+                return Err(format!("exited with {}", status));
+            }
+            String::from_utf8(output).map_err(|_| "output isn't valid UTF-8".to_string())
+        };
+        let result = tokio::time::timeout(self.timeout, run)
+            .await
+            .unwrap_or_else(|_| Err(format!("timed out after {}s", self.timeout.as_secs_f32())));
+        writer.abort();
+        result
+    }
+}
+
+#[cfg(windows)]
+fn shell(command: &str) -> Command {
+    let mut cmd = Command::new("cmd");
+    cmd.arg("/C").arg(command);
+    cmd
+}
+
+#[cfg(not(windows))]
+fn shell(command: &str) -> Command {
+    let mut cmd = Command::new("sh");
+    cmd.arg("-c").arg(command);
+    cmd
+}
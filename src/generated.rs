@@ -0,0 +1,118 @@
+//! Heuristics for minified and generated files, which `--skip-generated`
+//! leaves out: a well-known suffix, a generator marker near the top, or the
+//! long lines and scarce whitespace of minified output. Each returns the
+//! heuristic that fired, for the skip report.
+
+use std::path::Path;
+
+const GENERATED_SUFFIXES: &[&str] = &[
+    ".min.js",
+    ".min.css",
+    ".min.mjs",
+    ".bundle.js",
+    ".pb.go",
+    ".pb.cc",
+    ".pb.h",
+    "_pb2.py",
+    "_pb2_grpc.py",
+    ".g.dart",
+    ".freezed.dart",
+    ".designer.cs",
+    ".g.cs",
+    ".js.map",
+    ".css.map",
+];
+
+const GENERATED_MARKERS: &[&str] = &["DO NOT EDIT", "@generated", "Code generated by", "AUTO-GENERATED", "Autogenerated"];
+
+/// How many lines from the top of a file are searched for generator markers.
+const MARKER_LINES: usize = 5;
+
+/// Files smaller than this are never judged by density, since a short file
+/// can easily have one long line.
+const DENSITY_MIN_BYTES: usize = 1024;
+const MAX_AVERAGE_LINE_LENGTH: usize = 300;
+const MIN_WHITESPACE_RATIO: f64 = 0.03;
+
+/// Checks a file's name against well-known generated-file suffixes, returning
+/// the heuristic that fired.
+pub fn detect_by_name(path: &Path) -> Option<String> {
+    let name = path.file_name()?.to_str()?;
+    GENERATED_SUFFIXES
+        .iter()
+        .find(|suffix| name.ends_with(*suffix))
+        .map(|suffix| format!("suffix {}", suffix))
+}
+
+/// Checks a file's content for generator markers near the top and for the
+/// line-length and whitespace density typical of minified output.
+pub fn detect_by_content(content: &str) -> Option<String> {
+    for line in content.lines().take(MARKER_LINES) {
+        if let Some(marker) = GENERATED_MARKERS.iter().find(|marker| line.contains(*marker)) {
+            return Some(format!("marker \"{}\"", marker));
+        }
+    }
+
+    if content.len() < DENSITY_MIN_BYTES {
+        return None;
+    }
+
+    let lines = content.lines().count().max(1);
+    let average = content.len() / lines;
+    if average > MAX_AVERAGE_LINE_LENGTH {
+        return Some(format!("average line length {}", average));
+    }
+
+    let whitespace = content.chars().filter(|c| c.is_whitespace()).count();
+    let ratio = whitespace as f64 / content.chars().count() as f64;
+    if ratio < MIN_WHITESPACE_RATIO {
+        return Some(format!("whitespace ratio {:.1}%", ratio * 100.0));
+    }
+
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn generated_suffixes_are_matched_on_the_file_name() {
+        for (path, expected) in [
+            ("web/app.min.js", Some("suffix .min.js")),
+            ("api/user.pb.go", Some("suffix .pb.go")),
+            ("proto/user_pb2_grpc.py", Some("suffix _pb2_grpc.py")),
+            ("Form1.Designer.cs", None),
+            ("Form1.designer.cs", Some("suffix .designer.cs")),
+            ("app.js", None),
+            ("min.js/app.js", None),
+        ] {
+            assert_eq!(detect_by_name(Path::new(path)).as_deref(), expected, "{}", path);
+        }
+    }
+
+    #[test]
+    fn markers_count_only_near_the_top() {
+        let marked = "// Code generated by mockgen. DO NOT EDIT.\npackage mocks\n";
+        assert_eq!(detect_by_content(marked).as_deref(), Some("marker \"DO NOT EDIT\""));
+        let late = format!("{}// @generated\n", "line\n".repeat(MARKER_LINES));
+        assert_eq!(detect_by_content(&late), None);
+        let within = format!("{}// @generated\n", "line\n".repeat(MARKER_LINES - 1));
+        assert_eq!(detect_by_content(&within).as_deref(), Some("marker \"@generated\""));
+    }
+
+    #[test]
+    fn density_flags_minified_output_but_not_small_files() {
+        let words = "let answer = compute(value); ".repeat(40);
+        assert_eq!(detect_by_content(&words).as_deref(), Some("average line length 1160"));
+        let packed = "a+b;".repeat(300) + "\n";
+        assert_eq!(detect_by_content(&packed).as_deref(), Some("average line length 1201"));
+        // Short lines, but nothing between the tokens but the newline.
+        let tight = "a=1;b=2;c=3;d=4;e=5;f=6;g=7;h=8;i=9;j=0;\n".repeat(50);
+        assert_eq!(detect_by_content(&tight).as_deref(), Some("whitespace ratio 2.4%"));
+        let spaced = "a = 1; b = 2; c = 3; d = 4;\n".repeat(50);
+        assert_eq!(detect_by_content(&spaced), None);
+        assert_eq!(detect_by_content(&"x".repeat(DENSITY_MIN_BYTES - 1)), None);
+        assert_eq!(detect_by_content(&"fn main() {}\n".repeat(200)), None);
+    }
+}
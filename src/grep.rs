@@ -0,0 +1,115 @@
+//! Selecting files by content, and cutting them down to the matching lines.
+
+use crate::{Error, Result};
+use regex::Regex;
+
+/// How much of a file [`Options::grep_exclude`](crate::Options::grep_exclude)
+/// searches unless told to search all of it.
+pub const GREP_EXCLUDE_HEAD: u64 = 64 * 1024;
+
+fn compile(patterns: &[String], kind: &'static str) -> Result<Vec<Regex>> {
+    patterns
+        .iter()
+        .map(|pattern| Regex::new(pattern).map_err(|source| Error::Regex { kind, pattern: pattern.clone(), source }))
+        .collect()
+}
+
+/// The [`Options::grep`](crate::Options::grep) regexes, any of which selects
+/// a file.
+pub(crate) struct Grep {
+    regexes: Vec<Regex>,
+    /// Lines kept around each match, or `None` to keep whole files.
+    context: Option<usize>,
+}
+
+impl Grep {
+    /// Compiles `patterns`, or returns `None` when there are none.
+    pub(crate) fn new(patterns: &[String], context: Option<usize>) -> Result<Option<Self>> {
+        if patterns.is_empty() {
+            return Ok(None);
+        }
+        Ok(Some(Self { regexes: compile(patterns, "grep")?, context }))
+    }
+
+    fn is_match(&self, text: &str) -> bool {
+        self.regexes.iter().any(|regex| regex.is_match(text))
+    }
+
+    /// The number of lines of `text` that match.
+    pub(crate) fn count(&self, text: &str) -> usize {
+        // Most files don't match at all, and one pass over the whole text
+        // settles that.
+        if !self.is_match(text) {
+            return 0;
+        }
+        text.lines().filter(|line| self.is_match(line)).count()
+    }
+
+    /// With a context setting, `text` cut down to its matching lines and the
+    /// context around them, with a marker for each run of lines left out.
+    /// `None` when whole files are kept, or when nothing matches any more,
+    /// e.g. because a transformation removed the matched text.
+    pub(crate) fn excerpt(&self, text: &str) -> Option<String> {
+        let context = self.context?;
+        let lines = text.lines().collect::<Vec<_>>();
+        let mut keep = vec![false; lines.len()];
+        let mut any = false;
+        for (index, line) in lines.iter().enumerate() {
+            if self.is_match(line) {
+                any = true;
+                let end = (index + context + 1).min(lines.len());
+                keep[index.saturating_sub(context)..end].fill(true);
+            }
+        }
+        if !any {
+            return None;
+        }
+
+        let mut out = String::new();
+        let mut omitted = 0;
+        for (line, keep) in lines.iter().zip(keep) {
+            if !keep {
+                omitted += 1;
+                continue;
+            }
+            if omitted > 0 {
+                out.push_str(&omission(omitted));
+                omitted = 0;
+            }
+            out.push_str(line);
+            out.push('\n');
+        }
+        if omitted > 0 {
+            out.push_str(&omission(omitted));
+        }
+        Some(out)
+    }
+}
+
+pub(crate) fn omission(lines: usize) -> String {
+    format!("[... {} line{} omitted ...]\n", lines, if lines == 1 { "" } else { "s" })
+}
+
+/// The [`Options::grep_exclude`](crate::Options::grep_exclude) regexes, any
+/// of which leaves a file out.
+pub(crate) struct GrepExclude {
+    regexes: Vec<Regex>,
+    /// Whether to search whole files rather than their first
+    /// [`GREP_EXCLUDE_HEAD`] bytes.
+    pub(crate) full: bool,
+}
+
+impl GrepExclude {
+    /// Compiles `patterns`, or returns `None` when there are none.
+    pub(crate) fn new(patterns: &[String], full: bool) -> Result<Option<Self>> {
+        if patterns.is_empty() {
+            return Ok(None);
+        }
+        Ok(Some(Self { regexes: compile(patterns, "grep exclude")?, full }))
+    }
+
+    /// The first pattern that matches `text`, as written.
+    pub(crate) fn fired(&self, text: &str) -> Option<&str> {
+        self.regexes.iter().find(|regex| regex.is_match(text)).map(Regex::as_str)
+    }
+}
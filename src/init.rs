@@ -0,0 +1,236 @@
+//! `owo init`: a commented `owo.toml`, and an `.owoignore` with the detected
+//! profiles' patterns spelled out, for a new project.
+
+use anyhow::{Context, Result};
+use owo::profiles::Profile;
+use owo::walk::OWOIGNORE;
+use std::io::{BufRead, Write};
+
+/// What the questions settle, or their defaults under `--yes`.
+pub struct Answers {
+    pub output: String,
+    pub tests: bool,
+    /// The most tokens a bundle may hold, when there's a budget.
+    pub tokens: Option<u64>,
+    /// Whether to write an `.owoignore` with the detected profiles' patterns.
+    pub owoignore: bool,
+}
+
+impl Answers {
+    pub fn defaults(detected: &[(&'static Profile, String)]) -> Self {
+        Self { output: "context.md".to_string(), tests: true, tokens: None, owoignore: !detected.is_empty() }
+    }
+
+    /// Asks each question on stderr, reading the answers from stdin. An empty
+    /// answer, or the end of input, keeps the default.
+    pub fn ask(detected: &[(&'static Profile, String)]) -> Result<Self> {
+        let mut answers = Self::defaults(detected);
+        let output = ask(&format!("Output file [{}]", answers.output))?;
+        if !output.is_empty() {
+            answers.output = output;
+        }
+        answers.tests = yes_no("Include test files?", answers.tests)?;
+        loop {
+            let tokens = ask("Token budget, like 100k (empty for none)")?;
+            if tokens.is_empty() {
+                break;
+            }
+            match parse_tokens(&tokens) {
+                Some(tokens) => {
+                    answers.tokens = Some(tokens);
+                    break;
+                }
+                None => eprintln!("'{}' isn't a number of tokens, like 8000, 100k or 1.5M", tokens),
+            }
+        }
+        if !detected.is_empty() {
+            answers.owoignore = yes_no(&format!("Spell out the profiles' patterns in {} to edit?", OWOIGNORE), true)?;
+        }
+        Ok(answers)
+    }
+}
+
+fn ask(question: &str) -> Result<String> {
+    eprint!("{}: ", question);
+    std::io::stderr().flush()?;
+    let mut answer = String::new();
+    std::io::stdin().lock().read_line(&mut answer).context("Failed to read an answer")?;
+    Ok(answer.trim().to_string())
+}
+
+fn yes_no(question: &str, default: bool) -> Result<bool> {
+    loop {
+        let answer = ask(&format!("{} [{}]", question, if default { "Y/n" } else { "y/N" }))?;
+        match answer.to_ascii_lowercase().as_str() {
+            "" => return Ok(default),
+            "y" | "yes" => return Ok(true),
+            "n" | "no" => return Ok(false),
+            _ => eprintln!("Answer y or n"),
+        }
+    }
+}
+
+/// Parses a token count like `8000`, `100k` or `1.5M`. Units count in
+/// 1000s, as token budgets are given.
+fn parse_tokens(tokens: &str) -> Option<u64> {
+    let tokens = tokens.trim().replace(['_', ','], "");
+    let (number, scale) = match tokens.to_ascii_lowercase().chars().last()? {
+        'k' => (&tokens[..tokens.len() - 1], 1_000.0),
+        'm' => (&tokens[..tokens.len() - 1], 1_000_000.0),
+        _ => (tokens.as_str(), 1.0),
+    };
+    let tokens = number.trim().parse::<f64>().ok().filter(|tokens| *tokens > 0.0)?;
+    Some((tokens * scale) as u64)
+}
+
+/// The `owo.toml` for `answers`, every setting commented.
+pub fn config(answers: &Answers, detected: &[(&'static Profile, String)]) -> String {
+    let mut config = String::from(
+        "# owo's settings for this project, written by `owo init`. Keys are the\n\
+         # option names in snake_case (see `owo pack --help`); flags given on the\n\
+         # command line win.\n\
+         \n\
+         # Where `owo pack` writes the bundle.\n",
+    );
+    config.push_str(&format!("output = {}\n", toml::Value::from(answers.output.as_str())));
+    if !answers.tests {
+        config.push_str("\n# Test files and directories are left out.\nno_tests = true\n");
+    }
+    if let Some(tokens) = answers.tokens {
+        // owo estimates four bytes per token.
+        let bytes = tokens * 4;
+        config.push_str(&format!(
+            "\n# About {} tokens, at four bytes each: a larger bundle fails before\n\
+             # anything is written. on_oversize = \"truncate\" keeps the files that fit.\n\
+             max_output_size = {}\n",
+            owo::summary::thousands(tokens),
+            bytes
+        ));
+    }
+    if !detected.is_empty() {
+        let found = detected
+            .iter()
+            .map(|(profile, marker)| format!("{} ({})", profile.name, marker))
+            .collect::<Vec<_>>()
+            .join(", ");
+        config.push_str(&format!("\n# Detected: {}.\n", found));
+        if answers.owoignore {
+            config.push_str(&format!(
+                "# The profiles' ignore patterns are spelled out in {} to edit, so\n\
+                 # they aren't applied again here.\n\
+                 no_auto_profile = true\n",
+                OWOIGNORE
+            ));
+        } else {
+            let profiles = detected.iter().map(|(profile, _)| format!("{:?}", profile.name)).collect::<Vec<_>>();
+            config.push_str(&format!(
+                "# Their ignore patterns apply automatically; name them to keep them\n\
+                 # whatever marker files come and go.\n\
+                 # profile = [{}]\n",
+                profiles.join(", ")
+            ));
+        }
+    }
+    config.push_str(
+        "\n# More to consider:\n\
+         # redact = true               # replace secrets with markers\n\
+         # with_dotfiles = true        # include dotfiles and dot-directories\n\
+         # skip_generated = true       # leave out minified and generated files\n\
+         # ignore = \"obj|bin|build\"    # replaces the default ignore list\n",
+    );
+    config
+}
+
+/// The `.owoignore` with the detected profiles' patterns, one block per
+/// profile.
+pub fn owoignore(detected: &[(&'static Profile, String)]) -> String {
+    let mut ignore = format!(
+        "# Paths owo leaves out, in .gitignore syntax, on top of .gitignore and\n\
+         # owo's default list. Like a .gitignore, an {} in a subdirectory\n\
+         # applies below it. Seeded by `owo init` from the detected profiles.\n",
+        OWOIGNORE
+    );
+    for (profile, marker) in detected {
+        ignore.push_str(&format!("\n# {} ({})\n", profile.name, marker));
+        for pattern in profile.patterns {
+            ignore.push_str(pattern);
+            ignore.push('\n');
+        }
+    }
+    ignore
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cli;
+    use crate::config::{self, ConfigFile};
+
+    /// The flags the config loader reads out of `text`, as `owo pack` takes
+    /// them.
+    fn loaded(text: &str) -> Vec<String> {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join(config::PROJECT_CONFIG);
+        std::fs::write(&path, text).unwrap();
+        let cli = cli::cli();
+        let pack = cli.find_subcommand("pack").unwrap();
+        let (args, _) = config::to_args(&cli, pack, &[ConfigFile::load(&path).unwrap()], |_| false).unwrap();
+        pack.clone().try_get_matches_from([&["pack".to_string()], args.as_slice()].concat()).unwrap();
+        args
+    }
+
+    #[test]
+    fn configs_round_trip_through_the_loader() {
+        let rust = owo::profiles::detect_names(&["Cargo.toml".to_string()]);
+        let answers = |tests, tokens, owoignore| {
+            Answers { output: "out dir/ctx.md".to_string(), tests, tokens, owoignore }
+        };
+        // Each case's answers, whether rust was detected, and the flags loaded.
+        let cases: &[(Answers, bool, &[&str])] = &[
+            (Answers::defaults(&[]), false, &["--output=context.md"]),
+            (answers(true, None, false), true, &["--output=out dir/ctx.md"]),
+            (answers(true, None, true), true, &["--no-auto-profile", "--output=out dir/ctx.md"]),
+            (
+                answers(false, Some(100_000), false),
+                false,
+                &["--max-output-size=400000", "--no-tests", "--output=out dir/ctx.md"],
+            ),
+        ];
+        for (answers, detected, expected) in cases {
+            let detected = if *detected { rust.as_slice() } else { &[] };
+            assert_eq!(loaded(&config(answers, detected)), *expected, "{}", config(answers, detected));
+        }
+    }
+
+    #[test]
+    fn commented_settings_load_once_uncommented() {
+        let rust = owo::profiles::detect_names(&["Cargo.toml".to_string()]);
+        let text = config(&Answers { owoignore: false, ..Answers::defaults(&rust) }, &rust);
+        fn setting(line: &str) -> Option<&str> {
+            let line = line.strip_prefix("# ")?.split("  #").next()?.trim();
+            let (key, _) = line.split_once(" = ")?;
+            key.chars().all(|c| c == '_' || c.is_ascii_lowercase()).then_some(line)
+        }
+        let uncommented = text.lines().map(|line| setting(line).unwrap_or(line)).collect::<Vec<_>>().join("\n");
+        let args = loaded(&uncommented);
+        let expected = [
+            "--ignore=obj|bin|build",
+            "--output=context.md",
+            "--profile=rust",
+            "--redact",
+            "--skip-generated",
+            "--with-dotfiles",
+        ];
+        assert_eq!(args, expected, "{}", uncommented);
+    }
+
+    #[test]
+    fn token_budgets() {
+        for (tokens, expected) in [("8000", Some(8000)), ("100k", Some(100_000)), ("1.5M", Some(1_500_000))] {
+            assert_eq!(parse_tokens(tokens), expected, "{}", tokens);
+        }
+        for tokens in ["", "k", "-5", "0", "lots"] {
+            assert_eq!(parse_tokens(tokens), None, "{}", tokens);
+        }
+    }
+}
@@ -0,0 +1,218 @@
+use owo::summary::{estimated_tokens, human_size};
+use ratatui::crossterm::event::{self, Event, KeyCode, KeyEvent, KeyEventKind, KeyModifiers};
+use ratatui::layout::{Constraint, Layout};
+use ratatui::style::{Modifier, Style};
+use ratatui::text::{Line, Span};
+use ratatui::widgets::{List, ListItem, ListState, Paragraph};
+use ratatui::{DefaultTerminal, Frame};
+use std::collections::HashMap;
+use std::io;
+
+/// A file the walk would pack, as offered by the picker.
+pub struct Candidate {
+    /// The path relative to the root, with `/` separators.
+    pub rel: String,
+    pub size: u64,
+}
+
+/// A line of the list: a directory heading or one of its files.
+enum Row {
+    Dir { name: String, files: Vec<usize> },
+    File(usize),
+}
+
+struct Picker {
+    files: Vec<Candidate>,
+    selected: Vec<bool>,
+    query: String,
+    rows: Vec<Row>,
+    state: ListState,
+}
+
+/// Whether every character of `query` appears in `text` in order, ignoring
+/// case.
+fn fuzzy_match(query: &str, text: &str) -> bool {
+    let mut text = text.chars().flat_map(char::to_lowercase);
+    query
+        .chars()
+        .flat_map(char::to_lowercase)
+        .all(|wanted| text.any(|c| c == wanted))
+}
+
+fn checkbox(selected: usize, total: usize) -> &'static str {
+    match selected {
+        0 => "[ ]",
+        n if n == total => "[x]",
+        _ => "[-]",
+    }
+}
+
+impl Picker {
+    fn new(files: Vec<Candidate>) -> Self {
+        let selected = vec![true; files.len()];
+        let mut picker = Self { files, selected, query: String::new(), rows: Vec::new(), state: ListState::default() };
+        picker.refilter();
+        picker
+    }
+
+    /// Rebuilds the rows from the files matching the query, grouped by
+    /// directory in walk order.
+    fn refilter(&mut self) {
+        let mut groups: Vec<(String, Vec<usize>)> = Vec::new();
+        let mut index = HashMap::new();
+        for (i, file) in self.files.iter().enumerate() {
+            if !fuzzy_match(&self.query, &file.rel) {
+                continue;
+            }
+            let dir = file.rel.rsplit_once('/').map_or(".", |(dir, _)| dir).to_string();
+            let group = *index.entry(dir.clone()).or_insert_with(|| {
+                groups.push((dir, Vec::new()));
+                groups.len() - 1
+            });
+            groups[group].1.push(i);
+        }
+        self.rows = groups
+            .into_iter()
+            .flat_map(|(name, files)| {
+                let rows = files.iter().map(|&i| Row::File(i)).collect::<Vec<_>>();
+                std::iter::once(Row::Dir { name, files }).chain(rows)
+            })
+            .collect();
+        self.state.select((!self.rows.is_empty()).then_some(0));
+    }
+
+    /// Selects every file if some aren't selected, or none otherwise.
+    fn toggle(&mut self, files: &[usize]) {
+        let all = files.iter().all(|&i| self.selected[i]);
+        for &i in files {
+            self.selected[i] = !all;
+        }
+    }
+
+    fn toggle_current(&mut self) {
+        match self.state.selected().and_then(|row| self.rows.get(row)) {
+            Some(Row::Dir { files, .. }) => {
+                let files = files.clone();
+                self.toggle(&files);
+            }
+            Some(&Row::File(i)) => self.selected[i] = !self.selected[i],
+            None => {}
+        }
+    }
+
+    fn toggle_visible(&mut self) {
+        let visible = self
+            .rows
+            .iter()
+            .filter_map(|row| match row {
+                Row::File(i) => Some(*i),
+                Row::Dir { .. } => None,
+            })
+            .collect::<Vec<_>>();
+        self.toggle(&visible);
+    }
+
+    fn draw(&mut self, frame: &mut Frame) {
+        let [top, list, footer] = Layout::vertical([Constraint::Length(1), Constraint::Min(1), Constraint::Length(1)])
+            .areas(frame.area());
+
+        let prompt = Line::from(vec![Span::styled("Filter: ", Style::new().add_modifier(Modifier::BOLD)), Span::raw(&self.query)]);
+        frame.render_widget(Paragraph::new(prompt), top);
+
+        let items = self
+            .rows
+            .iter()
+            .map(|row| match row {
+                Row::Dir { name, files } => {
+                    let selected = files.iter().filter(|&&i| self.selected[i]).count();
+                    let text = format!("{} {}/ ({}/{})", checkbox(selected, files.len()), name, selected, files.len());
+                    ListItem::new(text).style(Style::new().add_modifier(Modifier::BOLD))
+                }
+                &Row::File(i) => {
+                    let file = &self.files[i];
+                    let name = file.rel.rsplit_once('/').map_or(file.rel.as_str(), |(_, name)| name);
+                    let mark = checkbox(self.selected[i] as usize, 1);
+                    ListItem::new(format!("    {} {}  {}", mark, name, human_size(file.size)))
+                }
+            })
+            .collect::<Vec<_>>();
+        let items = List::new(items).highlight_style(Style::new().add_modifier(Modifier::REVERSED));
+        frame.render_stateful_widget(items, list, &mut self.state);
+
+        let (count, bytes) = self
+            .files
+            .iter()
+            .zip(&self.selected)
+            .filter(|(_, selected)| **selected)
+            .fold((0, 0), |(count, bytes), (file, _)| (count + 1, bytes + file.size));
+        let status = format!(
+            "{}/{} files, {}, ~{} tokens | space: toggle  ctrl-a: all/none  enter: pack  esc: cancel",
+            count,
+            self.files.len(),
+            human_size(bytes),
+            estimated_tokens(bytes)
+        );
+        frame.render_widget(Paragraph::new(status).style(Style::new().add_modifier(Modifier::REVERSED)), footer);
+    }
+
+    /// Handles a key press; returns whether the picker is done, and if so,
+    /// whether it was confirmed.
+    fn key(&mut self, key: KeyEvent) -> Option<bool> {
+        let last = self.rows.len().saturating_sub(1);
+        let ctrl = key.modifiers.contains(KeyModifiers::CONTROL);
+        match key.code {
+            KeyCode::Enter => return Some(true),
+            KeyCode::Esc => return Some(false),
+            KeyCode::Char('c') if ctrl => return Some(false),
+            KeyCode::Char('a') if ctrl => self.toggle_visible(),
+            KeyCode::Char(' ') => self.toggle_current(),
+            KeyCode::Up => self.state.select_previous(),
+            KeyCode::Down => self.state.select(self.state.selected().map(|row| (row + 1).min(last))),
+            KeyCode::PageUp => self.state.scroll_up_by(10),
+            KeyCode::PageDown => self.state.select(self.state.selected().map(|row| (row + 10).min(last))),
+            KeyCode::Home => self.state.select_first(),
+            KeyCode::End => self.state.select(Some(last)),
+            KeyCode::Backspace => {
+                self.query.pop();
+                self.refilter();
+            }
+            KeyCode::Char(c) if !ctrl => {
+                self.query.push(c);
+                self.refilter();
+            }
+            _ => {}
+        }
+        None
+    }
+
+    fn run(&mut self, terminal: &mut DefaultTerminal) -> io::Result<bool> {
+        loop {
+            terminal.draw(|frame| self.draw(frame))?;
+            if let Event::Key(key) = event::read()?
+                && key.kind == KeyEventKind::Press
+                && let Some(confirmed) = self.key(key)
+            {
+                return Ok(confirmed);
+            }
+        }
+    }
+}
+
+/// Lets the user pick files from `files` on the terminal. Returns the
+/// selected files' paths in walk order, or `None` when cancelled.
+pub fn pick(files: Vec<Candidate>) -> io::Result<Option<Vec<String>>> {
+    let mut picker = Picker::new(files);
+    let mut terminal = ratatui::try_init()?;
+    let confirmed = picker.run(&mut terminal);
+    ratatui::restore();
+    if !confirmed? {
+        return Ok(None);
+    }
+    let selected = picker
+        .files
+        .into_iter()
+        .zip(picker.selected)
+        .filter_map(|(file, selected)| selected.then_some(file.rel))
+        .collect();
+    Ok(Some(selected))
+}
@@ -0,0 +1,30 @@
+use std::path::Path;
+
+/// Well-known files that carry no extension but whose language is implied by
+/// their name.
+const KNOWN_FILENAMES: &[(&str, &str)] = &[
+    ("Makefile", "makefile"),
+    ("makefile", "makefile"),
+    ("GNUmakefile", "makefile"),
+    ("Dockerfile", "dockerfile"),
+    ("Containerfile", "dockerfile"),
+    ("Jenkinsfile", "groovy"),
+    ("Rakefile", "ruby"),
+    ("Gemfile", "ruby"),
+];
+
+/// Returns the language tag used for a file's code fence.
+///
+/// This is the single source of language detection: everything else that
+/// behaves per language (comment stripping, ...) keys off this value.
+pub fn fence_tag(path: &Path) -> Option<String> {
+    if let Some(ext) = path.extension().and_then(|e| e.to_str()) {
+        return Some(ext.to_string());
+    }
+
+    let name = path.file_name()?.to_str()?;
+    KNOWN_FILENAMES
+        .iter()
+        .find(|(known, _)| *known == name)
+        .map(|(_, tag)| tag.to_string())
+}
@@ -0,0 +1,58 @@
+//! Packs a directory into a single Markdown document, one fenced code block
+//! per file, honoring .gitignore and owo's own ignore patterns.
+//!
+//! Build [`Options`], then call [`generate`] for a [`Bundle`] of the files
+//! and their rendered Markdown, or [`stream`] to receive the files one by one
+//! as they're ready. The `owo` binary is a thin CLI over this.
+
+#[cfg(feature = "fs")]
+pub mod archive;
+mod bundle;
+pub mod codeowners;
+mod comments;
+pub mod diff;
+mod error;
+#[cfg(feature = "fs")]
+pub mod explain;
+#[cfg(feature = "fs")]
+mod filter;
+mod generated;
+mod grep;
+mod lang;
+mod lockfiles;
+mod markdown;
+mod memory;
+pub mod merge;
+mod options;
+mod paths;
+#[cfg(feature = "fs")]
+mod pipeline;
+pub mod parse;
+pub mod patch;
+pub mod profiles;
+mod ranges;
+mod redact;
+mod sensitive;
+#[cfg(feature = "fs")]
+pub mod stats;
+pub mod summary;
+mod testfiles;
+mod transform;
+pub mod tree;
+pub mod walk;
+
+pub use bundle::{Bundle, FileContent, FileEntry};
+pub use error::{Error, Result};
+pub use grep::GREP_EXCLUDE_HEAD;
+pub use lang::fence_tag;
+pub use markdown::{code_span, Collapsible, GroupBy, MarkdownStyle};
+pub use memory::{bundle_files, render_files};
+pub use options::{OnChange, Options, DEFAULT_IGNORE, VCS_INTERNALS};
+pub use paths::{long_path, network_filesystem};
+#[cfg(feature = "fs")]
+pub use pipeline::{generate, stream};
+pub use ranges::{split_line_ranges, LineRange};
+pub use redact::SecretReportEntry;
+pub use sensitive::SENSITIVE_NAMES;
+pub use summary::Summary;
+pub use testfiles::{TestFiles, TEST_PATTERNS};
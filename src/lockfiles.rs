@@ -0,0 +1,195 @@
+use std::collections::BTreeSet;
+use std::path::Path;
+
+/// Well-known dependency lockfiles, matched by exact file name.
+pub const LOCKFILE_NAMES: &[&str] = &[
+    "Cargo.lock",
+    "package-lock.json",
+    "npm-shrinkwrap.json",
+    "yarn.lock",
+    "pnpm-lock.yaml",
+    "bun.lockb",
+    "bun.lock",
+    "deno.lock",
+    "poetry.lock",
+    "Pipfile.lock",
+    "pdm.lock",
+    "uv.lock",
+    "Gemfile.lock",
+    "composer.lock",
+    "go.sum",
+    "mix.lock",
+    "Podfile.lock",
+    "Package.resolved",
+    "packages.lock.json",
+    "gradle.lockfile",
+    "flake.lock",
+    "pubspec.lock",
+    "Manifest.toml",
+];
+
+pub fn is_lockfile(path: &Path) -> bool {
+    path.file_name()
+        .and_then(|name| name.to_str())
+        .is_some_and(|name| LOCKFILE_NAMES.contains(&name))
+}
+
+/// Extracts the dependency names recorded in a lockfile, preferring the
+/// top-level (directly declared) ones where the format records them.
+/// Returns `None` for formats that can't be summarized.
+pub fn dependency_names(path: &Path, content: &str) -> Option<Vec<String>> {
+    let name = path.file_name()?.to_str()?;
+    let names = match name {
+        "Cargo.lock" => cargo_lock(content),
+        "poetry.lock" | "uv.lock" | "pdm.lock" | "Manifest.toml" => toml_packages(content),
+        "package-lock.json" | "npm-shrinkwrap.json" => npm_lock(content)?,
+        "composer.lock" => {
+            let json: serde_json::Value = serde_json::from_str(content).ok()?;
+            json["packages"]
+                .as_array()?
+                .iter()
+                .filter_map(|p| p["name"].as_str().map(str::to_string))
+                .collect()
+        }
+        "Pipfile.lock" => {
+            let json: serde_json::Value = serde_json::from_str(content).ok()?;
+            ["default", "develop"]
+                .iter()
+                .filter_map(|section| json[section].as_object())
+                .flat_map(|deps| deps.keys().cloned())
+                .collect()
+        }
+        "flake.lock" => {
+            let json: serde_json::Value = serde_json::from_str(content).ok()?;
+            json["nodes"]["root"]["inputs"].as_object()?.keys().cloned().collect()
+        }
+        "yarn.lock" => yarn_lock(content),
+        "pnpm-lock.yaml" => yaml_sections(
+            content,
+            &["dependencies:", "devDependencies:", "optionalDependencies:"],
+        ),
+        "pubspec.lock" => yaml_sections(content, &["packages:"]),
+        "Gemfile.lock" => gemfile_lock(content),
+        "go.sum" => content
+            .lines()
+            .filter_map(|line| line.split_whitespace().next())
+            .map(str::to_string)
+            .collect(),
+        _ => return None,
+    };
+
+    Some(names.into_iter().collect::<BTreeSet<_>>().into_iter().collect())
+}
+
+fn toml_string_value<'a>(line: &'a str, key: &str) -> Option<&'a str> {
+    let rest = line.trim().strip_prefix(key)?.trim_start().strip_prefix('=')?;
+    Some(rest.trim().trim_matches('"'))
+}
+
+/// Names of every `[[package]]` entry.
+fn toml_packages(content: &str) -> Vec<String> {
+    content
+        .lines()
+        .filter_map(|line| toml_string_value(line, "name"))
+        .map(str::to_string)
+        .collect()
+}
+
+/// Dependencies of workspace members, i.e. packages without a `source`.
+fn cargo_lock(content: &str) -> Vec<String> {
+    let mut names = Vec::new();
+    for package in content.split("[[package]]").skip(1) {
+        if package.lines().any(|line| toml_string_value(line, "source").is_some()) {
+            continue;
+        }
+        let Some(start) = package.find("dependencies = [") else {
+            continue;
+        };
+        let list = &package[start + "dependencies = [".len()..];
+        let list = &list[..list.find(']').unwrap_or(list.len())];
+        for dependency in list.split(',') {
+            let dependency = dependency.trim().trim_matches('"');
+            if let Some(name) = dependency.split_whitespace().next() {
+                names.push(name.to_string());
+            }
+        }
+    }
+
+    if names.is_empty() {
+        toml_packages(content)
+    } else {
+        names
+    }
+}
+
+fn npm_lock(content: &str) -> Option<Vec<String>> {
+    let json: serde_json::Value = serde_json::from_str(content).ok()?;
+    let root = &json["packages"][""];
+    let sections = if root.is_object() { root } else { &json };
+    Some(
+        ["dependencies", "devDependencies", "optionalDependencies"]
+            .iter()
+            .filter_map(|section| sections[section].as_object())
+            .flat_map(|deps| deps.keys().cloned())
+            .collect(),
+    )
+}
+
+fn yarn_lock(content: &str) -> Vec<String> {
+    content
+        .lines()
+        .filter(|line| !line.starts_with([' ', '#']) && line.ends_with(':'))
+        .flat_map(|line| line.trim_end_matches(':').split(", "))
+        .filter_map(|spec| {
+            let spec = spec.trim_matches('"');
+            // Scoped packages start with `@`, so the version separator is the
+            // last `@`, not the first.
+            let at = spec[1..].find('@').map(|i| i + 1)?;
+            Some(spec[..at].to_string())
+        })
+        .collect()
+}
+
+/// Keys directly under the given sections of a YAML lockfile, including
+/// sections nested one level under pnpm's `importers: .:`.
+fn yaml_sections(content: &str, sections: &[&str]) -> Vec<String> {
+    let mut names = Vec::new();
+    let mut section_indent = None;
+
+    for line in content.lines() {
+        if line.trim().is_empty() {
+            continue;
+        }
+        let indent = line.len() - line.trim_start().len();
+        let trimmed = line.trim();
+
+        if let Some(parent) = section_indent {
+            if indent > parent {
+                if indent == parent + 2
+                    && let Some((key, _)) = trimmed.split_once(':')
+                {
+                    names.push(key.trim_matches(['"', '\'']).to_string());
+                }
+                continue;
+            }
+            section_indent = None;
+        }
+
+        if indent <= 4 && sections.contains(&trimmed) {
+            section_indent = Some(indent);
+        }
+    }
+
+    names
+}
+
+fn gemfile_lock(content: &str) -> Vec<String> {
+    content
+        .lines()
+        .skip_while(|line| *line != "DEPENDENCIES")
+        .skip(1)
+        .take_while(|line| line.starts_with("  "))
+        .filter_map(|line| line.split_whitespace().next())
+        .map(|name| name.trim_end_matches('!').to_string())
+        .collect()
+}
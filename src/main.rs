@@ -1,172 +1,1129 @@
+mod cli;
+mod config;
+mod init;
+mod interactive;
+mod man;
+mod permalink;
+mod remote;
+mod report;
+mod sqlite;
+
 use anyhow::{Context, Result};
-use clap::{Arg, ArgAction, Command};
-use ignore::{overrides::OverrideBuilder, WalkBuilder};
-use std::path::Path;
-use std::sync::{Arc, Mutex};
+use clap::ArgMatches;
+use cli::CommonArgs;
+use owo::codeowners::CodeOwners;
+use owo::profiles::Profile;
+use owo::{tree, walk};
+use std::collections::{HashMap, HashSet};
+use std::io::{IsTerminal, Write};
+use std::path::{Path, PathBuf};
+use std::process::ExitCode;
+use std::time::{Duration, Instant};
 use tokio::fs;
-use tokio::sync::Semaphore;
-
-#[tokio::main]
-async fn main() -> Result<()> {
-    let mut cmd = Command::new("owo")
-        .version("0.1.0")
-        .author("xOphiuchus")
-        .about("Like tree but outputs file contents to a single markdown file")
-        .override_help(r#"USAGE:
-    owo [OPTIONS] [PATH]
-
-EXAMPLES:
-    owo -o content.md
-    owo -I "obj|bin|build|dist" -o content.md -w
-    owo --help
-
-FLAGS:
-    -w, --with-dotfiles    Include hidden files and directories
-    -h, --help             Print help information
-    -V, --version          Print version information
-
-OPTIONS:
-    -I, --ignore <PATTERNS>    Ignore files/directories matching these patterns (pipe-separated) [default: obj|bin|build|dist|.git|.env|.env.*]
-    -o, --output <FILE>        Output file (required)
-
-ARGS:
-    <PATH>                     Directory to traverse [default: current directory]"#)
-        .arg(
-            Arg::new("ignore")
-                .short('I')
-                .long("ignore")
-                .value_name("PATTERNS")
-                .help("Ignore files/directories matching these patterns (pipe-separated)")
-                .default_value("obj|bin|build|dist|.git|.env|.env.*")
-        )
-        .arg(
-            Arg::new("output")
-                .short('o')
-                .long("output")
-                .value_name("FILE")
-                .help("Output file")
-                .required(true)
-        )
-        .arg(
-            Arg::new("with_dotfiles")
-                .short('w')
-                .long("with-dotfiles")
-                .action(ArgAction::SetTrue)
-                .help("Include dotfiles (hidden files and directories)")
-        )
-        .arg(
-            Arg::new("directory")
-                .help("Directory to traverse [default: current directory]")
-                .default_value(".")
-        )
-        .disable_help_flag(true)
-        .disable_version_flag(true);
 
-    let args: Vec<String> = std::env::args().collect();
-    if args.len() == 1 || args.contains(&"-h".to_string()) || args.contains(&"--help".to_string()) {
-        println!("{}", cmd.render_help());
+fn main() -> ExitCode {
+    let runtime = tokio::runtime::Runtime::new().expect("the Tokio runtime starts");
+    let result = runtime.block_on(run());
+    // A read that timed out is still blocked on its thread; waiting for it
+    // at shutdown would hang the run after all.
+    runtime.shutdown_background();
+    match result {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(e) => {
+            report::error(&format!("{:#}", e));
+            ExitCode::from(exit_code(&e))
+        }
+    }
+}
+
+/// The documented exit status for an error: 2 for bad arguments (clap exits
+/// with 2 on its own), 3 for files that couldn't be read under `--strict`, 1
+/// for anything else.
+fn exit_code(e: &anyhow::Error) -> u8 {
+    match e.downcast_ref::<owo::Error>() {
+        Some(owo::Error::PartialFailure(_)) => 3,
+        Some(e) if e.is_usage() => 2,
+        _ => 1,
+    }
+}
+
+/// Formats an error and its causes on one line, like anyhow's `{:#}`.
+fn describe(e: &dyn std::error::Error) -> String {
+    std::iter::successors(Some(e), |e| e.source())
+        .map(|e| e.to_string())
+        .collect::<Vec<_>>()
+        .join(": ")
+}
+
+/// Warns about every path that couldn't be read and, under `--strict`, turns
+/// them into a partial failure.
+fn check_failures(common: &CommonArgs, failures: Vec<owo::Error>) -> Result<()> {
+    for failure in &failures {
+        report::warning(&describe(failure));
+    }
+    if common.strict && !failures.is_empty() {
+        return Err(owo::Error::PartialFailure(failures).into());
+    }
+    Ok(())
+}
+
+/// The bundle's failures, plus under `--strict` every file that changed while
+/// it was read.
+fn bundle_failures(common: &CommonArgs, bundle: &mut owo::Bundle) -> Vec<owo::Error> {
+    let mut failures = std::mem::take(&mut bundle.failures);
+    if common.strict {
+        failures.extend(bundle.summary.changed.iter().map(|path| owo::Error::Changed { path: path.clone() }));
+    }
+    failures
+}
+
+/// Warns about every user-supplied ignore pattern and `--only` glob that
+/// matched nothing and, under `--strict-patterns`, turns them into an error.
+fn check_patterns(common: &CommonArgs, mut unmatched: Vec<String>, only: Vec<String>) -> Result<()> {
+    for pattern in &unmatched {
+        report::warning(&format!("ignore pattern '{}' did not match any files or directories", pattern));
+    }
+    for glob in &only {
+        report::warning(&format!("--only glob '{}' did not match any files or directories", glob));
+    }
+    unmatched.extend(only);
+    if common.strict_patterns && !unmatched.is_empty() {
+        return Err(owo::Error::UnmatchedPatterns(unmatched).into());
+    }
+    Ok(())
+}
+
+/// Writes `contents` to a temporary file next to `path` and renames it into
+/// place, so a failed write never leaves a truncated file behind.
+async fn write_output(path: &str, contents: String) -> Result<()> {
+    let path = Path::new(path);
+    let Some(name) = path.file_name() else {
+        return Err(owo::Error::Output {
+            path: path.to_path_buf(),
+            source: std::io::Error::new(std::io::ErrorKind::InvalidInput, "not a file name"),
+        }
+        .into());
+    };
+    let temp = path.with_file_name(format!(".{}.owo-tmp", name.to_string_lossy()));
+    let written = match fs::write(owo::long_path(&temp), contents).await {
+        Ok(()) => fs::rename(owo::long_path(&temp), owo::long_path(path)).await,
+        Err(e) => Err(e),
+    };
+    if let Err(source) = written {
+        let _ = fs::remove_file(&temp).await;
+        return Err(owo::Error::Output { path: path.to_path_buf(), source }.into());
+    }
+    Ok(())
+}
+
+async fn run() -> Result<()> {
+    let mut args = cli::normalize_args(std::env::args().collect());
+    if args.len() == 1 {
+        if config::discover(Path::new("."))?.is_empty() {
+            cli::cli().print_help()?;
+            return Ok(());
+        }
+        args.push("pack".to_string());
+    }
+
+    // A first pass finds the subcommand and config flags, before the output
+    // file (which a config file may provide) is required.
+    let matches = cli::cli()
+        .mut_subcommand("pack", |pack| pack.mut_arg("output", |arg| arg.required(false)))
+        .try_get_matches_from(&args)
+        .unwrap_or_else(|e| e.exit());
+    if matches.get_flag("generate_man") {
+        man::render(
+            cli::cli(),
+            cli::pack_command(),
+            cli::EXAMPLES,
+            cli::HELP_SECTIONS,
+            &mut std::io::stdout(),
+        )?;
         return Ok(());
     }
 
-    let matches = cmd.clone().try_get_matches().map_err(|e| {
-        if e.kind() == clap::error::ErrorKind::DisplayHelp {
-            println!("{}", cmd.render_help());
-            std::process::exit(0);
+    match matches.subcommand() {
+        Some(("completions", sub_matches)) => {
+            let shell = *sub_matches.get_one::<clap_complete::Shell>("shell").unwrap();
+            clap_complete::generate(shell, &mut cli::cli(), "owo", &mut std::io::stdout());
+            Ok(())
+        }
+        Some(("diff", sub_matches)) => {
+            report::set_color(sub_matches.get_one::<String>("color").unwrap());
+            diff(sub_matches)
         }
-        e
-    })?;
+        Some(("merge", sub_matches)) => merge(sub_matches).await,
+        Some(("init", sub_matches)) => init(sub_matches),
+        Some((name @ ("pack" | "tree" | "stats" | "explain"), sub_matches)) => {
+            let cmd = cli::cli().find_subcommand(name).expect("subcommand was matched above").clone();
+            let (config_args, config_sources) = config_args(&cmd, sub_matches)?;
+            // Config values go right after the subcommand name, so flags given
+            // on the command line still win.
+            args.splice(2..2, config_args);
+            let matches = cli::cli()
+                .mut_subcommand("pack", |pack| {
+                    pack.mut_arg("output", |arg| {
+                        arg.required(false).required_unless_present_any(["list", "check_patterns"])
+                    })
+                })
+                .try_get_matches_from(&args)
+                .unwrap_or_else(|e| e.exit());
+            let (_, sub_matches) = matches.subcommand().expect("subcommand was matched above");
+            report::set_color(sub_matches.get_one::<String>("color").unwrap());
+            if sub_matches.get_flag("verbose") {
+                config::print_effective(&cmd, sub_matches, &config_sources);
+            }
+            // Bad patterns fail before anything is cloned or walked, naming
+            // where a bad --ignore value was set.
+            walk::compile_patterns(&CommonArgs::from_matches(sub_matches).options())
+                .map_err(|e| set_where(e, &cmd, sub_matches, &config_sources))?;
+            match name {
+                "pack" => pack(sub_matches).await,
+                "tree" => tree(sub_matches).await,
+                "stats" => stats(sub_matches).await,
+                _ => explain(sub_matches),
+            }
+        }
+        _ => {
+            cli::cli().print_help()?;
+            Ok(())
+        }
+    }
+}
+
+/// Loads the config layers selected by `--config`/`--no-config` and turns
+/// them into arguments for every option not already set by a flag or
+/// environment variable.
+fn config_args(cmd: &clap::Command, matches: &ArgMatches) -> Result<(Vec<String>, HashMap<String, PathBuf>)> {
+    let layers = if matches.get_flag("no_config") {
+        Vec::new()
+    } else if let Some(path) = matches.get_one::<String>("config") {
+        vec![config::ConfigFile::load(Path::new(path))?]
+    } else {
+        // A repository to clone has no owo.toml on disk to discover, and the
+        // one it might contain is never read.
+        let remote = matches.try_get_one::<String>("remote").ok().flatten();
+        config::discover(Path::new(remote.unwrap_or_else(|| matches.get_one::<String>("directory").unwrap())))?
+    };
+    config::to_args(&cli::cli(), cmd, &layers, |id| {
+        matches!(
+            matches.value_source(id),
+            Some(clap::parser::ValueSource::CommandLine | clap::parser::ValueSource::EnvVariable)
+        )
+    })
+}
 
-    let ignore_patterns = matches.get_one::<String>("ignore").unwrap();
-    let output_file = matches.get_one::<String>("output").unwrap();
-    let with_dotfiles = matches.get_flag("with_dotfiles");
-    let directory = matches.get_one::<String>("directory").unwrap();
+/// Adds where `--ignore` was set to an error in one of its patterns: a config
+/// file and line, an environment variable or the command line.
+fn set_where(
+    e: owo::Error,
+    cmd: &clap::Command,
+    matches: &ArgMatches,
+    config_sources: &HashMap<String, PathBuf>,
+) -> owo::Error {
+    let owo::Error::Pattern { pattern, position, origin, source } = e else {
+        return e;
+    };
+    if origin != "--ignore" {
+        return owo::Error::Pattern { pattern, position, origin, source };
+    }
+    let set = match (config_sources.get("ignore"), matches.value_source("ignore")) {
+        (Some(path), _) => match config::key_line(path, "ignore") {
+            Some(line) => format!("set in {}:{}", path.display(), line),
+            None => format!("set in {}", path.display()),
+        },
+        (None, Some(clap::parser::ValueSource::EnvVariable)) => {
+            let arg = cmd.get_arguments().find(|arg| arg.get_id() == "ignore");
+            let env = arg.and_then(|arg| arg.get_env()).unwrap_or_default();
+            format!("set by {}", env.to_string_lossy())
+        }
+        _ => "on the command line".to_string(),
+    };
+    owo::Error::Pattern { pattern, position, origin: format!("{}, {}", origin, set), source }
+}
 
-    let mut override_builder = OverrideBuilder::new(directory);
-    for pattern in ignore_patterns.split('|') {
-        let pattern = pattern.trim();
-        if !pattern.is_empty() {
-            override_builder.add(&format!("!{}", pattern))?;
+/// Prints each pattern for `--check-patterns`: where it came from, as
+/// written, and the glob it matches as.
+fn print_patterns(patterns: &[walk::CompiledPattern]) -> Result<()> {
+    let labels = patterns.iter().map(|p| format!("{} #{}", p.source, p.position)).collect::<Vec<_>>();
+    let label_width = labels.iter().map(String::len).max().unwrap_or(0);
+    let pattern_width = patterns.iter().map(|p| p.pattern.len()).max().unwrap_or(0);
+    let glob_width = patterns.iter().map(|p| p.glob.len()).max().unwrap_or(0);
+    let mut out = std::io::stdout().lock();
+    for (pattern, label) in patterns.iter().zip(&labels) {
+        let mut notes = Vec::new();
+        if pattern.reinclude {
+            notes.push("re-include");
+        }
+        if pattern.only_dirs {
+            notes.push("directories only");
         }
+        let line = format!(
+            "{:label_width$}  {:pattern_width$}  {:glob_width$}  {}",
+            label,
+            pattern.pattern,
+            pattern.glob,
+            notes.join(", ")
+        );
+        writeln!(out, "{}", line.trim_end())?;
     }
-    let overrides = override_builder.build()?;
+    Ok(())
+}
 
-    let walker = WalkBuilder::new(directory)
-        .hidden(false)
-        .git_ignore(true)
-        .require_git(false)
-        .overrides(overrides)
-        .build()
-        .filter_map(|entry| {
-            let entry = entry.ok()?;
-            let path = entry.path();
+/// Notes which profiles were detected and, with `--verbose`, every pattern
+/// the walk applied.
+fn report_profiles(common: &CommonArgs, detected: &[(&Profile, String)], profiles: &[&Profile]) {
+    if !detected.is_empty() && !common.quiet {
+        let notes = detected
+            .iter()
+            .map(|(profile, marker)| format!("{} ({})", profile.name, marker))
+            .collect::<Vec<_>>();
+        eprintln!("Detected {}; applying matching ignore profiles", notes.join(", "));
+    }
+    if common.verbose {
+        let patterns = common.ignore.split('|').map(str::trim).filter(|p| !p.is_empty());
+        eprintln!("ignore patterns: {}", patterns.collect::<Vec<_>>().join(", "));
+        for profile in profiles {
+            eprintln!("profile {}: {}", profile.name, profile.patterns.join(", "));
+        }
+        if common.tests != owo::TestFiles::Include {
+            eprintln!("test patterns: {}", owo::TEST_PATTERNS.join(", "));
+        }
+        let sensitive = owo::SENSITIVE_NAMES.iter().copied().chain(common.sensitive.iter().map(String::as_str));
+        eprintln!("sensitive names: {}", sensitive.collect::<Vec<_>>().join(", "));
+        if !common.allow_sensitive.is_empty() {
+            eprintln!("allowed sensitive names: {}", common.allow_sensitive.join(", "));
+        }
+    }
+    if (!common.owners.is_empty() || common.owner_unowned)
+        && let Ok(codeowners) = CodeOwners::find(Path::new(&common.directory))
+    {
+        if common.verbose {
+            eprintln!("owners from {}: {}", codeowners.path.display(), common.owners.join(", "));
+        }
+        for (line, pattern) in &codeowners.invalid {
+            report::warning(&format!(
+                "{}:{}: skipped '{}'; CODEOWNERS has no negation or [...] classes",
+                codeowners.path.display(),
+                line,
+                pattern
+            ));
+        }
+    }
+}
 
-            if path.is_dir() {
-                return Some(entry);
+async fn tree(matches: &ArgMatches) -> Result<()> {
+    let common = CommonArgs::from_matches(matches);
+    let columns = cli::strings(matches, "tree_annotations");
+    let annotations = tree::Annotations {
+        size: matches.get_flag("sizes") || columns.iter().any(|column| column == "size"),
+        tokens: columns.iter().any(|column| column == "tokens"),
+        lines: columns.iter().any(|column| column == "lines"),
+        bytes: matches.get_flag("bytes"),
+    };
+    let mermaid = matches.get_one::<String>("tree_format").unwrap() == "mermaid";
+    let max_nodes = *matches.get_one::<usize>("max_nodes").unwrap();
+    let root = Path::new(&common.directory);
+    // Annotating what's packed means packing, so the numbers match the
+    // bundle's; otherwise nothing is read.
+    let tree = if columns.is_empty() {
+        let mut walk = walk::walk(&common.options())?;
+        report_profiles(&common, &walk.detected, &walk.profiles);
+        check_failures(&common, std::mem::take(&mut walk.errors))?;
+        check_patterns(
+            &common,
+            std::mem::take(&mut walk.unmatched_patterns),
+            std::mem::take(&mut walk.unmatched_only),
+        )?;
+        report::sensitive(&walk.skipped);
+        match mermaid {
+            true => tree::render_mermaid(root, &walk, annotations, max_nodes),
+            false => tree::render(root, &walk, annotations),
+        }
+    } else {
+        let mut bundle = owo::generate(&common.options()).await?;
+        report_profiles(&common, &bundle.detected_profiles, &bundle.profiles);
+        check_failures(&common, bundle_failures(&common, &mut bundle))?;
+        check_patterns(
+            &common,
+            std::mem::take(&mut bundle.unmatched_patterns),
+            std::mem::take(&mut bundle.unmatched_only),
+        )?;
+        report::sensitive(&bundle.summary.skipped);
+        match mermaid {
+            true => tree::render_bundle_mermaid(&bundle, annotations, max_nodes),
+            false => tree::render_bundle(&bundle, annotations),
+        }
+    };
+    match matches.get_one::<String>("tree_output") {
+        Some(path) => {
+            write_output(path, tree).await?;
+            if !common.quiet {
+                eprintln!("Successfully wrote the tree to {}", path);
             }
+        }
+        None => print!("{}", tree),
+    }
+    Ok(())
+}
 
-            if !with_dotfiles {
-                for component in path.components() {
-                    if let std::path::Component::Normal(name) = component {
-                        let name_str = name.to_string_lossy();
-                        if name_str.starts_with('.') && name_str != ".git" {
-                            return None;
-                        }
-                    }
-                }
+async fn stats(matches: &ArgMatches) -> Result<()> {
+    let common = CommonArgs::from_matches(matches);
+    let options = common
+        .options()
+        .only(cli::strings(matches, "only"))
+        .skip_generated(matches.get_flag("skip_generated"));
+    let mut stats = owo::stats::stats(&options).await?;
+    report_profiles(&common, &stats.detected_profiles, &stats.profiles);
+    check_failures(&common, std::mem::take(&mut stats.failures))?;
+    check_patterns(
+        &common,
+        std::mem::take(&mut stats.unmatched_patterns),
+        std::mem::take(&mut stats.unmatched_only),
+    )?;
+
+    let rows = stats.languages.iter().chain([&stats.total]);
+    match matches.get_one::<String>("stats_format").unwrap().as_str() {
+        "json" => {
+            let json = serde_json::json!({ "languages": stats.languages, "total": stats.total });
+            println!("{}", serde_json::to_string_pretty(&json)?);
+        }
+        "csv" => {
+            println!("language,files,lines,blank_lines,bytes");
+            for row in rows {
+                println!("{},{},{},{},{}", row.language, row.files, row.lines, row.blank_lines, row.bytes);
+            }
+        }
+        _ => {
+            let width = rows.clone().map(|row| row.language.len()).max().unwrap_or(0).max("Language".len());
+            let header = format!("{:<width$} {:>8} {:>10} {:>10} {:>10}", "Language", "Files", "Lines", "Blank", "Size");
+            let rule = "-".repeat(header.len());
+            println!("{}\n{}", header, rule);
+            for row in stats.languages.iter() {
+                println!("{}", stats_row(row, width));
             }
+            println!("{}\n{}", rule, stats_row(&stats.total, width));
+        }
+    }
+    Ok(())
+}
+
+fn stats_row(row: &owo::stats::LanguageStats, width: usize) -> String {
+    format!(
+        "{:<width$} {:>8} {:>10} {:>10} {:>10}",
+        row.language,
+        row.files,
+        row.lines,
+        row.blank_lines,
+        owo::summary::human_size(row.bytes)
+    )
+}
 
-            Some(entry)
+/// The paths the `--from-patch` diff touches, read from the file or from
+/// stdin for `-`.
+fn patch_paths(path: &str) -> Result<Vec<String>> {
+    let bytes = match path {
+        "-" => {
+            let mut bytes = Vec::new();
+            std::io::Read::read_to_end(&mut std::io::stdin(), &mut bytes)
+                .context("Failed to read the patch from stdin")?;
+            bytes
+        }
+        _ => std::fs::read(owo::long_path(Path::new(path))).with_context(|| format!("Failed to read {}", path))?,
+    };
+    let paths = owo::patch::touched_paths(&String::from_utf8_lossy(&bytes));
+    if paths.is_empty() {
+        anyhow::bail!("The patch in {} touches no files; it has no diff headers", path);
+    }
+    Ok(paths)
+}
+
+/// The `--only` entries for the `--from-patch` paths: each path the patch
+/// touches that an `--only` entry also matches, with that entry's line
+/// ranges, or every path when there's no `--only`. Fails when `--only`
+/// leaves none.
+fn patched_only(patched: &[String], only: &[String]) -> Result<Vec<String>> {
+    let mut globs = Vec::new();
+    for spec in only {
+        let (pattern, _) = owo::split_line_ranges(spec)?;
+        let invalid = |source| owo::Error::Only { pattern: pattern.to_string(), source };
+        let glob = globset::Glob::new(pattern).map_err(invalid)?;
+        globs.push((glob.compile_matcher(), &spec[pattern.len()..]));
+    }
+    let kept = patched.iter().filter_map(|path| match globs.is_empty() {
+        true => Some(globset::escape(path)),
+        false => globs
+            .iter()
+            .find(|(glob, _)| glob.is_match(path))
+            .map(|(_, ranges)| format!("{}{}", globset::escape(path), ranges)),
+    });
+    let kept = kept.collect::<Vec<_>>();
+    if kept.is_empty() {
+        // An empty list would mean no --only at all.
+        anyhow::bail!("None of the {} path(s) the patch touches matches --only", patched.len());
+    }
+    Ok(kept)
+}
+
+/// Reads and parses a bundle for `owo diff`.
+fn read_bundle(path: &str) -> Result<owo::parse::ParsedBundle> {
+    let text = std::fs::read_to_string(owo::long_path(Path::new(path)))
+        .with_context(|| format!("Failed to read {}", path))?;
+    owo::parse::parse(&text).with_context(|| format!("Failed to parse {}", path))
+}
+
+fn diff(matches: &ArgMatches) -> Result<()> {
+    let old = read_bundle(matches.get_one::<String>("old").unwrap())?;
+    let new = read_bundle(matches.get_one::<String>("new").unwrap())?;
+    let content = matches.get_flag("content");
+    let diff = owo::diff::diff(&old, &new, content);
+    for warning in &diff.warnings {
+        report::warning(warning);
+    }
+    if matches.get_flag("json") {
+        println!("{}", serde_json::to_string_pretty(&diff)?);
+        return Ok(());
+    }
+    report::diff(&diff, matches.get_flag("stat"), content);
+    Ok(())
+}
+
+fn init(matches: &ArgMatches) -> Result<()> {
+    let root = Path::new(matches.get_one::<String>("directory").unwrap());
+    if !root.is_dir() {
+        anyhow::bail!("{} isn't a directory", root.display());
+    }
+    let config_path = root.join(config::PROJECT_CONFIG);
+    let ignore_path = root.join(walk::OWOIGNORE);
+    let force = matches.get_flag("force");
+    if config_path.exists() && !force {
+        anyhow::bail!("{} already exists; pass --force to overwrite it", config_path.display());
+    }
+    let detected = owo::profiles::detect(root);
+    let answers = match matches.get_flag("yes") || !std::io::stdin().is_terminal() {
+        true => init::Answers::defaults(&detected),
+        false => init::Answers::ask(&detected)?,
+    };
+    if answers.owoignore && ignore_path.exists() && !force {
+        anyhow::bail!("{} already exists; pass --force to overwrite it", ignore_path.display());
+    }
+
+    let mut written = vec![config_path.display().to_string()];
+    std::fs::write(&config_path, init::config(&answers, &detected))
+        .with_context(|| format!("Failed to write {}", config_path.display()))?;
+    if answers.owoignore {
+        std::fs::write(&ignore_path, init::owoignore(&detected))
+            .with_context(|| format!("Failed to write {}", ignore_path.display()))?;
+        written.push(ignore_path.display().to_string());
+    }
+    eprintln!("Wrote {}", written.join(" and "));
+    eprintln!("\nNext steps:");
+    eprintln!("  owo pack --list    preview the files a pack would include");
+    eprintln!("  owo explain FILE   see why a file is in or out");
+    eprintln!("  owo pack           write {}", answers.output);
+    Ok(())
+}
+
+async fn merge(matches: &ArgMatches) -> Result<()> {
+    let inputs = matches
+        .get_many::<String>("inputs")
+        .unwrap()
+        .map(|path| Ok((path.clone(), read_bundle(path)?)))
+        .collect::<Result<Vec<_>>>()?;
+    let json = inputs[0].1.json;
+    let on_conflict = match matches.get_one::<String>("on_conflict").unwrap().as_str() {
+        "error" => owo::merge::OnConflict::Error,
+        "first" => owo::merge::OnConflict::First,
+        _ => owo::merge::OnConflict::Last,
+    };
+    let merged = owo::merge::merge(inputs, on_conflict)?;
+    let provenance = matches.get_flag("provenance");
+    let contents = match json {
+        true => merged.json(provenance),
+        false => merged.markdown(provenance),
+    };
+    let output = matches.get_one::<String>("output").unwrap();
+    let size = contents.len() as u64;
+    write_output(output, contents).await?;
+    for (path, kept) in &merged.conflicts {
+        report::warning(&format!("{} is in more than one bundle; kept the one from {}", path, kept));
+    }
+    eprintln!(
+        "Merged {} file(s) into {} ({})",
+        merged.bundle.files.len(),
+        output,
+        owo::summary::human_size(size)
+    );
+    Ok(())
+}
+
+fn explain(matches: &ArgMatches) -> Result<()> {
+    let common = CommonArgs::from_matches(matches);
+    let options = common
+        .options()
+        .only(cli::strings(matches, "only"))
+        .skip_generated(matches.get_flag("skip_generated"))
+        .lockfile_summary(matches.get_flag("lockfile_summary"));
+    let target = matches.get_one::<String>("target").unwrap();
+    let explanation = owo::explain::explain(&options, Path::new(target))?;
+    if matches.get_flag("json") {
+        println!("{}", serde_json::to_string_pretty(&explanation)?);
+        return Ok(());
+    }
+
+    println!("{}", explanation.path.display());
+    if !explanation.exists {
+        println!("  does not exist");
+    }
+    for step in &explanation.steps {
+        let effect = match (step.excludes, step.rule) {
+            (true, _) => "excludes",
+            (false, "ignore pattern") => "overridden",
+            (false, _) => "includes",
+        };
+        let pattern = step.pattern.as_ref().map(|p| format!(" `{}`", p)).unwrap_or_default();
+        let target = match step.applies_to == explanation.path {
+            true => String::new(),
+            false => format!(" via {}", step.applies_to.display()),
+        };
+        println!("  {}:{} ({}) {}{}", step.rule, pattern, step.source, effect, target);
+    }
+    if explanation.steps.is_empty() && explanation.exists {
+        println!("  no rule matches");
+    }
+    println!("verdict: {}", if explanation.included { "included" } else { "excluded" });
+    Ok(())
+}
+
+/// `--max-output-size`, and whether `--on-oversize truncate` was given.
+struct SizeLimit {
+    bytes: u64,
+    truncate: bool,
+}
+
+impl SizeLimit {
+    fn from_matches(matches: &ArgMatches) -> Option<Self> {
+        let bytes = *matches.get_one::<u64>("max_output_size")?;
+        Some(Self { bytes, truncate: matches.get_one::<String>("on_oversize").unwrap() == "truncate" })
+    }
+}
+
+/// Renders `bundle` as `format` for the output at `path`, held to `limit`:
+/// past it, an error, or under `truncate` only the files that fit, with the
+/// number left out recorded in `truncated`.
+fn render_within(
+    bundle: &owo::Bundle,
+    format: cli::OutputFormat,
+    path: &str,
+    limit: Option<&SizeLimit>,
+    truncated: &mut Vec<(String, usize)>,
+) -> Result<String> {
+    let contents = render(bundle, format);
+    let Some(limit) = limit.filter(|limit| contents.len() as u64 > limit.bytes) else {
+        return Ok(contents);
+    };
+    let size = owo::summary::human_size(contents.len() as u64);
+    let max = owo::summary::human_size(limit.bytes);
+    if !limit.truncate {
+        anyhow::bail!(
+            "{} would be {}, over --max-output-size {}; nothing was written (--on-oversize truncate keeps the files that fit)",
+            path,
+            size,
+            max
+        );
+    }
+    let Some((kept, omitted)) = bundle.truncated(limit.bytes, |bundle| render(bundle, format)) else {
+        anyhow::bail!("{} can't fit in --max-output-size {}, even without any file; nothing was written", path, max);
+    };
+    truncated.push((path.to_string(), omitted));
+    Ok(render(&kept, format))
+}
+
+fn render(bundle: &owo::Bundle, format: cli::OutputFormat) -> String {
+    match format {
+        cli::OutputFormat::Markdown => bundle.markdown(),
+        cli::OutputFormat::Json => bundle.json(),
+        cli::OutputFormat::Sqlite => unreachable!("SQLite outputs are written, not rendered"),
+    }
+}
+
+/// `path` as seen from the directory `base`, both relative to the same
+/// place: `../root/b.md` for `out/root/b.md` from `out/index`. Gives up and
+/// returns `path` when `base` climbs out of the shared part with `..`.
+fn relative_to(path: &Path, base: &Path) -> PathBuf {
+    let path_parts = path.components().collect::<Vec<_>>();
+    let base_parts = base.components().collect::<Vec<_>>();
+    let shared = path_parts.iter().zip(&base_parts).take_while(|(a, b)| a == b).count();
+    let climbs = &base_parts[shared..];
+    if climbs.iter().any(|part| !matches!(part, std::path::Component::Normal(_))) {
+        return path.to_path_buf();
+    }
+    climbs.iter().map(|_| Path::new("..")).chain(path_parts[shared..].iter().map(|part| Path::new(part.as_os_str()))).collect()
+}
+
+/// The name that stands for `dir` in `{dir}`: separators turned into `-`, or
+/// `root` for the files at the root, which is reserved for them. When a
+/// directory's name is already `taken`, by `index` or an earlier directory as
+/// `a/b` is by `a-b`, a `-2`, `-3`, ... suffix keeps one bundle from
+/// overwriting another.
+fn split_name(dir: &str, taken: &mut HashSet<String>) -> String {
+    if dir.is_empty() {
+        return "root".to_string();
+    }
+    let name = dir.replace(['/', '\\'], "-");
+    let mut candidates = std::iter::once(name.clone()).chain((2..).map(|n| format!("{}-{}", name, n)));
+    candidates.find(|candidate| taken.insert(candidate.clone())).expect("some suffix is free")
+}
+
+/// The outputs of `--split-by-dir`: for every target, one bundle per
+/// directory with `{dir}` in its path replaced, plus an index of them in the
+/// target's format, at `{dir}` = `index`.
+fn split_outputs(
+    bundle: &owo::Bundle,
+    depth: usize,
+    targets: &[&cli::OutputTarget],
+    limit: Option<&SizeLimit>,
+    truncated: &mut Vec<(String, usize)>,
+) -> Result<Vec<(String, String)>> {
+    let mut taken = HashSet::from(["index".to_string(), "root".to_string()]);
+    let parts = bundle
+        .split_by_dir(depth)
+        .into_iter()
+        .map(|(dir, part)| {
+            let name = split_name(&dir, &mut taken);
+            // The index lists the files at the root as `.`.
+            let dir = if dir.is_empty() { ".".to_string() } else { dir };
+            (name, dir, part)
         })
         .collect::<Vec<_>>();
 
-    let output = Arc::new(Mutex::new(String::new()));
-    let semaphore = Arc::new(Semaphore::new(num_cpus::get() * 2));
-    let mut handles = Vec::new();
-
-    for entry in walker {
-        if entry.path().is_file() {
-            let path = entry.path().to_path_buf();
-            let output = Arc::clone(&output);
-            let semaphore = Arc::clone(&semaphore);
-
-            let permit = semaphore.clone().acquire_owned().await?;
-            handles.push(tokio::spawn(async move {
-                let _permit = permit;
-                if let Ok(content) = read_file_with_fallback(&path).await {
-                    let mut output_lock = output.lock().unwrap();
-                    output_lock.push_str(&format!("\n## File: `{}`\n", path.display()));
-                    output_lock.push_str("```");
-                    if let Some(ext) = path.extension().and_then(|e| e.to_str()) {
-                        output_lock.push_str(ext);
-                    }
-                    output_lock.push_str("\n");
-                    output_lock.push_str(&content.trim_end());
-                    output_lock.push_str("\n```\n");
+    let mut outputs = Vec::new();
+    for target in targets {
+        let index_path = target.path.replace("{dir}", "index");
+        let index_dir = Path::new(&index_path).parent().unwrap_or(Path::new(""));
+        let mut entries = Vec::new();
+        for (name, dir, part) in &parts {
+            let path = target.path.replace("{dir}", name);
+            let contents = render_within(part, target.format, &path, limit, truncated)?;
+            let link = relative_to(Path::new(&path), index_dir).display().to_string();
+            entries.push((dir.as_str(), link, part.files.len(), contents.len() as u64));
+            outputs.push((path, contents));
+        }
+        let index = match target.format {
+            cli::OutputFormat::Json => {
+                let bundles = entries
+                    .iter()
+                    .map(|(dir, path, files, size)| serde_json::json!({ "dir": dir, "path": path, "files": files, "size": size }))
+                    .collect::<Vec<_>>();
+                serde_json::to_string_pretty(&serde_json::json!({ "bundles": bundles }))? + "\n"
+            }
+            cli::OutputFormat::Markdown | cli::OutputFormat::Sqlite => {
+                let mut index = "# Bundles\n\n| Directory | Bundle | Files | Size |\n| --- | --- | --- | --- |\n".to_string();
+                for (dir, path, files, size) in &entries {
+                    let size = owo::summary::human_size(*size);
+                    // A pipe ends a table cell even inside a code span.
+                    let dir = owo::code_span(dir).replace('|', "\\|");
+                    let text = path.replace('\\', "\\\\").replace('[', "\\[").replace(']', "\\]").replace('|', "\\|");
+                    let link = path.replace('%', "%25").replace(' ', "%20").replace('(', "%28").replace(')', "%29").replace('|', "%7C");
+                    index.push_str(&format!("| {} | [{}]({}) | {} | {} |\n", dir, text, link, files, size));
                 }
-            }));
+                index
+            }
+        };
+        outputs.push((index_path, index));
+    }
+    Ok(outputs)
+}
+
+/// The path's raw bytes on Unix, so names that aren't UTF-8 survive a pipe.
+#[cfg(unix)]
+fn path_bytes(path: &Path) -> std::borrow::Cow<'_, [u8]> {
+    use std::os::unix::ffi::OsStrExt;
+    path.as_os_str().as_bytes().into()
+}
+
+#[cfg(not(unix))]
+fn path_bytes(path: &Path) -> std::borrow::Cow<'_, [u8]> {
+    path.to_string_lossy().into_owned().into_bytes().into()
+}
+
+/// Prints the files `--list` covers: one per line (with sizes under `-v`),
+/// NUL-terminated with `--print0`, or as a JSON array with `--json`. Only
+/// paths go to stdout.
+fn list(common: &CommonArgs, matches: &ArgMatches, walk: &walk::Walk) -> Result<()> {
+    let size = |path: &Path| walk.size(path);
+    let mut out = std::io::BufWriter::new(std::io::stdout().lock());
+    if matches.get_flag("json") {
+        let files = walk
+            .files()
+            .map(|path| {
+                serde_json::json!({
+                    "path": path.to_string_lossy(),
+                    "size": size(path),
+                    "language": owo::fence_tag(path),
+                })
+            })
+            .collect::<Vec<_>>();
+        serde_json::to_writer_pretty(&mut out, &files)?;
+        writeln!(out)?;
+    } else {
+        let print0 = matches.get_flag("print0");
+        for path in walk.files() {
+            out.write_all(&path_bytes(path))?;
+            if print0 {
+                out.write_all(b"\0")?;
+            } else if common.verbose {
+                writeln!(out, " ({})", owo::summary::human_size(size(path)))?;
+            } else {
+                writeln!(out)?;
+            }
         }
     }
+    out.flush()?;
+    Ok(())
+}
 
-    for handle in handles {
-        let _ = handle.await;
+/// Quotes `text` for a POSIX shell when it needs it.
+fn shell_quote(text: &str) -> String {
+    let plain = |c: char| c.is_ascii_alphanumeric() || "/._-+=,:@".contains(c);
+    match !text.is_empty() && text.chars().all(plain) {
+        true => text.to_string(),
+        false => format!("'{}'", text.replace('\'', "'\\''")),
     }
+}
 
-    let final_output = output.lock().unwrap().clone();
-    fs::write(output_file, final_output)
-        .await
-        .context("Failed to write output file")?;
+/// Runs the `--interactive` picker over the files a pack would include and
+/// narrows `options` to the selection, or returns `None` when cancelled.
+fn pick(common: &CommonArgs, options: owo::Options) -> Result<Option<owo::Options>> {
+    if !std::io::stdin().is_terminal() || !std::io::stdout().is_terminal() {
+        anyhow::bail!("--interactive needs a terminal on stdin and stdout");
+    }
+    let mut walk = walk::walk(&options)?;
+    report_profiles(common, &walk.detected, &walk.profiles);
+    check_failures(common, std::mem::take(&mut walk.errors))?;
+    check_patterns(common, std::mem::take(&mut walk.unmatched_patterns), std::mem::take(&mut walk.unmatched_only))?;
+    report::sensitive(&walk.skipped);
+    let candidates = walk
+        .files()
+        .map(|path| {
+            let rel = path.strip_prefix(options.root()).unwrap_or(path);
+            interactive::Candidate {
+                rel: rel.to_string_lossy().replace('\\', "/"),
+                size: walk.size(path),
+            }
+        })
+        .collect();
 
-    println!("Successfully wrote output to {}", output_file);
-    Ok(())
+    let Some(selected) = interactive::pick(candidates).context("Failed to run the file picker")? else {
+        return Ok(None);
+    };
+    if selected.is_empty() {
+        anyhow::bail!("No files selected; no output was written");
+    }
+    let only = selected.iter().map(|rel| globset::escape(rel)).collect::<Vec<_>>();
+    let flags = only.iter().map(|glob| format!("--only {}", shell_quote(glob))).collect::<Vec<_>>();
+    eprintln!("Selection: {}", flags.join(" "));
+    Ok(Some(options.only(only)))
 }
 
-async fn read_file_with_fallback(path: &Path) -> Result<String> {
-    match fs::read_to_string(path).await {
-        Ok(content) => Ok(content),
-        Err(e) if e.kind() == std::io::ErrorKind::InvalidData => {
-            let bytes = fs::read(path).await?;
-            Ok(format!("[Binary file: {} bytes]", bytes.len()))
+async fn pack(matches: &ArgMatches) -> Result<()> {
+    let started = Instant::now();
+    let mut common = CommonArgs::from_matches(matches);
+
+    let (databases, targets): (Vec<_>, Vec<_>) = matches
+        .get_many::<cli::OutputTarget>("output")
+        .into_iter()
+        .flatten()
+        .partition(|target| target.format == cli::OutputFormat::Sqlite);
+    let usage_error = |message: String| cli::cli().error(clap::error::ErrorKind::ValueValidation, message).exit();
+    if matches.contains_id("split_by_dir") && !databases.is_empty() {
+        usage_error("--split-by-dir can't write a sqlite output".to_string());
+    }
+    if matches.contains_id("db_prune") && databases.is_empty() {
+        usage_error("--db-prune needs a sqlite output, like -o snapshots.db".to_string());
+    }
+    if matches.contains_id("split_by_dir")
+        && let Some(target) = targets.iter().find(|target| !target.path.contains("{dir}"))
+    {
+        cli::cli()
+            .error(
+                clap::error::ErrorKind::ValueValidation,
+                format!("--split-by-dir needs a {{dir}} placeholder in every --output, like bundle-{{dir}}.md; got '{}'", target.path),
+            )
+            .exit();
+    }
+    let remote = match matches.get_one::<String>("remote") {
+        Some(_) if matches.value_source("directory") == Some(clap::parser::ValueSource::CommandLine) => {
+            usage_error("--remote takes the place of PATH; give one or the other".to_string())
         }
-        Err(e) => Err(e.into()),
+        Some(url) => Some(url.clone()),
+        None => remote::is_git_url(&common.directory).then(|| common.directory.clone()),
+    };
+    if remote.is_none() && matches.contains_id("rev") {
+        usage_error("--rev needs a git URL, as PATH or with --remote".to_string());
+    }
+    if remote.is_some() && ["list", "check_patterns", "interactive"].iter().any(|id| matches.get_flag(id)) {
+        usage_error(
+            "--list, --check-patterns and --interactive don't work with a git URL; clone it and run owo there"
+                .to_string(),
+        );
+    }
+    if matches.get_flag("check_patterns") {
+        let patterns = walk::compile_patterns(&common.options())?;
+        print_patterns(&patterns)?;
+        if !common.quiet {
+            eprintln!("{} pattern(s) are valid", patterns.len());
+        }
+        return Ok(());
+    }
+    let patched = matches.get_one::<String>("from_patch").map(|path| patch_paths(path)).transpose()?;
+    // Dropping the clone removes it, whichever way the pack ends.
+    let checkout = match &remote {
+        Some(url) => Some(remote::clone(url, matches.get_one::<String>("rev").map(String::as_str)).await?),
+        None => None,
+    };
+    if let Some(checkout) = &checkout {
+        common.directory = checkout.dir.to_string_lossy().into_owned();
+    }
+
+    let fail_on_secret = matches.get_flag("fail_on_secret");
+    let secret_report = matches.get_one::<String>("secret_report");
+    let options = common
+        .options()
+        .lockfile_summary(matches.get_flag("lockfile_summary"))
+        .redact(matches.get_flag("redact"))
+        .redact_patterns(cli::strings(matches, "redact_pattern"))
+        .redact_entropy(matches.get_flag("redact_entropy"))
+        .secret_allow(cli::strings(matches, "secret_allow"))
+        .scan_secrets(fail_on_secret || secret_report.is_some())
+        .strip_comments(matches.get_flag("strip_comments"))
+        .keep_doc_comments(matches.get_flag("keep_doc_comments"))
+        .strip_license_headers(matches.get_flag("strip_license_headers"))
+        .skip_generated(matches.get_flag("skip_generated") && !matches.get_flag("include_generated"))
+        .squeeze_blank_lines(matches.get_one::<usize>("squeeze_blank_lines").copied())
+        .trim_trailing_whitespace(matches.get_flag("trim_trailing_whitespace"))
+        .strip_ansi(matches.get_flag("strip_ansi"))
+        .wrap(matches.get_one::<u64>("wrap").map(|&columns| columns as usize))
+        .wrap_marker(matches.get_one::<String>("wrap_marker").unwrap())
+        .expand_tabs(matches.get_one::<usize>("expand_tabs").copied())
+        .dedent(matches.get_flag("dedent"))
+        .lossy(matches.get_flag("lossy"))
+        .timings(matches.get_flag("timings") || matches.contains_id("timings_json"))
+        .only(match &patched {
+            Some(paths) => patched_only(paths, &cli::strings(matches, "only"))?,
+            None => cli::strings(matches, "only"),
+        })
+        .grep(cli::strings(matches, "grep"))
+        .grep_context(matches.get_one::<usize>("grep_context").copied())
+        .grep_exclude(cli::strings(matches, "grep_exclude"))
+        .grep_exclude_full(matches.get_flag("grep_exclude_full"))
+        .filter_cmds(cli::strings(matches, "filter_cmd"))
+        .filter_timeout(Duration::from_secs(*matches.get_one::<u64>("filter_timeout").unwrap()))
+        .filter_max_output(*matches.get_one::<usize>("filter_max_output").unwrap())
+        .collapsible(matches.get_one::<String>("collapsible").map(|mode| match mode.as_str() {
+            "summary" => owo::Collapsible::Summary,
+            _ => owo::Collapsible::KeepHeading,
+        }))
+        .group_by(match matches.get_one::<String>("group_by").unwrap().as_str() {
+            "dir" => owo::GroupBy::Dir,
+            "language" => owo::GroupBy::Language,
+            _ => owo::GroupBy::None,
+        });
+    let options = match (matches.get_one::<String>("header_file"), matches.get_one::<String>("header_text")) {
+        (Some(path), _) => options.header_file(path),
+        (None, Some(text)) => options.header_text(text),
+        (None, None) => options,
+    };
+    let options = match (matches.get_one::<String>("footer_file"), matches.get_one::<String>("footer_text")) {
+        (Some(path), _) => options.footer_file(path),
+        (None, Some(text)) => options.footer_text(text),
+        (None, None) => options,
+    };
+
+    if matches.get_flag("list") {
+        let mut walk = walk::walk(&options)?;
+        report_profiles(&common, &walk.detected, &walk.profiles);
+        check_failures(&common, std::mem::take(&mut walk.errors))?;
+        check_patterns(
+            &common,
+            std::mem::take(&mut walk.unmatched_patterns),
+            std::mem::take(&mut walk.unmatched_only),
+        )?;
+        report::sensitive(&walk.skipped);
+        return list(&common, matches, &walk);
+    }
+
+    let options = match matches.get_flag("interactive") {
+        true => match pick(&common, options)? {
+            Some(options) => options,
+            None => {
+                eprintln!("Cancelled; no output was written");
+                return Ok(());
+            }
+        },
+        false => options,
+    };
+
+    if common.jobs.is_none()
+        && !common.quiet
+        && let Some(fstype) = owo::network_filesystem(options.root())
+    {
+        eprintln!("{} is on a network file system ({}); --sequential may read it faster", common.directory, fstype);
+    }
+
+    let mut bundle = owo::generate(&options).await?;
+    let permalink_base = matches.get_one::<String>("permalink_base");
+    if matches.get_flag("permalinks") || permalink_base.is_some() {
+        match permalink::annotate(&mut bundle, permalink_base.map(String::as_str)).await {
+            Ok(true) => report::warning(&format!(
+                "{} has uncommitted changes; permalinks point at HEAD and may not match the packed content",
+                common.directory
+            )),
+            Ok(false) => {}
+            Err(e) => report::warning(&format!("{:#}; no permalinks were added", e)),
+        }
+    }
+    if let Some(checkout) = &checkout {
+        bundle.rebase(Path::new(checkout.name()));
+        bundle.header = Some(match bundle.header.take() {
+            Some(header) => format!("{}\n\n{}", checkout.provenance(), header),
+            None => checkout.provenance(),
+        });
+    }
+    report_profiles(&common, &bundle.detected_profiles, &bundle.profiles);
+    for warning in &bundle.summary.warnings {
+        report::warning(warning);
     }
-}
\ No newline at end of file
+    check_failures(&common, bundle_failures(&common, &mut bundle))?;
+    check_patterns(
+        &common,
+        std::mem::take(&mut bundle.unmatched_patterns),
+        std::mem::take(&mut bundle.unmatched_only),
+    )?;
+    report::sensitive(&bundle.summary.skipped);
+
+    if let Some(report) = secret_report {
+        let json = serde_json::to_string_pretty(&serde_json::json!({ "findings": bundle.findings }))?;
+        fs::write(report, json)
+            .await
+            .context("Failed to write secret report")?;
+    }
+
+    if fail_on_secret && !bundle.findings.is_empty() {
+        for finding in &bundle.findings {
+            eprintln!("{}:{}: {}", finding.path.display(), finding.line, finding.detector);
+        }
+        anyhow::bail!(
+            "Found {} secret(s); no output was written",
+            bundle.findings.len()
+        );
+    }
+
+    let limit = SizeLimit::from_matches(matches);
+    let mut truncated = Vec::new();
+    let rendering = Instant::now();
+    let outputs = match matches.get_one::<u64>("split_by_dir") {
+        Some(&depth) => split_outputs(&bundle, depth as usize, &targets, limit.as_ref(), &mut truncated)?,
+        None => {
+            // Each format is rendered once, however many targets use it.
+            let mut rendered = HashMap::new();
+            let mut outputs = Vec::new();
+            for target in &targets {
+                let contents = match rendered.get(&target.format) {
+                    Some(contents) => contents,
+                    None => {
+                        let contents = render_within(&bundle, target.format, &target.path, limit.as_ref(), &mut truncated)?;
+                        rendered.entry(target.format).or_insert(contents)
+                    }
+                };
+                outputs.push((target.path.clone(), contents.clone()));
+            }
+            outputs
+        }
+    };
+    let render = rendering.elapsed();
+
+    // A target that can't be written doesn't stop the others.
+    let mut written = Vec::new();
+    let mut failed = None;
+    let writing = Instant::now();
+    for (path, contents) in outputs {
+        let size = contents.len() as u64;
+        // A {dir} placeholder may name directories that don't exist yet.
+        if matches.contains_id("split_by_dir")
+            && let Some(parent) = Path::new(&path).parent()
+            && !parent.as_os_str().is_empty()
+            && let Err(source) = fs::create_dir_all(parent).await
+        {
+            let e = owo::Error::Output { path: parent.to_path_buf(), source }.into();
+            match failed {
+                None => failed = Some(e),
+                Some(_) => report::error(&format!("{:#}", e)),
+            }
+            continue;
+        }
+        match write_output(&path, contents).await {
+            Ok(()) => written.push((path, size)),
+            Err(e) if failed.is_none() => failed = Some(e),
+            Err(e) => report::error(&format!("{:#}", e)),
+        }
+    }
+    let command_line = std::env::args().skip(1).collect::<Vec<_>>();
+    let prune = matches.get_one::<u64>("db_prune").map(|&keep| keep as usize);
+    for database in databases {
+        match sqlite::write(Path::new(&database.path), &bundle, &command_line, prune) {
+            Ok(size) => written.push((database.path.clone(), size)),
+            Err(e) if failed.is_none() => failed = Some(e),
+            Err(e) => report::error(&format!("{:#}", e)),
+        }
+    }
+    let write = writing.elapsed();
+    if !common.quiet && !written.is_empty() {
+        let files = bundle.files.len();
+        report::summary(&mut bundle.summary, files, &written, started.elapsed(), common.verbose);
+        if let Some(limit) = &limit {
+            report::size_limit(limit.bytes, &truncated);
+        }
+        let root = Path::new(&common.directory);
+        let missing = patched.iter().flatten().filter(|path| root.join(path).symlink_metadata().is_err());
+        report::missing_from_patch(&missing.collect::<Vec<_>>());
+    }
+    if let Some(timings) = &bundle.summary.timings {
+        if matches.get_flag("timings") {
+            report::timings(timings, render, write);
+        }
+        if let Some(path) = matches.get_one::<String>("timings_json") {
+            let json = serde_json::to_string_pretty(&timings_json(timings, render, write))?;
+            fs::write(path, json).await.context("Failed to write the timings report")?;
+        }
+    }
+    failed.map_or(Ok(()), Err)
+}
+
+/// The `--timings-json` report. Durations are in seconds.
+fn timings_json(timings: &owo::summary::Timings, render: Duration, write: Duration) -> serde_json::Value {
+    let seconds = |duration: Duration| duration.as_secs_f64();
+    let slowest = timings.slowest(10).into_iter().map(|read| {
+        serde_json::json!({ "path": read.path.to_string_lossy(), "seconds": seconds(read.duration), "size": read.size })
+    });
+    serde_json::json!({
+        "walk": { "seconds": seconds(timings.walk), "entries": timings.entries },
+        "read": {
+            "seconds": seconds(timings.reading),
+            "files": timings.reads.len(),
+            "bytes": timings.bytes(),
+            "bytes_per_second": match seconds(timings.reading) {
+                0.0 => 0.0,
+                reading => timings.bytes() as f64 / reading,
+            },
+            "p50": seconds(timings.percentile(50)),
+            "p90": seconds(timings.percentile(90)),
+            "p99": seconds(timings.percentile(99)),
+            "max": seconds(timings.percentile(100)),
+        },
+        "render": { "seconds": seconds(render) },
+        "write": { "seconds": seconds(write) },
+        "slowest": slowest.collect::<Vec<_>>(),
+    })
+}
@@ -0,0 +1,73 @@
+use clap::Command;
+use clap_mangen::Man;
+use std::io::{self, Write};
+
+fn roff_escape(text: &str) -> String {
+    text.replace('\\', "\\\\").replace('-', "\\-")
+}
+
+/// Renders the `owo.1` man page from the clap definition, with the examples
+/// and extra help sections that `--help` shows. The options listed are those
+/// of `default`, the subcommand a bare `owo` runs.
+pub fn render(
+    cmd: Command,
+    default: Command,
+    examples: &[&str],
+    sections: &[(&str, &str)],
+    w: &mut dyn Write,
+) -> io::Result<()> {
+    let man = Man::new(cmd);
+    man.render_title(w)?;
+    man.render_name_section(w)?;
+    man.render_synopsis_section(w)?;
+    man.render_description_section(w)?;
+    Man::new(default).render_options_section(w)?;
+    man.render_subcommands_section(w)?;
+
+    writeln!(w, ".SH EXAMPLES")?;
+    for example in examples {
+        writeln!(w, ".TP")?;
+        writeln!(w, "\\fB{}\\fR", roff_escape(example))?;
+    }
+
+    for (title, body) in sections {
+        writeln!(w, ".SH {}", title.to_uppercase())?;
+        for line in body.lines() {
+            writeln!(w, "{}", roff_escape(line))?;
+        }
+    }
+
+    man.render_version_section(w)?;
+    man.render_authors_section(w)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::cli;
+
+    fn page() -> String {
+        let mut out = Vec::new();
+        super::render(cli::cli(), cli::pack_command(), cli::EXAMPLES, cli::HELP_SECTIONS, &mut out).unwrap();
+        String::from_utf8(out).unwrap()
+    }
+
+    #[test]
+    fn has_the_key_sections() {
+        let page = page();
+        let sections = page.lines().filter_map(|line| line.strip_prefix(".SH ")).collect::<Vec<_>>();
+        for section in ["NAME", "SYNOPSIS", "DESCRIPTION", "OPTIONS", "SUBCOMMANDS", "EXAMPLES", "EXIT STATUS"] {
+            assert!(sections.contains(&section), "no {} section in {:?}", section, sections);
+        }
+        assert!(page.contains(".TH owo 1"), "no title");
+    }
+
+    #[test]
+    fn lists_pack_options_and_every_example() {
+        let page = page();
+        assert!(page.contains("\\-\\-with\\-dotfiles"));
+        assert!(page.contains("\\-\\-output"));
+        for example in cli::EXAMPLES {
+            assert!(page.contains(&super::roff_escape(example)), "missing example {}", example);
+        }
+    }
+}
@@ -0,0 +1,161 @@
+use crate::summary::human_size;
+use crate::{FileContent, FileEntry};
+
+/// How file sections are laid out in the Markdown output.
+#[derive(Debug, Clone, Default)]
+pub struct MarkdownStyle {
+    /// Wraps each file's code block in a `<details>` block.
+    pub collapsible: Option<Collapsible>,
+    /// Puts the files under a top-level heading per directory or language.
+    pub group_by: GroupBy,
+}
+
+/// How the files of a bundle are grouped under top-level headings.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub enum GroupBy {
+    /// No headings: the files in walk order.
+    #[default]
+    None,
+    /// A `# Directory:` heading per directory, in path order.
+    Dir,
+    /// A `# Language:` heading per fence tag, in tag order, and files without
+    /// one under `# Other` at the end.
+    Language,
+}
+
+/// What a collapsible file section shows while it's collapsed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Collapsible {
+    /// The `## File:` heading stays above the `<details>` block.
+    KeepHeading,
+    /// The `<summary>` line replaces the heading.
+    Summary,
+}
+
+/// `text` as a Markdown code span that shows it exactly: fenced by more
+/// backticks than any run inside it, and padded with a space on each side
+/// when it starts or ends with a backtick, or with a space on both ends,
+/// which Markdown would otherwise trim.
+pub fn code_span(text: &str) -> String {
+    let longest = text.split(|c| c != '`').map(str::len).max().unwrap_or(0);
+    let fence = "`".repeat(longest + 1);
+    let all_spaces = text.chars().all(|c| c == ' ');
+    let pad = !all_spaces
+        && (text.starts_with('`') || text.ends_with('`') || (text.starts_with(' ') && text.ends_with(' ')));
+    match pad {
+        true => format!("{} {} {}", fence, text, fence),
+        false => format!("{}{}{}", fence, text, fence),
+    }
+}
+
+fn escape_html(text: &str) -> String {
+    text.replace('&', "&amp;").replace('<', "&lt;").replace('>', "&gt;")
+}
+
+impl FileEntry {
+    /// ` (lines 120–240, 300–310)` for a file cut to line ranges, or nothing.
+    fn line_note(&self) -> String {
+        if self.lines.is_empty() {
+            return String::new();
+        }
+        let ranges = self.lines.iter().map(|(start, end)| format!("{}–{}", start, end)).collect::<Vec<_>>();
+        format!(" (lines {})", ranges.join(", "))
+    }
+
+    /// Renders the entry as a `## File:` heading followed by a fenced code
+    /// block, laid out per `style`.
+    pub fn markdown(&self, style: &MarkdownStyle) -> String {
+        let body = match &self.content {
+            FileContent::Text(text) => text.trim_end().to_string(),
+            FileContent::Binary(len) => format!("[Binary file: {} bytes]", len),
+        };
+        let mut fence = format!("```{}\n{}\n```\n", self.fence_tag.as_deref().unwrap_or(""), body);
+        if self.changed {
+            fence.insert_str(0, "> ⚠ file changed during generation\n\n");
+        }
+        if self.invalid_utf8 > 0 {
+            let sequences = if self.invalid_utf8 == 1 { "sequence" } else { "sequences" };
+            let note = format!("(contained {} invalid UTF-8 {}, shown as \u{FFFD})\n\n", self.invalid_utf8, sequences);
+            fence.insert_str(0, &note);
+        }
+        let link = match &self.permalink {
+            Some(url) => format!(" ([permalink]({}))", url),
+            None => String::new(),
+        };
+        let path = code_span(&self.path.display().to_string());
+        let heading = format!("\n## File: {}{}{}\n", path, self.line_note(), link);
+
+        let Some(collapsible) = style.collapsible else {
+            return heading + &fence;
+        };
+        let details = match &self.content {
+            FileContent::Text(text) => {
+                let lines = text.lines().count();
+                format!("{}, {} line{}", human_size(text.len() as u64), lines, if lines == 1 { "" } else { "s" })
+            }
+            FileContent::Binary(len) => format!("{}, binary", human_size(*len as u64)),
+        };
+        // HTML blocks end at a blank line, and a fence only renders inside
+        // <details> when blank lines separate it from the tags.
+        let link = match &self.permalink {
+            Some(url) => format!(" <a href=\"{}\">permalink</a>", escape_html(url).replace('"', "&quot;")),
+            None => String::new(),
+        };
+        let summary = format!(
+            "<details>\n<summary><code>{}</code>{} ({}){}</summary>\n\n",
+            escape_html(&self.path.display().to_string()),
+            self.line_note(),
+            details,
+            link
+        );
+        let heading = match collapsible {
+            Collapsible::KeepHeading => heading + "\n",
+            Collapsible::Summary => "\n".to_string(),
+        };
+        format!("{}{}{}\n</details>\n", heading, summary, fence)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::PathBuf;
+
+    #[test]
+    fn code_spans_show_text_exactly() {
+        for (text, span) in [
+            ("a.rs", "`a.rs`"),
+            ("a`b", "``a`b``"),
+            ("``tick", "``` ``tick ```"),
+            ("tick`", "`` tick` ``"),
+            ("x | y", "`x | y`"),
+            ("#notes", "`#notes`"),
+            (" lead", "` lead`"),
+            ("trail ", "`trail `"),
+            (" both ", "`  both  `"),
+            ("  ", "`  `"),
+        ] {
+            assert_eq!(code_span(text), span, "{:?}", text);
+        }
+    }
+
+    #[test]
+    fn headings_parse_back_to_their_paths() {
+        let names = ["a`b.txt", "``tick", "x | y.md", "#notes.md", "a # b", " lead.txt", "trail.txt ", " both "];
+        for name in names {
+            let entry = FileEntry {
+                path: PathBuf::from(name),
+                fence_tag: None,
+                content: FileContent::Text("x\n".to_string()),
+                size: 2,
+                modified: None,
+                lines: Vec::new(),
+                changed: false,
+                invalid_utf8: 0,
+                permalink: None,
+            };
+            let parsed = crate::parse::parse(&entry.markdown(&MarkdownStyle::default())).unwrap();
+            assert_eq!(parsed.files.iter().map(|file| file.path.as_str()).collect::<Vec<_>>(), [name]);
+        }
+    }
+}
@@ -0,0 +1,107 @@
+//! Packing files held in memory, for builds without the file system, like
+//! WebAssembly in a browser.
+
+use crate::bundle::{Step, Transformer};
+use crate::options::Insert;
+use crate::paths::enclosed;
+use crate::walk::{self, ListFilter};
+use crate::{profiles, Bundle, Error, FileContent, FileEntry, Options, Result, GREP_EXCLUDE_HEAD};
+use std::collections::BTreeMap;
+use std::path::Path;
+
+/// Packs `files`, each a path relative to the options' root and its bytes,
+/// as [`generate`](crate::generate) packs a directory: the ignore patterns,
+/// profiles, dotfile rules and every other filter apply, in walk order, and
+/// profiles are detected from the files at the top. Bytes that aren't valid
+/// UTF-8 make a binary file, unless [`Options::lossy`] keeps them as text.
+/// Paths that are absolute or climb out with `..` are skipped and reported.
+///
+/// There are no .gitignore files, and filter commands can't run, so giving
+/// any is an [`Error::Filter`]. A header or footer file is read from disk.
+pub fn bundle_files<P, B>(files: impl IntoIterator<Item = (P, B)>, options: &Options) -> Result<Bundle>
+where
+    P: AsRef<Path>,
+    B: AsRef<[u8]>,
+{
+    if let Some(spec) = options.filter_cmds.first() {
+        let reason = "filter commands can't run on files in memory".to_string();
+        return Err(Error::Filter { spec: spec.clone(), reason });
+    }
+    let mut skipped = Vec::new();
+    let mut sorted = BTreeMap::new();
+    for (path, bytes) in files {
+        let Some(rel) = enclosed(path.as_ref()) else {
+            skipped.push((options.root.join(path.as_ref()), "unsafe path".to_string()));
+            continue;
+        };
+        sorted.insert(options.root.join(rel), bytes);
+    }
+    let names = sorted
+        .keys()
+        .filter_map(|path| path.strip_prefix(&options.root).ok())
+        .filter(|rel| rel.components().count() == 1)
+        .map(|rel| rel.to_string_lossy().into_owned())
+        .collect::<Vec<_>>();
+    let (detected, profiles) = walk::resolve_profiles(options, || profiles::detect_names(&names))?;
+    let mut filter = ListFilter::new(options, &profiles)?;
+    let transformer = Transformer::new(options)?;
+
+    let mut entries = Vec::new();
+    for (path, bytes) in sorted {
+        let bytes = bytes.as_ref();
+        if !filter.keeps(&path, false, bytes) {
+            continue;
+        }
+        let head = &bytes[..bytes.len().min(GREP_EXCLUDE_HEAD as usize)];
+        if transformer.checks_head() && transformer.excludes_head(&path, head) {
+            continue;
+        }
+        let (content, invalid_utf8) = FileContent::decode(bytes.to_vec(), options.lossy);
+        let file = match transformer.select(path, bytes.len() as u64, None, content) {
+            Step::Done(file) => file,
+            Step::Text(file) => Some(transformer.finish(file)),
+        };
+        entries.extend(file.map(|file| FileEntry { invalid_utf8, ..file }));
+    }
+
+    let mut findings = std::mem::take(&mut *transformer.findings.lock().unwrap());
+    findings.sort_by(|a, b| a.path.cmp(&b.path).then(a.line.cmp(&b.line)));
+    let mut summary = std::mem::take(&mut *transformer.summary.lock().unwrap());
+    summary.skipped.append(&mut skipped);
+    summary.skipped.append(&mut filter.skipped);
+    let failures = std::mem::take(&mut *transformer.failures.lock().unwrap());
+    let (unmatched_patterns, unmatched_only) = filter.unmatched_patterns();
+    Ok(Bundle {
+        root: options.root.clone(),
+        header: read_insert(options.header.as_ref())?,
+        files: entries,
+        footer: read_insert(options.footer.as_ref())?,
+        findings,
+        summary,
+        detected_profiles: detected,
+        profiles,
+        style: options.style.clone(),
+        failures,
+        unmatched_patterns,
+        unmatched_only,
+    })
+}
+
+/// The Markdown document [`bundle_files`] packs `files` into.
+pub fn render_files<P, B>(files: impl IntoIterator<Item = (P, B)>, options: &Options) -> Result<String>
+where
+    P: AsRef<Path>,
+    B: AsRef<[u8]>,
+{
+    bundle_files(files, options).map(|bundle| bundle.markdown())
+}
+
+fn read_insert(insert: Option<&Insert>) -> Result<Option<String>> {
+    match insert {
+        None => Ok(None),
+        Some(Insert::Text(text)) => Ok(Some(text.clone())),
+        Some(Insert::File(path)) => std::fs::read_to_string(path)
+            .map(Some)
+            .map_err(|source| Error::Read { path: path.clone(), source }),
+    }
+}
@@ -0,0 +1,130 @@
+//! Combining several bundles into one.
+
+use crate::parse::ParsedBundle;
+use crate::{Bundle, Error, FileContent, FileEntry, MarkdownStyle, Result};
+use std::collections::HashMap;
+use std::path::PathBuf;
+
+/// Which section wins when two inputs have a file with the same path.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum OnConflict {
+    /// Fail with [`Error::MergeConflict`].
+    Error,
+    /// Keep the first input's section.
+    First,
+    /// Keep the last input's section, where the first one was.
+    #[default]
+    Last,
+}
+
+/// The combined bundle, with the input each file came from.
+pub struct Merged {
+    pub bundle: Bundle,
+    /// The input of each of `bundle.files`, by index.
+    pub sources: Vec<String>,
+    /// Paths that more than one input had, with the input that was kept.
+    pub conflicts: Vec<(String, String)>,
+}
+
+/// Merges `inputs`, each a name and a parsed bundle, in order. The header
+/// is the first input's that has one, and the footer likewise the last's;
+/// the layout is the first input's. Inputs must all be Markdown or all JSON.
+pub fn merge(inputs: Vec<(String, ParsedBundle)>, on_conflict: OnConflict) -> Result<Merged> {
+    if let Some((first, _)) = inputs.first()
+        && let Some((other, _)) = inputs.iter().find(|(_, bundle)| bundle.json != inputs[0].1.json)
+    {
+        return Err(Error::MergeFormats { first: first.clone(), other: other.clone() });
+    }
+    let style = MarkdownStyle {
+        collapsible: inputs.first().and_then(|(_, bundle)| bundle.collapsible),
+        ..MarkdownStyle::default()
+    };
+    let header = inputs.iter().find_map(|(_, bundle)| bundle.header.clone());
+    let footer = inputs.iter().rev().find_map(|(_, bundle)| bundle.footer.clone());
+
+    let mut files: Vec<FileEntry> = Vec::new();
+    let mut sources: Vec<String> = Vec::new();
+    let mut seen = HashMap::new();
+    let mut conflicts = Vec::new();
+    for (name, bundle) in inputs {
+        for file in bundle.files {
+            let entry = FileEntry {
+                path: PathBuf::from(&file.path),
+                fence_tag: file.fence_tag,
+                size: match &file.content {
+                    FileContent::Text(text) => text.len() as u64,
+                    FileContent::Binary(size) => *size as u64,
+                },
+                content: file.content,
+                modified: None,
+                lines: file.lines,
+                changed: false,
+                invalid_utf8: 0,
+                permalink: None,
+            };
+            // Bundles of `.` write `./src/..`, others `src/..`.
+            let key = file.path.strip_prefix("./").unwrap_or(&file.path).to_string();
+            let Some(&index) = seen.get(&key) else {
+                seen.insert(key, files.len());
+                files.push(entry);
+                sources.push(name.clone());
+                continue;
+            };
+            match on_conflict {
+                OnConflict::Error => {
+                    return Err(Error::MergeConflict { path: file.path, first: sources[index].clone(), other: name });
+                }
+                OnConflict::First => conflicts.push((file.path, sources[index].clone())),
+                OnConflict::Last => {
+                    files[index] = entry;
+                    sources[index] = name.clone();
+                    conflicts.push((file.path, name.clone()));
+                }
+            }
+        }
+    }
+
+    let bundle = Bundle::from_files(PathBuf::new(), header, files, footer, style);
+    Ok(Merged { bundle, sources, conflicts })
+}
+
+impl Merged {
+    /// The merged bundle as Markdown; with `provenance`, each file section
+    /// is preceded by an HTML comment naming its input.
+    pub fn markdown(&self, provenance: bool) -> String {
+        if !provenance {
+            return self.bundle.markdown();
+        }
+        let mut out = String::new();
+        if let Some(header) = &self.bundle.header {
+            out.push_str(header);
+            out.push('\n');
+        }
+        for (file, source) in self.bundle.files.iter().zip(&self.sources) {
+            out.push_str(&format!("\n<!-- from {} -->", source.replace("--", "-\\-")));
+            out.push_str(&file.markdown(&self.bundle.style));
+        }
+        if let Some(footer) = &self.bundle.footer {
+            out.push('\n');
+            out.push_str(footer);
+            out.push('\n');
+        }
+        out
+    }
+
+    /// The merged bundle as JSON; with `provenance`, every file gets a
+    /// `source` field naming its input.
+    pub fn json(&self, provenance: bool) -> String {
+        let json = self.bundle.json();
+        if !provenance {
+            return json;
+        }
+        let mut value: serde_json::Value = serde_json::from_str(&json).expect("bundle JSON always parses");
+        if let Some(files) = value["files"].as_array_mut() {
+            for (file, source) in files.iter_mut().zip(&self.sources) {
+                file["source"] = serde_json::Value::from(source.as_str());
+            }
+        }
+        serde_json::to_string_pretty(&value).expect("JSON values always serialize") + "\n"
+    }
+}
@@ -0,0 +1,541 @@
+use crate::markdown::{Collapsible, GroupBy, MarkdownStyle};
+use crate::TestFiles;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+/// Patterns ignored when none are given, matching the CLI's `--ignore` default.
+pub const DEFAULT_IGNORE: &str = "obj|bin|build|dist|.env|.env.*";
+
+/// Version control metadata, left out at any depth whatever the ignore
+/// patterns say: the directories, and the `.git` file a submodule or
+/// worktree has instead.
+pub const VCS_INTERNALS: &[&str] = &[".git", ".hg", ".svn"];
+
+/// Text placed verbatim before or after the file sections.
+#[derive(Debug, Clone)]
+pub(crate) enum Insert {
+    Text(String),
+    /// Read when generating, and never packed as a regular file.
+    File(PathBuf),
+}
+
+/// What to do with a file that changed between the walk and the read, or
+/// while it was read.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum OnChange {
+    /// Read it once more, and mark it only if it changed again.
+    #[default]
+    Reread,
+    /// Mark its section with a warning and list it in the summary.
+    Mark,
+}
+
+/// What to walk and how to transform each file, built up with chained setters:
+///
+/// ```no_run
+/// # async fn run() -> anyhow::Result<()> {
+/// let options = owo::Options::new("my-repo").with_dotfiles(true).redact(true);
+/// let bundle = owo::generate(&options).await?;
+/// println!("{}", bundle.markdown());
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug, Clone)]
+pub struct Options {
+    pub(crate) root: PathBuf,
+    pub(crate) ignore: Vec<String>,
+    /// Whether `ignore` is still [`DEFAULT_IGNORE`].
+    pub(crate) default_ignore: bool,
+    pub(crate) with_dotfiles: bool,
+    pub(crate) keep_dotfiles: Vec<String>,
+    pub(crate) sensitive: Vec<String>,
+    pub(crate) allow_sensitive: Vec<String>,
+    pub(crate) include_vcs_internals: bool,
+    pub(crate) only: Vec<String>,
+    pub(crate) tests: TestFiles,
+    pub(crate) owners: Vec<String>,
+    pub(crate) grep: Vec<String>,
+    pub(crate) grep_context: Option<usize>,
+    pub(crate) grep_exclude: Vec<String>,
+    pub(crate) grep_exclude_full: bool,
+    pub(crate) include_unowned: bool,
+    pub(crate) profiles: Vec<String>,
+    pub(crate) auto_profile: bool,
+    pub(crate) include_lockfiles: bool,
+    pub(crate) lockfile_summary: bool,
+    pub(crate) redact: bool,
+    pub(crate) redact_patterns: Vec<String>,
+    pub(crate) redact_entropy: bool,
+    pub(crate) secret_allow: Vec<String>,
+    pub(crate) scan_secrets: bool,
+    pub(crate) strip_comments: bool,
+    pub(crate) keep_doc_comments: bool,
+    pub(crate) strip_license_headers: bool,
+    pub(crate) skip_generated: bool,
+    pub(crate) squeeze_blank_lines: Option<usize>,
+    pub(crate) trim_trailing_whitespace: bool,
+    pub(crate) strip_ansi: bool,
+    pub(crate) wrap: Option<usize>,
+    pub(crate) wrap_marker: String,
+    pub(crate) expand_tabs: Option<usize>,
+    pub(crate) dedent: bool,
+    pub(crate) filter_cmds: Vec<String>,
+    pub(crate) filter_timeout: Duration,
+    pub(crate) filter_max_output: usize,
+    pub(crate) lossy: bool,
+    pub(crate) on_change: OnChange,
+    pub(crate) jobs: Option<usize>,
+    pub(crate) walk_threads: Option<usize>,
+    pub(crate) read_timeout: Option<Duration>,
+    pub(crate) timings: bool,
+    pub(crate) header: Option<Insert>,
+    pub(crate) footer: Option<Insert>,
+    pub(crate) style: MarkdownStyle,
+}
+
+impl Options {
+    /// Options for walking `root` with the CLI's defaults: the default ignore
+    /// patterns, no dotfiles, detected profiles, lockfiles skipped and no
+    /// transformations.
+    pub fn new(root: impl Into<PathBuf>) -> Self {
+        Self {
+            root: root.into(),
+            ignore: DEFAULT_IGNORE.split('|').map(str::to_string).collect(),
+            default_ignore: true,
+            with_dotfiles: false,
+            keep_dotfiles: Vec::new(),
+            sensitive: Vec::new(),
+            allow_sensitive: Vec::new(),
+            include_vcs_internals: false,
+            only: Vec::new(),
+            tests: TestFiles::Include,
+            owners: Vec::new(),
+            grep: Vec::new(),
+            grep_context: None,
+            grep_exclude: Vec::new(),
+            grep_exclude_full: false,
+            include_unowned: false,
+            profiles: Vec::new(),
+            auto_profile: true,
+            include_lockfiles: false,
+            lockfile_summary: false,
+            redact: false,
+            redact_patterns: Vec::new(),
+            redact_entropy: false,
+            secret_allow: Vec::new(),
+            scan_secrets: false,
+            strip_comments: false,
+            keep_doc_comments: false,
+            strip_license_headers: false,
+            skip_generated: false,
+            squeeze_blank_lines: None,
+            trim_trailing_whitespace: false,
+            strip_ansi: false,
+            wrap: None,
+            wrap_marker: "↩".to_string(),
+            expand_tabs: None,
+            dedent: false,
+            filter_cmds: Vec::new(),
+            filter_timeout: Duration::from_secs(10),
+            filter_max_output: 16 * 1024 * 1024,
+            lossy: false,
+            on_change: OnChange::default(),
+            jobs: None,
+            walk_threads: None,
+            read_timeout: None,
+            timings: false,
+            header: None,
+            footer: None,
+            style: MarkdownStyle::default(),
+        }
+    }
+
+    /// The directory to walk.
+    pub fn root(&self) -> &Path {
+        &self.root
+    }
+
+    /// Replaces the ignore patterns. Each is a gitignore-style glob; a leading
+    /// `!` re-includes paths an earlier pattern excluded.
+    pub fn ignore<I, S>(mut self, patterns: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        self.ignore = patterns.into_iter().map(Into::into).collect();
+        self.default_ignore = false;
+        self
+    }
+
+    /// Includes hidden files and directories: a leading dot, or on Windows
+    /// the hidden or system attribute.
+    pub fn with_dotfiles(mut self, yes: bool) -> Self {
+        self.with_dotfiles = yes;
+        self
+    }
+
+    /// Keeps the hidden files and directories these globs match while the
+    /// rest stay out. A glob without a `/`, like `.gitignore`, matches names
+    /// at any depth; one with a `/`, like `.github/workflows`, matches paths
+    /// from the root and keeps the hidden directories above them, too. What's
+    /// inside a kept directory is kept unless it's hidden itself. Ignore
+    /// patterns and .gitignore files still apply.
+    pub fn keep_dotfiles<I, S>(mut self, globs: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        self.keep_dotfiles = globs.into_iter().map(Into::into).collect();
+        self
+    }
+
+    /// Withholds the files these globs match on top of
+    /// [`SENSITIVE_NAMES`](crate::SENSITIVE_NAMES),
+    /// whatever the ignore patterns say. A glob without a `/` matches names
+    /// at any depth, ignoring case; one with a `/` matches paths from the
+    /// root.
+    pub fn sensitive<I, S>(mut self, globs: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        self.sensitive = globs.into_iter().map(Into::into).collect();
+        self
+    }
+
+    /// Packs the sensitive files these globs match, matched like
+    /// [`sensitive`](Self::sensitive)'s; `*` packs them all. Each withheld
+    /// file is recorded as skipped with the pattern it matched.
+    pub fn allow_sensitive<I, S>(mut self, globs: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        self.allow_sensitive = globs.into_iter().map(Into::into).collect();
+        self
+    }
+
+    /// Walks into [`VCS_INTERNALS`] like anything else, so the ignore
+    /// patterns and the dotfile rule decide.
+    pub fn include_vcs_internals(mut self, yes: bool) -> Self {
+        self.include_vcs_internals = yes;
+        self
+    }
+
+    /// Packs only files whose path relative to the root matches one of these
+    /// globs. Every other filter still applies.
+    pub fn only(mut self, globs: Vec<String>) -> Self {
+        self.only = globs;
+        self
+    }
+
+    /// Leaves out test code, or keeps only test code, as recognized by
+    /// [`TEST_PATTERNS`](crate::TEST_PATTERNS).
+    pub fn tests(mut self, tests: TestFiles) -> Self {
+        self.tests = tests;
+        self
+    }
+
+    /// Packs only files that `CODEOWNERS` assigns to one of these owners,
+    /// like `@org/payments-team`. See [`codeowners`](crate::codeowners).
+    pub fn owners(mut self, owners: Vec<String>) -> Self {
+        self.owners = owners;
+        self
+    }
+
+    /// With [`owners`](Self::owners), also packs files no `CODEOWNERS` line
+    /// assigns. On its own, packs only those.
+    pub fn include_unowned(mut self, yes: bool) -> Self {
+        self.include_unowned = yes;
+        self
+    }
+
+    /// Packs only text files whose content matches one of these regexes.
+    /// Binary files are left out without being searched.
+    pub fn grep(mut self, patterns: Vec<String>) -> Self {
+        self.grep = patterns;
+        self
+    }
+
+    /// With [`grep`](Self::grep), renders only the matching lines and this
+    /// many lines around each, instead of whole files.
+    pub fn grep_context(mut self, lines: Option<usize>) -> Self {
+        self.grep_context = lines;
+        self
+    }
+
+    /// Leaves out files whose first [`GREP_EXCLUDE_HEAD`](crate::GREP_EXCLUDE_HEAD)
+    /// bytes match one of these regexes, even when [`grep`](Self::grep)
+    /// selects them. Each is recorded as skipped with the pattern.
+    pub fn grep_exclude(mut self, patterns: Vec<String>) -> Self {
+        self.grep_exclude = patterns;
+        self
+    }
+
+    /// Searches whole text files for [`grep_exclude`](Self::grep_exclude)
+    /// patterns, rather than their start.
+    pub fn grep_exclude_full(mut self, yes: bool) -> Self {
+        self.grep_exclude_full = yes;
+        self
+    }
+
+    /// Adds the ignore patterns of these [`profiles`](crate::profiles), which
+    /// replaces detection.
+    pub fn profiles<I, S>(mut self, names: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        self.profiles = names.into_iter().map(Into::into).collect();
+        self
+    }
+
+    /// Applies the profiles detected from marker files when none are given.
+    pub fn auto_profile(mut self, yes: bool) -> Self {
+        self.auto_profile = yes;
+        self
+    }
+
+    /// Includes lockfiles, which are skipped by default.
+    pub fn include_lockfiles(mut self, yes: bool) -> Self {
+        self.include_lockfiles = yes;
+        self
+    }
+
+    /// Lists only the dependency names of each skipped lockfile.
+    pub fn lockfile_summary(mut self, yes: bool) -> Self {
+        self.lockfile_summary = yes;
+        self
+    }
+
+    /// Replaces secrets with redaction markers.
+    pub fn redact(mut self, yes: bool) -> Self {
+        self.redact = yes;
+        self
+    }
+
+    /// Additional secret regexes to redact; implies [`redact`](Self::redact).
+    pub fn redact_patterns(mut self, patterns: Vec<String>) -> Self {
+        self.redact_patterns = patterns;
+        self
+    }
+
+    /// Also redacts high-entropy strings; implies [`redact`](Self::redact).
+    pub fn redact_entropy(mut self, yes: bool) -> Self {
+        self.redact_entropy = yes;
+        self
+    }
+
+    /// Regexes for secrets (or whole files, by path) to leave alone.
+    pub fn secret_allow(mut self, patterns: Vec<String>) -> Self {
+        self.secret_allow = patterns;
+        self
+    }
+
+    /// Collects every secret found into [`Bundle::findings`](crate::Bundle::findings),
+    /// whether or not it's redacted.
+    pub fn scan_secrets(mut self, yes: bool) -> Self {
+        self.scan_secrets = yes;
+        self
+    }
+
+    /// Removes comments from languages with known comment syntax.
+    pub fn strip_comments(mut self, yes: bool) -> Self {
+        self.strip_comments = yes;
+        self
+    }
+
+    /// Keeps doc comments when stripping comments.
+    pub fn keep_doc_comments(mut self, yes: bool) -> Self {
+        self.keep_doc_comments = yes;
+        self
+    }
+
+    /// Replaces a leading license comment with a one-line placeholder.
+    pub fn strip_license_headers(mut self, yes: bool) -> Self {
+        self.strip_license_headers = yes;
+        self
+    }
+
+    /// Skips minified and generated files, except those an
+    /// [`only`](Self::only) glob names.
+    pub fn skip_generated(mut self, yes: bool) -> Self {
+        self.skip_generated = yes;
+        self
+    }
+
+    /// Collapses runs of more than `max` blank lines down to `max`.
+    pub fn squeeze_blank_lines(mut self, max: Option<usize>) -> Self {
+        self.squeeze_blank_lines = max;
+        self
+    }
+
+    /// Removes trailing spaces and tabs from every line.
+    pub fn trim_trailing_whitespace(mut self, yes: bool) -> Self {
+        self.trim_trailing_whitespace = yes;
+        self
+    }
+
+    /// Removes ANSI escape sequences, like terminal colors in logs, from text
+    /// files that have them.
+    pub fn strip_ansi(mut self, yes: bool) -> Self {
+        self.strip_ansi = yes;
+        self
+    }
+
+    /// Wraps lines wider than this many display columns, after every other
+    /// transformation, ending each wrapped segment with the
+    /// [`wrap_marker`](Self::wrap_marker).
+    pub fn wrap(mut self, columns: Option<usize>) -> Self {
+        self.wrap = columns;
+        self
+    }
+
+    /// What ends each segment of a wrapped line, so wrapping can be told from
+    /// real line breaks. Defaults to `↩`.
+    pub fn wrap_marker(mut self, marker: impl Into<String>) -> Self {
+        self.wrap_marker = marker.into();
+        self
+    }
+
+    /// Expands leading tabs to this many columns, except in Makefiles.
+    pub fn expand_tabs(mut self, width: Option<usize>) -> Self {
+        self.expand_tabs = width;
+        self
+    }
+
+    /// Strips the longest common leading whitespace from each file.
+    pub fn dedent(mut self, yes: bool) -> Self {
+        self.dedent = yes;
+        self
+    }
+
+    /// Pipes files matching a glob through a shell command, as
+    /// `<glob>:<command>`, and packs its stdout instead. The glob is matched
+    /// against the path relative to the root and against the file name; the
+    /// first match wins.
+    pub fn filter_cmds(mut self, specs: Vec<String>) -> Self {
+        self.filter_cmds = specs;
+        self
+    }
+
+    /// How long a filter command may run before it's killed. Defaults to 10s.
+    pub fn filter_timeout(mut self, timeout: Duration) -> Self {
+        self.filter_timeout = timeout;
+        self
+    }
+
+    /// The most output a filter command may produce. Defaults to 16 MiB.
+    pub fn filter_max_output(mut self, bytes: usize) -> Self {
+        self.filter_max_output = bytes;
+        self
+    }
+
+    /// Packs files that are almost UTF-8, with no NUL bytes and at most one
+    /// invalid sequence per 16 bytes, with each invalid sequence shown as
+    /// U+FFFD, rather than as binary.
+    pub fn lossy(mut self, lossy: bool) -> Self {
+        self.lossy = lossy;
+        self
+    }
+
+    /// What to do with a file whose size or modification time changed since
+    /// the walk saw it, or while it was read. Defaults to re-reading it once.
+    pub fn on_change(mut self, on_change: OnChange) -> Self {
+        self.on_change = on_change;
+        self
+    }
+
+    /// How many files are read at once. Defaults to twice the CPU count; `1`
+    /// reads them one at a time in walk order, which suits network and
+    /// spinning disks. The bundle is the same either way.
+    pub fn jobs(mut self, jobs: Option<usize>) -> Self {
+        self.jobs = jobs;
+        self
+    }
+
+    /// How many threads list directories during the walk. Defaults to one
+    /// per CPU, up to 12; `1` walks on the calling thread. Files come out in
+    /// the same order either way.
+    pub fn walk_threads(mut self, threads: Option<usize>) -> Self {
+        self.walk_threads = threads;
+        self
+    }
+
+    /// How long reading one file may take before it fails as unreadable, so
+    /// something that blocks, like a FIFO the walk didn't recognize, can't
+    /// hang the run. Defaults to no limit. The read stays blocked on its
+    /// thread, so shut the runtime down with `shutdown_background` rather
+    /// than waiting for it.
+    pub fn read_timeout(mut self, timeout: Option<Duration>) -> Self {
+        self.read_timeout = timeout;
+        self
+    }
+
+    /// Times the walk and every file read into the bundle's
+    /// [`Summary::timings`](crate::Summary::timings). Off by default.
+    pub fn timings(mut self, yes: bool) -> Self {
+        self.timings = yes;
+        self
+    }
+
+    /// Places this text verbatim before the first file section.
+    pub fn header_text(mut self, text: impl Into<String>) -> Self {
+        self.header = Some(Insert::Text(text.into()));
+        self
+    }
+
+    /// Places this file's contents verbatim before the first file section.
+    /// It's exempt from ignore patterns and never packed as a regular file.
+    pub fn header_file(mut self, path: impl Into<PathBuf>) -> Self {
+        self.header = Some(Insert::File(path.into()));
+        self
+    }
+
+    /// Places this text verbatim after the last file section.
+    pub fn footer_text(mut self, text: impl Into<String>) -> Self {
+        self.footer = Some(Insert::Text(text.into()));
+        self
+    }
+
+    /// Places this file's contents verbatim after the last file section.
+    /// It's exempt from ignore patterns and never packed as a regular file.
+    pub fn footer_file(mut self, path: impl Into<PathBuf>) -> Self {
+        self.footer = Some(Insert::File(path.into()));
+        self
+    }
+
+    /// Wraps each file's code block in a `<details>` block.
+    pub fn collapsible(mut self, collapsible: Option<Collapsible>) -> Self {
+        self.style.collapsible = collapsible;
+        self
+    }
+
+    /// Groups the files under a top-level heading per directory or language,
+    /// in JSON as well as Markdown. Defaults to [`GroupBy::None`].
+    pub fn group_by(mut self, group_by: GroupBy) -> Self {
+        self.style.group_by = group_by;
+        self
+    }
+
+    /// Header and footer files, which the walk leaves out.
+    pub(crate) fn insert_files(&self) -> impl Iterator<Item = &Path> {
+        [&self.header, &self.footer].into_iter().filter_map(|insert| match insert {
+            Some(Insert::File(path)) => Some(path.as_path()),
+            _ => None,
+        })
+    }
+
+    pub(crate) fn redacts(&self) -> bool {
+        self.redact || self.redact_entropy || !self.redact_patterns.is_empty()
+    }
+
+    /// Whether walking skips lockfiles; a lockfile summary keeps them.
+    pub(crate) fn skips_lockfiles(&self) -> bool {
+        !self.include_lockfiles && !self.lockfile_summary
+    }
+
+    pub(crate) fn summarizes_lockfiles(&self) -> bool {
+        !self.include_lockfiles && self.lockfile_summary
+    }
+}
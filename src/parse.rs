@@ -0,0 +1,227 @@
+//! Reading bundles back: the files of a Markdown or JSON bundle owo wrote.
+
+use crate::{Collapsible, Error, FileContent, Result};
+use serde::Deserialize;
+
+/// One file section of a bundle.
+#[derive(Debug, Clone)]
+pub struct ParsedFile {
+    /// The path as written in the heading, usually starting with the root.
+    pub path: String,
+    /// The code fence's info string.
+    pub fence_tag: Option<String>,
+    /// The file's text as packed, or a binary file's size.
+    pub content: FileContent,
+    /// The line ranges the file was cut to, from the heading. Empty for the
+    /// whole file.
+    pub lines: Vec<(usize, usize)>,
+}
+
+/// The files of a bundle, in order, and how it was written.
+#[derive(Debug, Clone, Default)]
+pub struct ParsedBundle {
+    /// Whether it was JSON rather than Markdown.
+    pub json: bool,
+    /// Text before the first file.
+    pub header: Option<String>,
+    pub files: Vec<ParsedFile>,
+    /// Text after the last file.
+    pub footer: Option<String>,
+    /// How the files sat in `<details>` blocks, if they did.
+    pub collapsible: Option<Collapsible>,
+}
+
+/// Parses a bundle, as JSON when it looks like JSON and as Markdown
+/// otherwise. Collapsible and plain layouts both parse.
+pub fn parse(text: &str) -> Result<ParsedBundle> {
+    if text.trim_start().starts_with('{') {
+        return parse_json(text);
+    }
+    parse_markdown(text)
+}
+
+#[derive(Deserialize)]
+struct JsonBundle {
+    header: Option<String>,
+    files: Vec<JsonFile>,
+    footer: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct JsonFile {
+    path: String,
+    language: Option<String>,
+    content: Option<String>,
+    #[serde(default)]
+    size: u64,
+    #[serde(default)]
+    lines: Option<Vec<(usize, usize)>>,
+}
+
+fn parse_json(text: &str) -> Result<ParsedBundle> {
+    let bundle: JsonBundle =
+        serde_json::from_str(text).map_err(|e| Error::Parse { line: e.line(), reason: e.to_string() })?;
+    let files = bundle
+        .files
+        .into_iter()
+        .map(|file| ParsedFile {
+            path: file.path,
+            fence_tag: file.language,
+            content: match file.content {
+                Some(text) => FileContent::Text(text),
+                None => FileContent::Binary(file.size as usize),
+            },
+            lines: file.lines.unwrap_or_default(),
+        })
+        .collect();
+    Ok(ParsedBundle { json: true, header: bundle.header, files, footer: bundle.footer, collapsible: None })
+}
+
+/// A file's heading: `## File: ` and the path as a code span or, in a
+/// collapsed layout, the `<summary>`, with the optional ` (lines ...)` note.
+fn heading(line: &str) -> Option<(String, Vec<(usize, usize)>)> {
+    let (path, rest) = if let Some(rest) = line.strip_prefix("## File: ") {
+        code_span(rest)?
+    } else {
+        let rest = line.strip_prefix("<summary><code>")?;
+        let (path, rest) = rest.split_once("</code>")?;
+        let path = path.replace("&lt;", "<").replace("&gt;", ">").replace("&amp;", "&");
+        (path, rest)
+    };
+    let lines = rest
+        .trim_start()
+        .strip_prefix("(lines ")
+        .and_then(|rest| rest.split_once(')'))
+        .map(|(lines, _)| {
+            lines
+                .split(", ")
+                .filter_map(|range| range.split_once('–'))
+                .filter_map(|(start, end)| Some((start.parse().ok()?, end.parse().ok()?)))
+                .collect()
+        })
+        .unwrap_or_default();
+    Some((path, lines))
+}
+
+/// Whether `line` is the heading of a group of files, as
+/// [`GroupBy`](crate::GroupBy) writes them.
+fn group_heading(line: &str) -> bool {
+    line == "# Other" || line.starts_with("# Language: ") || line.starts_with("# Directory: ")
+}
+
+/// The text of the code span `line` starts with, as
+/// [`code_span`](crate::code_span) writes it, and what
+/// follows.
+fn code_span(line: &str) -> Option<(String, &str)> {
+    let fence = &line[..line.len() - line.trim_start_matches('`').len()];
+    if fence.is_empty() {
+        return None;
+    }
+    let rest = &line[fence.len()..];
+    // The closing fence is a run of exactly as many backticks.
+    let mut from = 0;
+    let end = loop {
+        let start = from + rest[from..].find(fence)?;
+        let run = rest[start..].len() - rest[start..].trim_start_matches('`').len();
+        if run == fence.len() {
+            break start;
+        }
+        from = start + run;
+    };
+    let text = &rest[..end];
+    let padded = text.starts_with(' ') && text.ends_with(' ') && !text.trim_matches(' ').is_empty();
+    let text = if padded { &text[1..text.len() - 1] } else { text };
+    Some((text.to_string(), &rest[end + fence.len()..]))
+}
+
+/// Whether line `i` is the fence closing a file: a bare fence followed by a
+/// blank line and another section or group (or a merged bundle's provenance
+/// comment), or by the end.
+fn ends_block(lines: &[&str], i: usize) -> bool {
+    if lines[i] != "```" {
+        return false;
+    }
+    match (lines.get(i + 1), lines.get(i + 2)) {
+        (None, _) | (Some(&""), None) => true,
+        (Some(&""), Some(next)) => {
+            heading(next).is_some() || group_heading(next) || *next == "</details>" || next.starts_with("<!-- from ")
+        }
+        _ => false,
+    }
+}
+
+fn parse_markdown(text: &str) -> Result<ParsedBundle> {
+    let lines = text.lines().collect::<Vec<_>>();
+    let mut files = Vec::new();
+    let mut collapsible = None;
+    let mut header_end = None;
+    let mut last_close = None;
+    let mut index = 0;
+    while index < lines.len() {
+        let Some((path, ranges)) = heading(lines[index]) else {
+            // The first group's heading ends the header.
+            if group_heading(lines[index]) {
+                header_end.get_or_insert(index);
+            }
+            index += 1;
+            continue;
+        };
+        header_end.get_or_insert(index);
+        // A collapsed layout without headings starts each file with
+        // <details>, the line before its summary.
+        if lines[index].starts_with("<summary>") {
+            collapsible = Some(Collapsible::Summary);
+            if header_end == Some(index) {
+                header_end = Some(index.saturating_sub(1));
+            }
+        }
+        // A kept heading is followed by its <summary>, which names the file
+        // again.
+        let start = index;
+        index += 1;
+        while index < lines.len() && !lines[index].starts_with("```") {
+            if lines[index].starts_with("<details>") {
+                collapsible = Some(Collapsible::KeepHeading);
+            }
+            index += 1;
+        }
+        let Some(open) = lines.get(index) else {
+            return Err(Error::Parse { line: start + 1, reason: format!("no code block for `{}`", path) });
+        };
+        let fence_tag = Some(open.trim_start_matches('`').trim()).filter(|tag| !tag.is_empty()).map(str::to_string);
+        let body = index + 1;
+        // Files may contain fence lines of their own, so the block ends at a
+        // bare fence followed by the next section, or else at the last bare
+        // fence before the next heading, as before a footer.
+        let next = (body..lines.len()).find(|&i| heading(lines[i]).is_some()).unwrap_or(lines.len());
+        let close = (body..lines.len())
+            .find(|&i| ends_block(&lines, i))
+            .or_else(|| (body..next).rev().find(|&i| lines[i] == "```"));
+        let Some(close) = close else {
+            return Err(Error::Parse { line: index + 1, reason: format!("the code block for `{}` isn't closed", path) });
+        };
+        let content = lines[body..close].join("\n");
+        let binary = content
+            .strip_prefix("[Binary file: ")
+            .and_then(|rest| rest.strip_suffix(" bytes]"))
+            .and_then(|size| size.parse().ok());
+        let content = match binary {
+            Some(size) => FileContent::Binary(size),
+            None => FileContent::Text(content),
+        };
+        files.push(ParsedFile { path, fence_tag, content, lines: ranges });
+        index = close + 1;
+        last_close = Some(index);
+    }
+
+    let text_of = |lines: &[&str]| Some(lines.join("\n").trim().to_string()).filter(|text| !text.is_empty());
+    let header = text_of(&lines[..header_end.unwrap_or(lines.len())]);
+    let after = last_close.map_or(&[][..], |end| &lines[end..]);
+    // The collapsed layout closes each file's block after its fence.
+    let after = match after.iter().position(|line| *line == "</details>") {
+        Some(end) if collapsible.is_some() => &after[end + 1..],
+        _ => after,
+    };
+    let footer = text_of(after);
+    Ok(ParsedBundle { json: false, header, files, footer, collapsible })
+}
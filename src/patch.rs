@@ -0,0 +1,144 @@
+//! The paths a unified diff touches, for packing the files a patch changes.
+
+/// Every path the patch touches, relative to the directory it applies to, in
+/// the order they first appear. Reads `---`/`+++` headers with git's `a/` and
+/// `b/` prefixes or without, `rename`/`copy` lines, and `diff --git` lines
+/// for changes with no hunks, like a mode change. Quoted paths are unquoted,
+/// and `/dev/null` for an added or deleted file is left out. Text outside the
+/// headers, like a `git format-patch` mail or hunk lines that start with
+/// `---`, is ignored.
+pub fn touched_paths(patch: &str) -> Vec<String> {
+    let mut paths = Vec::new();
+    let mut add = |path: Option<String>| {
+        if let Some(path) = path
+            && path != "/dev/null"
+            && !path.is_empty()
+            && !paths.contains(&path)
+        {
+            paths.push(path);
+        }
+    };
+    // The old and new lines left in the current hunk.
+    let (mut old, mut new) = (0usize, 0usize);
+    for line in patch.lines() {
+        if old > 0 || new > 0 {
+            match line.as_bytes().first() {
+                Some(b'-') => old = old.saturating_sub(1),
+                Some(b'+') => new = new.saturating_sub(1),
+                Some(b'\\') => {}
+                _ => {
+                    old = old.saturating_sub(1);
+                    new = new.saturating_sub(1);
+                }
+            }
+            continue;
+        }
+        if let Some(range) = line.strip_prefix("@@ ") {
+            (old, new) = hunk_lengths(range).unwrap_or((0, 0));
+        } else if let Some(rest) = line.strip_prefix("diff --git ") {
+            git_header(rest).into_iter().flatten().for_each(&mut add);
+        } else if let Some(rest) = line.strip_prefix("--- ") {
+            add(header_path(rest, "a/"));
+        } else if let Some(rest) = line.strip_prefix("+++ ") {
+            add(header_path(rest, "b/"));
+        } else if let Some(rest) = ["rename from ", "rename to ", "copy from ", "copy to "]
+            .iter()
+            .find_map(|prefix| line.strip_prefix(prefix))
+        {
+            add(unquoted(rest));
+        }
+    }
+    paths
+}
+
+/// The old and new line counts of a hunk from the rest of its `@@ -1,5 +1,6 @@`
+/// line. A count left out is 1.
+fn hunk_lengths(range: &str) -> Option<(usize, usize)> {
+    let mut ranges = range.split(' ');
+    let count = |range: &str| match range.split_once(',') {
+        Some((_, count)) => count.parse().ok(),
+        None => Some(1),
+    };
+    let old = count(ranges.next()?.strip_prefix('-')?)?;
+    let new = count(ranges.next()?.strip_prefix('+')?)?;
+    Some((old, new))
+}
+
+/// The path on a `---` or `+++` line, without `prefix` or the timestamp some
+/// diff tools put after a tab.
+fn header_path(rest: &str, prefix: &str) -> Option<String> {
+    let path = match rest.starts_with('"') {
+        true => unquoted(rest)?,
+        false => rest.split('\t').next()?.trim_end().to_string(),
+    };
+    Some(path.strip_prefix(prefix).map(str::to_string).unwrap_or(path))
+}
+
+/// The two paths of a `diff --git a/x b/x` line. Unquoted paths may hold
+/// spaces, so they're only read when both halves name the same file.
+fn git_header(rest: &str) -> Option<[Option<String>; 2]> {
+    if let Some(after) = rest.strip_prefix('"') {
+        let end = closing_quote(after)?;
+        let (old, new) = (&rest[..end + 2], rest[end + 2..].trim_start());
+        let strip = |path: &str, prefix| unquoted(path)?.strip_prefix(prefix).map(str::to_string);
+        return Some([strip(old, "a/"), strip(new, "b/")]);
+    }
+    let half = rest.len().checked_sub(1)? / 2;
+    let (old, new) = (rest.get(..half)?, rest.get(half + 1..)?);
+    let (old, new) = match (old.strip_prefix("a/"), new.strip_prefix("b/")) {
+        (Some(old), Some(new)) => (old, new),
+        _ => (old, new),
+    };
+    (old == new).then(|| [Some(old.to_string()), None])
+}
+
+/// Where the quote closing a quoted path is, in the text after the opening one.
+fn closing_quote(after: &str) -> Option<usize> {
+    let mut escaped = false;
+    for (index, byte) in after.bytes().enumerate() {
+        match byte {
+            _ if escaped => escaped = false,
+            b'\\' => escaped = true,
+            b'"' => return Some(index),
+            _ => {}
+        }
+    }
+    None
+}
+
+/// A path as git writes it: as is, or in double quotes with C escapes and
+/// bytes outside ASCII as octal, like `"caf\303\251.txt"`.
+fn unquoted(text: &str) -> Option<String> {
+    let Some(after) = text.strip_prefix('"') else {
+        return Some(text.trim_end().to_string());
+    };
+    let quoted = after.get(..closing_quote(after)?)?.as_bytes();
+    let mut bytes = Vec::new();
+    let mut index = 0;
+    while index < quoted.len() {
+        let byte = quoted[index];
+        index += 1;
+        if byte != b'\\' {
+            bytes.push(byte);
+            continue;
+        }
+        let escape = *quoted.get(index)?;
+        index += 1;
+        bytes.push(match escape {
+            b'n' => b'\n',
+            b't' => b'\t',
+            b'r' => b'\r',
+            b'a' => 0x07,
+            b'b' => 0x08,
+            b'f' => 0x0c,
+            b'v' => 0x0b,
+            b'0'..=b'3' => {
+                let digits = quoted.get(index - 1..index + 2)?;
+                index += 2;
+                u8::from_str_radix(std::str::from_utf8(digits).ok()?, 8).ok()?
+            }
+            other => other,
+        });
+    }
+    Some(String::from_utf8_lossy(&bytes).into_owned())
+}
@@ -0,0 +1,131 @@
+//! Paths as handed to the file system.
+
+use std::borrow::Cow;
+use std::path::{Component, Path, PathBuf};
+
+/// `path` in the form file operations should use. On Windows that's an
+/// absolute extended-length `\\?\` path, which isn't limited to `MAX_PATH`
+/// and opens a file named `con.txt` or `aux.log` rather than a device.
+/// Elsewhere it's `path` as is.
+pub fn long_path(path: &Path) -> Cow<'_, Path> {
+    #[cfg(windows)]
+    {
+        use std::path::Prefix;
+
+        if let Some(Component::Prefix(prefix)) = path.components().next()
+            && prefix.kind().is_verbatim()
+        {
+            return Cow::Borrowed(path);
+        }
+        // Extended-length paths skip normalization, so `.` and `..` have to
+        // be resolved first.
+        let Ok(absolute) = std::path::absolute(path) else {
+            return Cow::Borrowed(path);
+        };
+        let Some(text) = absolute.to_str() else {
+            return Cow::Borrowed(path);
+        };
+        let long = match absolute.components().next() {
+            Some(Component::Prefix(prefix)) if matches!(prefix.kind(), Prefix::UNC(..)) => {
+                format!(r"\\?\UNC\{}", text.trim_start_matches('\\'))
+            }
+            Some(Component::Prefix(_)) => format!(r"\\?\{}", text),
+            _ => return Cow::Borrowed(path),
+        };
+        Cow::Owned(PathBuf::from(long))
+    }
+    #[cfg(not(windows))]
+    Cow::Borrowed(path)
+}
+
+/// The type of the network file system `path` is on, like `nfs4` or `cifs`,
+/// from `/proc/mounts`. `None` for a local one, and off Linux.
+pub fn network_filesystem(path: &Path) -> Option<String> {
+    const NETWORK: &[&str] =
+        &["nfs", "nfs4", "cifs", "smb3", "smbfs", "9p", "afs", "ceph", "glusterfs", "fuse.sshfs", "fuse.rclone"];
+    filesystem_type(path).filter(|fstype| NETWORK.contains(&fstype.as_str()))
+}
+
+/// Whether `path` is on a kernel pseudo file system like procfs or sysfs,
+/// whose files claim to be empty whatever reading them gives, or blocks on.
+#[cfg(feature = "fs")]
+pub(crate) fn pseudo_filesystem(path: &Path) -> bool {
+    const PSEUDO: &[&str] = &["proc", "sysfs", "debugfs", "tracefs", "securityfs", "configfs", "cgroup", "cgroup2"];
+    filesystem_type(path).is_some_and(|fstype| PSEUDO.contains(&fstype.as_str()))
+}
+
+/// The type of the file system `path` is on, from `/proc/mounts`. `None`
+/// off Linux.
+fn filesystem_type(path: &Path) -> Option<String> {
+    #[cfg(target_os = "linux")]
+    {
+        let path = std::fs::canonicalize(path).ok()?;
+        let mounts = std::fs::read_to_string("/proc/mounts").ok()?;
+        // Later mounts over the same point hide earlier ones, and max_by_key
+        // keeps the last of equals.
+        let (_, fstype) = mounts
+            .lines()
+            .filter_map(|line| {
+                let mut fields = line.split(' ').skip(1);
+                let dir = fields.next()?.replace("\\040", " ").replace("\\011", "\t").replace("\\134", "\\");
+                Some((PathBuf::from(dir), fields.next()?))
+            })
+            .filter(|(dir, _)| path.starts_with(dir))
+            .max_by_key(|(dir, _)| dir.as_os_str().len())?;
+        Some(fstype.to_string())
+    }
+    #[cfg(not(target_os = "linux"))]
+    {
+        let _ = path;
+        None
+    }
+}
+
+/// `name` without `./` components, or `None` when it's absolute or has a
+/// `..` component, so nothing can name a path outside the root it belongs
+/// under.
+pub(crate) fn enclosed(name: &Path) -> Option<PathBuf> {
+    let mut rel = PathBuf::new();
+    for component in name.components() {
+        match component {
+            Component::Normal(part) => rel.push(part),
+            Component::CurDir => {}
+            Component::ParentDir | Component::RootDir | Component::Prefix(_) => return None,
+        }
+    }
+    (!rel.as_os_str().is_empty()).then_some(rel)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[cfg(windows)]
+    #[test]
+    fn windows_paths_become_extended_length() {
+        let deep = format!(r"C:\{}\file.txt", vec!["a".repeat(60); 6].join(r"\"));
+        assert_eq!(long_path(Path::new(&deep)), Path::new(&format!(r"\\?\{}", deep)));
+        assert_eq!(long_path(Path::new(r"C:\repo\.\src\..\con.txt")), Path::new(r"\\?\C:\repo\con.txt"));
+        assert_eq!(long_path(Path::new(r"\\server\share\aux.log")), Path::new(r"\\?\UNC\server\share\aux.log"));
+        assert_eq!(long_path(Path::new(r"\\?\C:\already\long")), Path::new(r"\\?\C:\already\long"));
+        let relative = long_path(Path::new(r"src\main.rs"));
+        assert!(relative.to_str().unwrap().starts_with(r"\\?\"), "{}", relative.display());
+        assert!(relative.ends_with(r"src\main.rs"));
+    }
+
+    #[cfg(not(windows))]
+    #[test]
+    fn other_paths_are_left_alone() {
+        let deep = format!("/{}/file.txt", vec!["a".repeat(60); 6].join("/"));
+        assert_eq!(long_path(Path::new(&deep)), Path::new(&deep));
+        assert_eq!(long_path(Path::new("src/../con.txt")), Path::new("src/../con.txt"));
+    }
+
+    #[test]
+    fn enclosed_names_stay_under_the_root() {
+        assert_eq!(enclosed(Path::new("./src/main.rs")), Some(PathBuf::from("src/main.rs")));
+        assert_eq!(enclosed(Path::new("src/../../etc/passwd")), None);
+        assert_eq!(enclosed(Path::new("/etc/passwd")), None);
+        assert_eq!(enclosed(Path::new(".")), None);
+    }
+}
@@ -0,0 +1,342 @@
+//! Packing from the file system: walking the root and reading its files, a
+//! bounded number at a time.
+
+use crate::archive::{Archive, ArchiveEntry};
+use crate::bundle::{Step, Transformer};
+use crate::filter::Filters;
+use crate::grep::GREP_EXCLUDE_HEAD;
+use crate::options::Insert;
+use crate::summary::{ReadTiming, Timings};
+use crate::walk::{self, FoundFile, Stamp};
+use crate::{long_path, Bundle, Error, FileContent, FileEntry, OnChange, Options, Result};
+use std::collections::VecDeque;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::time::Instant;
+use tokio::fs;
+use tokio::io::AsyncReadExt;
+use tokio::sync::mpsc;
+use tokio::task::JoinHandle;
+use tokio_stream::Stream;
+use tokio_stream::wrappers::ReceiverStream;
+
+/// Walks the options' root and reads and transforms every file that passes
+/// the filters, a bounded number at a time.
+pub async fn generate(options: &Options) -> Result<Bundle> {
+    let header = read_insert(options.header.as_ref()).await?;
+    let footer = read_insert(options.footer.as_ref()).await?;
+    let started = Instant::now();
+    let (walk, paths) = walk::walk_files(options, false)?;
+    let pipeline = Pipeline::new(options)?;
+    let mut files = pipeline.spawn(paths, walk.archive.clone());
+    let walking = tokio::task::spawn_blocking(move || {
+        let walk = walk.run();
+        (walk, started.elapsed())
+    });
+
+    let mut entries = Vec::new();
+    let mut failures = Vec::new();
+    let mut unreadable = Vec::new();
+    while let Some(file) = files.recv().await {
+        match file {
+            Ok(file) => entries.push(file),
+            Err(e) => {
+                // Listed with the skips too, so an unreadable file never just
+                // goes missing from the summary.
+                if let Error::Read { path, source } = &e {
+                    unreadable.push((path.clone(), format!("unreadable: {}", source)));
+                }
+                failures.push(e)
+            }
+        }
+    }
+    let reading = started.elapsed();
+    let (walk, walked) = walking.await.expect("the walk panicked");
+    // Files are read as the walk's threads find them; the bundle lists them
+    // in walk order.
+    entries.sort_by(|a, b| a.path.cmp(&b.path));
+    sort_failures(&mut failures);
+    unreadable.sort_by(|a, b| a.0.cmp(&b.0));
+    failures.splice(0..0, walk.errors);
+
+    let transformer = &pipeline.transformer;
+    let mut findings = std::mem::take(&mut *transformer.findings.lock().unwrap());
+    findings.sort_by(|a, b| a.path.cmp(&b.path).then(a.line.cmp(&b.line)));
+    failures.append(&mut transformer.failures.lock().unwrap());
+    let mut summary = std::mem::take(&mut *transformer.summary.lock().unwrap());
+    summary.skipped.extend(walk.skipped);
+    summary.skipped.append(&mut unreadable);
+    if let Some(reads) = &pipeline.reads {
+        let mut reads = std::mem::take(&mut *reads.lock().unwrap());
+        reads.sort_by(|a, b| a.path.cmp(&b.path));
+        summary.timings = Some(Timings { walk: walked, entries: walk.reached, reading, reads });
+    }
+
+    Ok(Bundle {
+        root: options.root.clone(),
+        header,
+        files: entries,
+        footer,
+        findings,
+        summary,
+        detected_profiles: walk.detected,
+        profiles: walk.profiles,
+        style: options.style.clone(),
+        failures,
+        unmatched_patterns: walk.unmatched_patterns,
+        unmatched_only: walk.unmatched_only,
+    })
+}
+
+/// Streams the files [`generate`] would produce, in the same order, as each
+/// is read and transformed, so callers can render them as they arrive. Reads
+/// run a bounded distance ahead of the consumer, and dropping the stream
+/// aborts the ones still outstanding. An unreadable file yields an error and
+/// the stream carries on.
+///
+/// The walk goes on as files are read, on one thread so they come in walk
+/// order. Bad patterns fail here rather than mid-stream; entries the walk
+/// couldn't list are dropped.
+/// Must be called from within a Tokio runtime.
+pub fn stream(options: &Options) -> Result<impl Stream<Item = Result<FileEntry>> + use<>> {
+    let (walk, paths) = walk::walk_files(options, true)?;
+    let pipeline = Pipeline::new(options)?;
+    let files = pipeline.spawn(paths, walk.archive.clone());
+    tokio::task::spawn_blocking(move || walk.run());
+    Ok(ReceiverStream::new(files))
+}
+
+/// Sorts read failures into walk order, by the path each is about.
+pub(crate) fn sort_failures(failures: &mut [Error]) {
+    let path = |e: &Error| match e {
+        Error::Read { path, .. } => Some(path.clone()),
+        _ => None,
+    };
+    failures.sort_by_cached_key(path);
+}
+
+/// What every per-file task shares: the transformations, the filters built
+/// from the options, and where to read from.
+#[derive(Clone)]
+pub(crate) struct Pipeline {
+    pub(crate) transformer: Transformer,
+    filters: Option<Arc<Filters>>,
+    /// Where files are read from when the root is an archive.
+    archive: Option<Arc<Archive>>,
+    /// How long each read took, with [`Options::timings`].
+    reads: Option<Arc<Mutex<Vec<ReadTiming>>>>,
+}
+
+impl Pipeline {
+    pub(crate) fn new(options: &Options) -> Result<Self> {
+        let filters = if options.filter_cmds.is_empty() {
+            None
+        } else {
+            Some(Arc::new(Filters::new(
+                &options.filter_cmds,
+                options.filter_timeout,
+                options.filter_max_output,
+            )?))
+        };
+        let reads = options.timings.then(Default::default);
+        Ok(Self { transformer: Transformer::new(options)?, filters, archive: None, reads })
+    }
+
+    /// Processes the files coming from `paths` on a background task, keeping
+    /// up to the options' `jobs` reads in flight and sending results in the
+    /// order the paths came. Stops, aborting outstanding reads, once the
+    /// receiver is dropped.
+    pub(crate) fn spawn(
+        &self,
+        mut paths: mpsc::Receiver<FoundFile>,
+        archive: Option<Arc<Archive>>,
+    ) -> mpsc::Receiver<Result<FileEntry>> {
+        let in_flight = self.transformer.options.jobs.unwrap_or_else(|| num_cpus::get() * 2).max(1);
+        let (tx, rx) = mpsc::channel(in_flight);
+        let mut pipeline = self.clone();
+        pipeline.archive = archive;
+        tokio::spawn(async move {
+            let mut pending = VecDeque::<(PathBuf, JoinHandle<Result<Option<FileEntry>>>)>::new();
+            let mut walking = true;
+            loop {
+                if pending.is_empty() && !walking {
+                    break;
+                }
+                // New files are taken while there's room; the oldest read
+                // is waited on all the same, to send it as soon as it's done.
+                let room = walking && pending.len() < in_flight;
+                let next = tokio::select! {
+                    found = paths.recv(), if room => Next::Found(found),
+                    result = async { (&mut pending.front_mut().unwrap().1).await }, if !pending.is_empty() => {
+                        Next::Read(result)
+                    }
+                    _ = tx.closed() => break,
+                };
+                match next {
+                    Next::Found(Some((path, seen))) => {
+                        let read = tokio::spawn(pipeline.clone().process(path.clone(), seen));
+                        pending.push_back((path, read));
+                    }
+                    Next::Found(None) => walking = false,
+                    Next::Read(result) => {
+                        let (path, _) = pending.pop_front().unwrap();
+                        // A read that panicked fails its file like any other
+                        // read error.
+                        let result = result.unwrap_or_else(|panicked| {
+                            Err(Error::Read { path, source: std::io::Error::other(panicked) })
+                        });
+                        if let Some(file) = result.transpose()
+                            && tx.send(file).await.is_err()
+                        {
+                            break;
+                        }
+                    }
+                }
+            }
+            for (_, handle) in pending {
+                handle.abort();
+            }
+        });
+        rx
+    }
+
+    /// Up to `limit` bytes from the start of the file.
+    async fn read_head(&self, path: &Path, limit: u64) -> std::io::Result<Vec<u8>> {
+        match &self.archive {
+            Some(archive) => Ok(archived(archive, path)?.head(limit).to_vec()),
+            None => read_head(path, limit).await,
+        }
+    }
+
+    /// `read`, failing with [`TimedOut`](std::io::ErrorKind::TimedOut) past
+    /// the options' read timeout.
+    async fn timed<T>(&self, read: impl Future<Output = std::io::Result<T>>) -> std::io::Result<T> {
+        let Some(limit) = self.transformer.options.read_timeout else {
+            return read.await;
+        };
+        tokio::time::timeout(limit, read).await.unwrap_or_else(|_| {
+            let message = format!("timed out after {}s", limit.as_secs_f64());
+            Err(std::io::Error::new(std::io::ErrorKind::TimedOut, message))
+        })
+    }
+
+    /// The file's size and modification time, its content with the number of
+    /// invalid UTF-8 sequences [`Options::lossy`](crate::Options::lossy)
+    /// replaced, and whether it changed since the walk saw it as `seen`, or
+    /// while it was read. A change means one more read under
+    /// [`OnChange::Reread`], which counts as a change only if the file
+    /// changes again.
+    async fn read(&self, path: &Path, seen: Option<Stamp>) -> std::io::Result<(Stamp, FileContent, usize, bool)> {
+        let lossy = self.transformer.options.lossy;
+        if let Some(archive) = &self.archive {
+            let entry = archived(archive, path)?;
+            let (content, invalid) = entry.content(lossy);
+            return Ok((Stamp { size: entry.size(), modified: entry.modified }, content, invalid, false));
+        }
+        let mut reads = match self.transformer.options.on_change {
+            OnChange::Reread => 2,
+            OnChange::Mark => 1,
+        };
+        let mut expected = seen;
+        loop {
+            let (before, bytes) = read_file(path).await?;
+            let after = Stamp::of(&fs::metadata(long_path(path)).await?);
+            let changed = expected.is_some_and(|expected| expected != before) || after != before;
+            reads -= 1;
+            if !changed || reads == 0 {
+                let (content, invalid) = FileContent::decode(bytes, lossy);
+                return Ok((before, content, invalid, changed));
+            }
+            expected = Some(after);
+        }
+    }
+
+    /// Reads and transforms one file. Returns `None` when it's skipped.
+    async fn process(self, path: PathBuf, seen: Option<Stamp>) -> Result<Option<FileEntry>> {
+        let transformer = &self.transformer;
+        let started = self.reads.is_some().then(Instant::now);
+
+        // Only the start is searched, so a file can be dropped before it's
+        // read in full.
+        if transformer.checks_head() {
+            let head = match self.timed(self.read_head(&path, GREP_EXCLUDE_HEAD)).await {
+                Ok(head) => head,
+                Err(source) => return Err(Error::Read { path, source }),
+            };
+            if transformer.excludes_head(&path, &head) {
+                return Ok(None);
+            }
+        }
+
+        let (stamp, read, invalid_utf8, changed) = match self.timed(self.read(&path, seen)).await {
+            Ok(read) => read,
+            Err(source) => return Err(Error::Read { path, source }),
+        };
+        if let (Some(reads), Some(started)) = (&self.reads, started) {
+            let timing = ReadTiming { path: path.clone(), duration: started.elapsed(), size: stamp.size };
+            reads.lock().unwrap().push(timing);
+        }
+        let mark = |mut file: FileEntry| {
+            if changed {
+                transformer.summary.lock().unwrap().changed.push(file.path.clone());
+                file.changed = true;
+            }
+            file.invalid_utf8 = invalid_utf8;
+            file
+        };
+        let mut file = match transformer.select(path, stamp.size, stamp.modified, read) {
+            Step::Done(file) => return Ok(file.map(mark)),
+            Step::Text(file) => file,
+        };
+        let rel = file.path.strip_prefix(&transformer.options.root).unwrap_or(&file.path);
+        if let Some(filters) = &self.filters
+            && let Some(command) = filters.command_for(rel)
+        {
+            match filters.run(command, &file.content).await {
+                Ok(filtered) => file.content = filtered,
+                Err(reason) => transformer.failures.lock().unwrap().push(Error::FilterFailed {
+                    path: file.path.clone(),
+                    command: command.to_string(),
+                    reason,
+                }),
+            }
+        }
+        Ok(Some(mark(transformer.finish(file))))
+    }
+}
+
+async fn read_insert(insert: Option<&Insert>) -> Result<Option<String>> {
+    match insert {
+        None => Ok(None),
+        Some(Insert::Text(text)) => Ok(Some(text.clone())),
+        Some(Insert::File(path)) => fs::read_to_string(long_path(path))
+            .await
+            .map(Some)
+            .map_err(|source| Error::Read { path: path.clone(), source }),
+    }
+}
+
+/// Up to `limit` bytes from the start of the file.
+async fn read_head(path: &Path, limit: u64) -> std::io::Result<Vec<u8>> {
+    let mut head = Vec::new();
+    fs::File::open(long_path(path)).await?.take(limit).read_to_end(&mut head).await?;
+    Ok(head)
+}
+
+/// What the processing task waits on: the walk finding a file or finishing,
+/// or the oldest read.
+enum Next {
+    Found(Option<FoundFile>),
+    Read(std::result::Result<Result<Option<FileEntry>>, tokio::task::JoinError>),
+}
+
+fn archived<'a>(archive: &'a Archive, path: &Path) -> std::io::Result<&'a ArchiveEntry> {
+    archive.get(path).ok_or_else(|| std::io::ErrorKind::NotFound.into())
+}
+
+/// The file's stamp from just before it's read, and its bytes.
+async fn read_file(path: &Path) -> std::io::Result<(Stamp, Vec<u8>)> {
+    let path = long_path(path);
+    let stamp = Stamp::of(&fs::metadata(&path).await?);
+    Ok((stamp, fs::read(&path).await?))
+}
@@ -0,0 +1,108 @@
+//! Ecosystem ignore profiles, applied by name or detected from marker files.
+
+use std::path::Path;
+
+/// A curated set of ignore patterns for one ecosystem's build output, caches
+/// and dependency directories.
+#[derive(Debug)]
+pub struct Profile {
+    /// The name given to `--profile`, e.g. `rust`.
+    pub name: &'static str,
+    /// Files at the root whose presence identifies the ecosystem. A leading
+    /// `*` matches by suffix, e.g. `*.csproj`.
+    pub markers: &'static [&'static str],
+    /// Gitignore-style patterns added to the ignore list.
+    pub patterns: &'static [&'static str],
+}
+
+/// Every built-in profile.
+pub const PROFILES: &[Profile] = &[
+    Profile {
+        name: "rust",
+        markers: &["Cargo.toml"],
+        patterns: &["target/", "**/*.rs.bk"],
+    },
+    Profile {
+        name: "node",
+        markers: &["package.json"],
+        patterns: &[
+            "node_modules/",
+            ".next/",
+            ".nuxt/",
+            ".svelte-kit/",
+            ".turbo/",
+            ".parcel-cache/",
+            "coverage/",
+            ".nyc_output/",
+        ],
+    },
+    Profile {
+        name: "python",
+        markers: &["pyproject.toml", "setup.py", "setup.cfg", "requirements.txt"],
+        patterns: &[
+            "__pycache__/",
+            "*.pyc",
+            "*.pyo",
+            ".venv/",
+            "venv/",
+            ".mypy_cache/",
+            ".pytest_cache/",
+            ".ruff_cache/",
+            ".tox/",
+            "*.egg-info/",
+        ],
+    },
+    Profile {
+        name: "dotnet",
+        markers: &["*.csproj", "*.fsproj", "*.sln"],
+        patterns: &["bin/", "obj/", ".vs/", "packages/", "TestResults/", "*.user"],
+    },
+    Profile {
+        name: "go",
+        markers: &["go.mod"],
+        patterns: &["vendor/"],
+    },
+    Profile {
+        name: "java",
+        markers: &["pom.xml", "build.gradle", "build.gradle.kts"],
+        patterns: &["target/", "build/", "out/", ".gradle/", ".idea/", "*.class"],
+    },
+];
+
+/// The names of every built-in profile.
+pub fn names() -> impl Iterator<Item = &'static str> {
+    PROFILES.iter().map(|profile| profile.name)
+}
+
+/// Looks up a built-in profile by name.
+pub fn find(name: &str) -> Option<&'static Profile> {
+    PROFILES.iter().find(|profile| profile.name == name)
+}
+
+/// Detects which ecosystems a project root belongs to from its marker files,
+/// returning each matching profile with the marker that identified it.
+pub fn detect(root: &Path) -> Vec<(&'static Profile, String)> {
+    let names = std::fs::read_dir(root)
+        .map(|entries| {
+            entries
+                .filter_map(|entry| entry.ok())
+                .filter_map(|entry| entry.file_name().into_string().ok())
+                .collect::<Vec<_>>()
+        })
+        .unwrap_or_default();
+    detect_names(&names)
+}
+
+/// Like [`detect`], from the names of the entries at the root.
+pub fn detect_names(names: &[String]) -> Vec<(&'static Profile, String)> {
+    PROFILES
+        .iter()
+        .filter_map(|profile| {
+            let found = profile.markers.iter().find_map(|marker| match marker.strip_prefix('*') {
+                Some(suffix) => names.iter().find(|name| name.ends_with(suffix)).cloned(),
+                None => names.iter().find(|name| name == marker).cloned(),
+            })?;
+            Some((profile, found))
+        })
+        .collect()
+}
@@ -0,0 +1,132 @@
+//! Line ranges on [`Options::only`](crate::Options::only) entries, like
+//! `src/big.rs:120-240`.
+
+use crate::grep::omission;
+use crate::{Error, Result};
+use globset::{Glob, GlobMatcher};
+use std::path::Path;
+
+/// One `START-END` range, 1-based and inclusive. A missing bound means the
+/// start or end of the file.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct LineRange {
+    pub start: Option<usize>,
+    pub end: Option<usize>,
+}
+
+/// Splits a trailing `:RANGES` suffix off `spec`, e.g. `src/big.rs:10-20,55-`
+/// gives `src/big.rs` and two ranges. Only a suffix made of digits, `-` and
+/// `,` after the last colon counts, so `C:\repo\file.rs` is left whole.
+pub fn split_line_ranges(spec: &str) -> Result<(&str, Vec<LineRange>)> {
+    let Some((path, suffix)) = spec.rsplit_once(':') else {
+        return Ok((spec, Vec::new()));
+    };
+    if path.is_empty() || suffix.is_empty() || !suffix.chars().all(|c| c.is_ascii_digit() || c == '-' || c == ',') {
+        return Ok((spec, Vec::new()));
+    }
+    let invalid = |reason: &str| Error::LineRange { spec: spec.to_string(), reason: reason.to_string() };
+    let bound = |text: &str| match text {
+        "" => Ok(None),
+        text => match text.parse::<usize>() {
+            Ok(0) => Err(invalid("lines are numbered from 1")),
+            Ok(line) => Ok(Some(line)),
+            Err(_) => Err(invalid("expected START-END, START- or -END")),
+        },
+    };
+    let ranges = suffix
+        .split(',')
+        .map(|range| {
+            let (start, end) = match range.split_once('-') {
+                Some((start, end)) => (bound(start)?, bound(end)?),
+                None => (bound(range)?, bound(range)?),
+            };
+            match (start, end) {
+                (None, None) => Err(invalid("a range needs a start or an end")),
+                (Some(start), Some(end)) if start > end => Err(invalid("a range can't end before it starts")),
+                _ => Ok(LineRange { start, end }),
+            }
+        })
+        .collect::<Result<Vec<_>>>()?;
+    Ok((path, ranges))
+}
+
+/// The only entries that carry ranges, matched against paths relative to the
+/// root.
+pub(crate) struct LineRanges(Vec<(GlobMatcher, Vec<LineRange>)>);
+
+impl LineRanges {
+    /// The ranged entries of `only`, or `None` when none has ranges.
+    pub(crate) fn new(only: &[String]) -> Result<Option<Self>> {
+        let mut ranged = Vec::new();
+        for spec in only {
+            let (pattern, ranges) = split_line_ranges(spec)?;
+            if ranges.is_empty() {
+                continue;
+            }
+            let glob = Glob::new(pattern).map_err(|source| Error::Only { pattern: pattern.to_string(), source })?;
+            ranged.push((glob.compile_matcher(), ranges));
+        }
+        Ok((!ranged.is_empty()).then_some(Self(ranged)))
+    }
+
+    /// The ranges for the file at `rel`: those of its first ranged entry.
+    pub(crate) fn for_path(&self, rel: &Path) -> Option<&[LineRange]> {
+        self.0
+            .iter()
+            .find(|(glob, _)| glob.is_match(rel))
+            .map(|(_, ranges)| ranges.as_slice())
+    }
+}
+
+/// The ranges [`resolve`] keeps of a file.
+pub(crate) struct Selection {
+    /// The ranges kept, sorted, merged and clamped to the file.
+    pub(crate) lines: Vec<(usize, usize)>,
+    /// Ranges that reached past the end of the file.
+    pub(crate) clamped: Vec<String>,
+}
+
+/// The lines of `text` that `ranges` keep.
+pub(crate) fn resolve(text: &str, ranges: &[LineRange]) -> Selection {
+    let total = text.lines().count();
+    let mut clamped = Vec::new();
+    let mut resolved = Vec::new();
+    for range in ranges {
+        let start = range.start.unwrap_or(1);
+        let end = range.end.unwrap_or(total);
+        if start > total || end > total {
+            clamped.push(format!("lines {}-{} reach past the end ({} lines)", start, end, total));
+        }
+        if start <= total {
+            resolved.push((start, end.min(total)));
+        }
+    }
+    resolved.sort();
+    let mut merged: Vec<(usize, usize)> = Vec::new();
+    for (start, end) in resolved {
+        match merged.last_mut() {
+            Some(last) if start <= last.1 + 1 => last.1 = last.1.max(end),
+            _ => merged.push((start, end)),
+        }
+    }
+    Selection { lines: merged, clamped }
+}
+
+/// Keeps only the `lines` of `text` that [`resolve`] gave, with an omission
+/// marker between ranges.
+pub(crate) fn excerpt(text: &str, lines: &[(usize, usize)]) -> String {
+    let all = text.lines().collect::<Vec<_>>();
+    let mut out = String::new();
+    let mut previous_end = None;
+    for &(start, end) in lines {
+        if let Some(previous_end) = previous_end {
+            out.push_str(&omission(start - previous_end - 1));
+        }
+        for line in &all[start - 1..end] {
+            out.push_str(line);
+            out.push('\n');
+        }
+        previous_end = Some(end);
+    }
+    out
+}
@@ -0,0 +1,85 @@
+//! Packing a git repository by URL: a shallow clone into a temporary
+//! directory, removed again once the pack is done.
+
+use anyhow::{Context, Result};
+use std::path::{Path, PathBuf};
+use std::process::Stdio;
+use tempfile::TempDir;
+use tokio::process::Command;
+
+/// Whether `path` names a git repository to clone rather than a local path.
+pub fn is_git_url(path: &str) -> bool {
+    const SCHEMES: &[&str] = &["https://", "http://", "ssh://", "git://", "file://"];
+    let scp_like = path.split_once(':').is_some_and(|(host, _)| host.contains('@') && !host.contains('/'));
+    (SCHEMES.iter().any(|scheme| path.starts_with(scheme)) || scp_like) && !Path::new(path).exists()
+}
+
+/// A shallow clone, deleted when dropped, so an error anywhere in the pack
+/// still cleans up.
+pub struct Checkout {
+    /// Holds the checkout, in a directory named after the repository.
+    _temp: TempDir,
+    pub dir: PathBuf,
+    pub url: String,
+    /// The commit that was checked out.
+    pub commit: String,
+}
+
+impl Checkout {
+    /// The repository's name: the URL's last segment without `.git`.
+    pub fn name(&self) -> &str {
+        self.dir.file_name().and_then(|name| name.to_str()).unwrap_or("repo")
+    }
+
+    /// The line recorded at the top of the bundle for provenance.
+    pub fn provenance(&self) -> String {
+        format!("Packed from {} at commit {}", self.url, self.commit)
+    }
+}
+
+/// Runs git with `args`, leaving stdin and stderr on the terminal so
+/// credential helpers and prompts work, and returns its stdout.
+async fn git(args: &[&str]) -> Result<String> {
+    // `output` would capture stderr too.
+    let output = Command::new("git")
+        .args(args)
+        .stdin(Stdio::inherit())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::inherit())
+        .spawn()
+        .context("Failed to run git; packing a repository by URL needs git on the PATH")?
+        .wait_with_output()
+        .await?;
+    if !output.status.success() {
+        anyhow::bail!("git failed ({})", output.status);
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).trim().to_string())
+}
+
+/// Clones `url` at depth 1 into a new temporary directory, checking out
+/// `rev`, a branch or tag, when given and the default branch otherwise.
+pub async fn clone(url: &str, rev: Option<&str>) -> Result<Checkout> {
+    let name = url.trim_end_matches('/').rsplit(['/', ':']).next().unwrap_or_default();
+    let name = Some(name.trim_end_matches(".git")).filter(|name| !name.is_empty()).unwrap_or("repo");
+    let temp = tempfile::Builder::new()
+        .prefix("owo-remote-")
+        .tempdir()
+        .context("Failed to create a temporary directory for the clone")?;
+    let dir = temp.path().join(name);
+    let mut checkout = Checkout { dir, _temp: temp, url: url.to_string(), commit: String::new() };
+
+    let dir = checkout.dir.to_string_lossy().into_owned();
+    let mut args = vec!["clone", "--quiet", "--depth", "1", "--no-tags"];
+    if let Some(rev) = rev {
+        args.extend(["--branch", rev]);
+    }
+    args.extend(["--", url, &dir]);
+    git(&args).await.with_context(|| match rev {
+        Some(rev) => format!("Failed to clone {} at {}", url, rev),
+        None => format!("Failed to clone {}", url),
+    })?;
+    checkout.commit = git(&["-C", &dir, "rev-parse", "HEAD"])
+        .await
+        .with_context(|| format!("Failed to resolve the commit cloned from {}", url))?;
+    Ok(checkout)
+}
@@ -0,0 +1,258 @@
+//! Status output on stderr, colored when stderr is a terminal.
+
+use anstream::ColorChoice;
+use anstyle::{AnsiColor, Style};
+use owo::summary::{human_size, thousands, Timings};
+use owo::Summary;
+use std::collections::BTreeMap;
+use std::path::PathBuf;
+use std::time::Duration;
+
+const SUCCESS: Style = AnsiColor::Green.on_default().bold();
+const WARNING: Style = AnsiColor::Yellow.on_default().bold();
+const ERROR: Style = AnsiColor::Red.on_default().bold();
+const DIM: Style = Style::new().dimmed();
+
+/// Applies `--color`. `auto` colors only a terminal, and honors `NO_COLOR`
+/// and `CLICOLOR_FORCE`.
+pub fn set_color(choice: &str) {
+    let choice = match choice {
+        "always" => ColorChoice::Always,
+        "never" => ColorChoice::Never,
+        _ => ColorChoice::Auto,
+    };
+    choice.write_global();
+}
+
+pub fn warning(message: &str) {
+    anstream::eprintln!("{WARNING}warning:{WARNING:#} {}", message);
+}
+
+pub fn error(message: &str) {
+    anstream::eprintln!("{ERROR}Error:{ERROR:#} {}", message);
+}
+
+/// `owo diff` for a terminal: a line per added, removed or modified file,
+/// with line counts under `stat` and unified diffs under `content`.
+pub fn diff(diff: &owo::diff::BundleDiff, stat: bool, content: bool) {
+    const ADDED: Style = AnsiColor::Green.on_default();
+    const REMOVED: Style = AnsiColor::Red.on_default();
+    const HUNK: Style = AnsiColor::Cyan.on_default();
+
+    for path in &diff.added {
+        anstream::println!("{ADDED}added{ADDED:#}     {}", path);
+    }
+    for path in &diff.removed {
+        anstream::println!("{REMOVED}removed{REMOVED:#}   {}", path);
+    }
+    let width = diff.modified.iter().map(|file| file.path.chars().count()).max().unwrap_or(0);
+    for file in &diff.modified {
+        match stat {
+            true => anstream::println!(
+                "{WARNING}modified{WARNING:#}  {:width$}  {ADDED}+{}{ADDED:#} {REMOVED}-{}{REMOVED:#}",
+                file.path,
+                file.lines_added,
+                file.lines_removed
+            ),
+            false => anstream::println!("{WARNING}modified{WARNING:#}  {}", file.path),
+        }
+    }
+    anstream::println!(
+        "{DIM}{} added, {} removed, {} modified{DIM:#}",
+        diff.added.len(),
+        diff.removed.len(),
+        diff.modified.len()
+    );
+
+    if !content {
+        return;
+    }
+    for text in diff.modified.iter().filter_map(|file| file.diff.as_deref()) {
+        anstream::println!();
+        for line in text.lines() {
+            let style = match line.as_bytes().first() {
+                Some(b'+') if !line.starts_with("+++") => ADDED,
+                Some(b'-') if !line.starts_with("---") => REMOVED,
+                Some(b'@') => HUNK,
+                _ if line.starts_with("+++") || line.starts_with("---") => Style::new().bold(),
+                _ => Style::new(),
+            };
+            anstream::println!("{style}{}{style:#}", line);
+        }
+    }
+}
+
+/// Whether `--max-output-size` cut any output short, and by how many files.
+pub fn size_limit(limit: u64, truncated: &[(String, usize)]) {
+    if truncated.is_empty() {
+        anstream::eprintln!("{DIM}Every output is within --max-output-size {}{DIM:#}", human_size(limit));
+    }
+    for (path, omitted) in truncated {
+        anstream::eprintln!(
+            "{WARNING}Truncated{WARNING:#} {} to stay under {}: {} file(s) omitted",
+            path,
+            human_size(limit),
+            omitted
+        );
+    }
+}
+
+/// Warns about the key and credentials files withheld from what was
+/// `skipped`, one line each with the pattern it matched.
+pub fn sensitive(skipped: &[(PathBuf, String)]) {
+    let withheld = skipped
+        .iter()
+        .filter_map(|(path, reason)| Some((path, reason.strip_prefix("sensitive: ")?)))
+        .collect::<Vec<_>>();
+    if withheld.is_empty() {
+        return;
+    }
+    warning(&format!(
+        "withheld {} key or credentials file(s); --allow-sensitive=GLOB packs them",
+        withheld.len()
+    ));
+    for (path, pattern) in withheld {
+        anstream::eprintln!("  {} {DIM}({}){DIM:#}", path.display(), pattern);
+    }
+}
+
+/// `--timings`: how long each phase took, the spread of read times, and the
+/// slowest files.
+pub fn timings(timings: &Timings, render: Duration, write: Duration) {
+    let seconds = timings.reading.as_secs_f64();
+    let rate = match seconds > 0.0 {
+        true => format!("{}/s", human_size((timings.bytes() as f64 / seconds) as u64)),
+        false => "-".to_string(),
+    };
+    anstream::eprintln!("{SUCCESS}Timings{SUCCESS:#}");
+    anstream::eprintln!("  walk    {:>9.2?}  {} entries", timings.walk, thousands(timings.entries as u64));
+    anstream::eprintln!(
+        "  read    {:>9.2?}  {} file(s), {}, {}",
+        timings.reading,
+        thousands(timings.reads.len() as u64),
+        human_size(timings.bytes()),
+        rate
+    );
+    anstream::eprintln!(
+        "          {DIM}per file p50 {:.2?}, p90 {:.2?}, p99 {:.2?}, max {:.2?}{DIM:#}",
+        timings.percentile(50),
+        timings.percentile(90),
+        timings.percentile(99),
+        timings.percentile(100)
+    );
+    anstream::eprintln!("  render  {:>9.2?}", render);
+    anstream::eprintln!("  write   {:>9.2?}", write);
+    let slowest = timings.slowest(10);
+    if !slowest.is_empty() {
+        anstream::eprintln!("Slowest reads:");
+    }
+    for read in slowest {
+        anstream::eprintln!("  {:>9.2?}  {} {DIM}({}){DIM:#}", read.duration, read.path.display(), human_size(read.size));
+    }
+}
+
+/// The paths `--from-patch` named that aren't under `PATH`, like files it
+/// deletes.
+pub fn missing_from_patch(missing: &[&String]) {
+    if missing.is_empty() {
+        return;
+    }
+    anstream::eprintln!("{WARNING}Missing{WARNING:#} {} path(s) the patch touches:", missing.len());
+    for path in missing {
+        anstream::eprintln!("  {}", path);
+    }
+}
+
+/// The reason a file was skipped, without its details: `lockfile` for
+/// `lockfile, 11.0 KB`, `generated` for `generated: suffix .min.js`.
+fn reason_kind(reason: &str) -> &str {
+    reason.split([',', ':']).next().unwrap_or(reason)
+}
+
+/// What a pack did, in a few lines: files packed, every output written with
+/// its size, skips by reason, match counts per file for `--grep`, and the
+/// transformations applied. Generated files are listed with the heuristic
+/// that fired, so a false positive is easy to spot, and lockfiles with their
+/// sizes, so the reader knows dependency info was left out; `verbose` adds a
+/// line per other skipped file and per redacted file.
+pub fn summary(summary: &mut Summary, files: usize, outputs: &[(String, u64)], elapsed: Duration, verbose: bool) {
+    let into = match outputs {
+        [(path, size)] => format!("{} ({})", path, human_size(*size)),
+        _ => format!("{} outputs", outputs.len()),
+    };
+    anstream::eprintln!(
+        "{SUCCESS}Packed{SUCCESS:#} {} file{} into {} {DIM}in {:.2}s{DIM:#}",
+        files,
+        if files == 1 { "" } else { "s" },
+        into,
+        elapsed.as_secs_f64()
+    );
+    if outputs.len() > 1 {
+        for (path, size) in outputs {
+            anstream::eprintln!("  {} {DIM}({}){DIM:#}", path, human_size(*size));
+        }
+    }
+
+    summary.skipped.sort();
+    if !summary.skipped.is_empty() {
+        let mut kinds = BTreeMap::new();
+        for (_, reason) in &summary.skipped {
+            *kinds.entry(reason_kind(reason)).or_insert(0) += 1;
+        }
+        let kinds = kinds.iter().map(|(kind, count)| format!("{} {}", count, kind)).collect::<Vec<_>>();
+        anstream::eprintln!("{WARNING}Skipped{WARNING:#} {}: {}", summary.skipped.len(), kinds.join(", "));
+        for (path, reason) in &summary.skipped {
+            if verbose || matches!(reason_kind(reason), "generated" | "lockfile") {
+                anstream::eprintln!("  {} {DIM}({}){DIM:#}", path.display(), reason);
+            }
+        }
+    }
+
+    summary.grep_matches.sort();
+    if !summary.grep_matches.is_empty() {
+        let lines = summary.grep_matches.iter().map(|(_, count)| count).sum::<usize>();
+        anstream::eprintln!("Matched {} line(s) in {} file(s)", lines, summary.grep_matches.len());
+        for (path, count) in &summary.grep_matches {
+            anstream::eprintln!("  {} {DIM}({}){DIM:#}", path.display(), count);
+        }
+    }
+
+    summary.redactions.sort();
+    if !summary.redactions.is_empty() {
+        let secrets = summary.redactions.iter().map(|(_, count)| count).sum::<usize>();
+        anstream::eprintln!(
+            "{WARNING}Redacted{WARNING:#} {} secret(s) in {} file(s)",
+            secrets,
+            summary.redactions.len()
+        );
+        if verbose {
+            for (path, count) in &summary.redactions {
+                anstream::eprintln!("  {} {DIM}({}){DIM:#}", path.display(), count);
+            }
+        }
+    }
+
+    summary.changed.sort();
+    if !summary.changed.is_empty() {
+        anstream::eprintln!(
+            "{WARNING}Changed{WARNING:#} {} file(s) while reading them; their sections are marked",
+            summary.changed.len()
+        );
+        for path in &summary.changed {
+            anstream::eprintln!("  {}", path.display());
+        }
+    }
+
+    if summary.license_headers_stripped > 0 {
+        anstream::eprintln!("Stripped {} license header(s)", summary.license_headers_stripped);
+    }
+    if summary.ansi_stripped > 0 {
+        anstream::eprintln!("Stripped ANSI escapes from {} file(s)", summary.ansi_stripped);
+    }
+    if summary.blank_lines_removed > 0 {
+        anstream::eprintln!("Squeezed {} blank line(s)", summary.blank_lines_removed);
+    }
+    if summary.lines_wrapped > 0 {
+        anstream::eprintln!("Wrapped {} long line(s)", summary.lines_wrapped);
+    }
+}
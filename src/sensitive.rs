@@ -0,0 +1,121 @@
+//! Files that are almost always key material or credentials, withheld by
+//! name whatever the ignore patterns say.
+
+use crate::{Error, Options, Result};
+use globset::{GlobBuilder, GlobMatcher};
+use std::path::{Component, Path};
+
+/// Globs for the names of key and credential files, matched
+/// case-insensitively at any depth. Withheld unless
+/// [`Options::allow_sensitive`](crate::Options::allow_sensitive) lets them in.
+pub const SENSITIVE_NAMES: &[&str] = &[
+    "id_rsa",
+    "id_dsa",
+    "id_ecdsa",
+    "id_ed25519",
+    "*.pem",
+    "*.key",
+    "*.p12",
+    "*.pfx",
+    "*.keystore",
+    "*.jks",
+    "credentials",
+    ".netrc",
+    "_netrc",
+    ".pgpass",
+    ".htpasswd",
+    "kubeconfig",
+];
+
+/// Registry configs that are withheld only when they hold a token, with the
+/// keys that give one away.
+const TOKEN_FILES: &[(&str, &[&str])] = &[
+    (".npmrc", &["_authToken", "_auth", "_password"]),
+    (".yarnrc.yml", &["npmAuthToken"]),
+    (".pypirc", &["password"]),
+];
+
+/// A sensitive-file glob. One without a `/` matches names at any depth; one
+/// with a `/` is matched against paths from the root.
+struct NamePattern {
+    pattern: String,
+    anchored: bool,
+    glob: GlobMatcher,
+}
+
+impl NamePattern {
+    fn new(pattern: &str) -> Result<Self> {
+        let anchored = pattern.contains('/');
+        let glob = GlobBuilder::new(pattern.trim_start_matches('/'))
+            .case_insensitive(true)
+            .literal_separator(true)
+            .build()
+            .map_err(|source| Error::Sensitive { pattern: pattern.to_string(), source })?;
+        Ok(Self { pattern: pattern.to_string(), anchored, glob: glob.compile_matcher() })
+    }
+
+    fn is_match(&self, rel: &Path, name: &str) -> bool {
+        match self.anchored {
+            false => self.glob.is_match(name),
+            true => self.glob.is_match(slashed(rel)),
+        }
+    }
+}
+
+/// `rel` with `/` between its components on every platform.
+fn slashed(rel: &Path) -> String {
+    let names = rel.components().filter_map(|component| match component {
+        Component::Normal(name) => Some(name.to_string_lossy()),
+        _ => None,
+    });
+    names.collect::<Vec<_>>().join("/")
+}
+
+/// The sensitive-file guard: [`SENSITIVE_NAMES`] and the options' extra
+/// globs, less what [`Options::allow_sensitive`](crate::Options::allow_sensitive)
+/// lets in.
+pub(crate) struct Sensitive {
+    patterns: Vec<NamePattern>,
+    allowed: Vec<NamePattern>,
+}
+
+impl Sensitive {
+    pub(crate) fn new(options: &Options) -> Result<Self> {
+        let names = SENSITIVE_NAMES.iter().copied().chain(options.sensitive.iter().map(String::as_str));
+        Ok(Self {
+            patterns: names.map(NamePattern::new).collect::<Result<_>>()?,
+            allowed: options.allow_sensitive.iter().map(|glob| NamePattern::new(glob)).collect::<Result<_>>()?,
+        })
+    }
+
+    /// Why the file at `rel` is withheld, like `*.pem` or `.npmrc with a
+    /// token`, or `None` when it isn't sensitive or is allowed. `read` gives
+    /// the file's bytes, for configs that are sensitive only with a token.
+    pub(crate) fn withholds(&self, rel: &Path, read: impl FnOnce() -> Option<Vec<u8>>) -> Option<String> {
+        match self.allows(rel) {
+            Some(_) => None,
+            None => self.matched(rel, read),
+        }
+    }
+
+    /// The sensitive pattern the file at `rel` matches, allowed or not.
+    pub(crate) fn matched(&self, rel: &Path, read: impl FnOnce() -> Option<Vec<u8>>) -> Option<String> {
+        let name = rel.file_name()?.to_string_lossy();
+        if let Some(pattern) = self.patterns.iter().find(|pattern| pattern.is_match(rel, &name)) {
+            return Some(pattern.pattern.clone());
+        }
+        let (file, keys) = TOKEN_FILES.iter().find(|(file, _)| name.eq_ignore_ascii_case(file))?;
+        let text = String::from_utf8(read()?).ok()?;
+        let token = text.lines().map(str::trim_start).any(|line| {
+            keys.iter().any(|key| line.contains(key) && !line.starts_with('#') && !line.starts_with(';'))
+        });
+        token.then(|| format!("{} with a token", file))
+    }
+
+    /// The allow-sensitive glob that lets the file at `rel` in.
+    pub(crate) fn allows(&self, rel: &Path) -> Option<&str> {
+        let name = rel.file_name()?.to_string_lossy();
+        let allowed = self.allowed.iter().find(|allowed| allowed.is_match(rel, &name))?;
+        Some(&allowed.pattern)
+    }
+}
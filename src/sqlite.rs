@@ -0,0 +1,170 @@
+//! The SQLite output: every run appended to a database, for querying across
+//! snapshots.
+
+use anyhow::{Context, Result};
+use owo::{Bundle, FileContent};
+use rusqlite::{params, Connection};
+use sha2::{Digest, Sha256};
+use std::path::Path;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// The schema, one migration per version. A database's `user_version` is the
+/// number of migrations applied to it; add new ones at the end and never
+/// change old ones.
+const MIGRATIONS: &[&str] = &[
+    "CREATE TABLE runs (
+        id INTEGER PRIMARY KEY,
+        timestamp INTEGER NOT NULL,
+        root TEXT NOT NULL,
+        options TEXT NOT NULL
+    );
+    CREATE TABLE files (
+        run_id INTEGER NOT NULL REFERENCES runs(id) ON DELETE CASCADE,
+        path TEXT NOT NULL,
+        language TEXT,
+        size INTEGER NOT NULL,
+        line_count INTEGER,
+        content TEXT,
+        content_hash TEXT
+    );
+    CREATE INDEX files_run_id ON files(run_id);
+    CREATE INDEX files_path ON files(path);",
+];
+
+/// Opens the database at `path`, creating it or bringing its schema up to
+/// date.
+fn open(path: &Path) -> Result<Connection> {
+    let mut db = Connection::open(path)?;
+    db.pragma_update(None, "foreign_keys", true)?;
+    let version = db.pragma_query_value(None, "user_version", |row| row.get::<_, i64>(0))? as usize;
+    if version > MIGRATIONS.len() {
+        anyhow::bail!(
+            "{} has schema version {}, newer than this owo understands ({}); upgrade owo to write to it",
+            path.display(),
+            version,
+            MIGRATIONS.len()
+        );
+    }
+    let tx = db.transaction()?;
+    for migration in &MIGRATIONS[version..] {
+        tx.execute_batch(migration)?;
+    }
+    tx.pragma_update(None, "user_version", MIGRATIONS.len() as i64)?;
+    tx.commit()?;
+    Ok(db)
+}
+
+fn sha256(bytes: &[u8]) -> String {
+    Sha256::digest(bytes).iter().map(|byte| format!("{:02x}", byte)).collect()
+}
+
+/// Appends `bundle` to the database at `path` as a new run, in one
+/// transaction, recording `options` (the command line) with it. With
+/// `prune`, only that many of the latest runs are kept. Returns the
+/// database's size afterwards.
+pub fn write(path: &Path, bundle: &Bundle, options: &[String], prune: Option<usize>) -> Result<u64> {
+    let mut db = open(path).with_context(|| format!("Failed to write {}", path.display()))?;
+    let tx = db.transaction()?;
+    let timestamp = SystemTime::now().duration_since(UNIX_EPOCH).map_or(0, |d| d.as_secs() as i64);
+    tx.execute(
+        "INSERT INTO runs (timestamp, root, options) VALUES (?1, ?2, ?3)",
+        params![timestamp, bundle.root.display().to_string(), serde_json::to_string(options)?],
+    )?;
+    let run_id = tx.last_insert_rowid();
+    {
+        let mut insert = tx.prepare(
+            "INSERT INTO files (run_id, path, language, size, line_count, content, content_hash)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)",
+        )?;
+        for file in &bundle.files {
+            let (lines, content, hash) = match &file.content {
+                FileContent::Text(text) => {
+                    (Some(text.lines().count() as i64), Some(text.as_str()), Some(sha256(text.as_bytes())))
+                }
+                // Binary contents aren't kept in the bundle, so they're hashed
+                // from disk.
+                FileContent::Binary(_) => {
+                    let bytes = std::fs::read(owo::long_path(&file.path)).ok();
+                    (None, None, bytes.map(|bytes| sha256(&bytes)))
+                }
+            };
+            insert.execute(params![
+                run_id,
+                file.path.display().to_string(),
+                file.fence_tag,
+                file.size as i64,
+                lines,
+                content,
+                hash
+            ])?;
+        }
+    }
+    if let Some(keep) = prune {
+        tx.execute(
+            "DELETE FROM runs WHERE id NOT IN (SELECT id FROM runs ORDER BY id DESC LIMIT ?1)",
+            params![keep as i64],
+        )?;
+    }
+    tx.commit()?;
+    Ok(std::fs::metadata(path).map_or(0, |metadata| metadata.len()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn bundle(files: &[(&str, &str)]) -> Bundle {
+        owo::bundle_files(files.iter().copied(), &owo::Options::new("/repo")).unwrap()
+    }
+
+    fn count(db: &Connection, table: &str) -> i64 {
+        db.query_row(&format!("SELECT COUNT(*) FROM {}", table), [], |row| row.get(0)).unwrap()
+    }
+
+    #[test]
+    fn prune_keeps_the_latest_runs_and_their_files() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("runs.db");
+        write(&path, &bundle(&[("a.rs", "fn a() {}\n"), ("b.rs", "fn b() {}\n")]), &[], None).unwrap();
+        write(&path, &bundle(&[("c.rs", "fn c() {}\n")]), &[], None).unwrap();
+        let db = Connection::open(&path).unwrap();
+        assert_eq!((count(&db, "runs"), count(&db, "files")), (2, 3));
+        drop(db);
+
+        write(&path, &bundle(&[("d.rs", "fn d() {}\n")]), &[], Some(1)).unwrap();
+        let db = Connection::open(&path).unwrap();
+        assert_eq!((count(&db, "runs"), count(&db, "files")), (1, 1));
+        let kept: String = db.query_row("SELECT path FROM files", [], |row| row.get(0)).unwrap();
+        assert_eq!(Path::new(&kept).file_name().unwrap(), "d.rs");
+    }
+
+    #[test]
+    fn older_databases_are_migrated() {
+        for version in 0..MIGRATIONS.len() {
+            let dir = tempfile::tempdir().unwrap();
+            let path = dir.path().join("old.db");
+            let old = Connection::open(&path).unwrap();
+            for migration in &MIGRATIONS[..version] {
+                old.execute_batch(migration).unwrap();
+            }
+            old.pragma_update(None, "user_version", version as i64).unwrap();
+            drop(old);
+
+            write(&path, &bundle(&[("a.rs", "fn a() {}\n")]), &[], None).unwrap();
+            let db = Connection::open(&path).unwrap();
+            let migrated = db.pragma_query_value(None, "user_version", |row| row.get::<_, i64>(0)).unwrap();
+            assert_eq!(migrated as usize, MIGRATIONS.len(), "from version {}", version);
+            assert_eq!((count(&db, "runs"), count(&db, "files")), (1, 1), "from version {}", version);
+        }
+    }
+
+    #[test]
+    fn newer_databases_are_left_alone() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("new.db");
+        let newer = MIGRATIONS.len() as i64 + 1;
+        Connection::open(&path).unwrap().pragma_update(None, "user_version", newer).unwrap();
+        let error = write(&path, &bundle(&[("a.rs", "fn a() {}\n")]), &[], None).unwrap_err();
+        assert!(format!("{:#}", error).contains("newer than this owo understands"), "{:#}", error);
+    }
+}
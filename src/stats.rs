@@ -0,0 +1,102 @@
+//! Per-language file, line and byte counts over the files a pack would
+//! include.
+
+use crate::pipeline::{sort_failures, Pipeline};
+use crate::profiles::Profile;
+use crate::{walk, Error, FileContent, FileEntry, Options, Result};
+use serde::Serialize;
+
+/// Counts for one language, or for all of them.
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct LanguageStats {
+    /// The fence tag, as in the bundle: `rs`, `toml`, `makefile`. `other` for
+    /// text files without one and `binary` for binary files.
+    pub language: String,
+    pub files: usize,
+    /// Lines in text files, blank ones included. Binary files count bytes
+    /// only.
+    pub lines: usize,
+    pub blank_lines: usize,
+    pub bytes: u64,
+}
+
+impl LanguageStats {
+    fn add(&mut self, file: &FileEntry) {
+        self.files += 1;
+        self.bytes += file.size;
+        if let FileContent::Text(text) = &file.content {
+            for line in text.lines() {
+                self.lines += 1;
+                self.blank_lines += line.trim().is_empty() as usize;
+            }
+        }
+    }
+}
+
+/// What [`stats`] found.
+#[derive(Debug, Default)]
+pub struct Stats {
+    /// One entry per language, most lines first.
+    pub languages: Vec<LanguageStats>,
+    pub total: LanguageStats,
+    /// Profiles detected from marker files, with the marker for each.
+    pub detected_profiles: Vec<(&'static Profile, String)>,
+    /// The profiles whose ignore patterns were applied.
+    pub profiles: Vec<&'static Profile>,
+    /// Files and directories that couldn't be read; see
+    /// [`Bundle::failures`](crate::Bundle::failures).
+    pub failures: Vec<Error>,
+    /// User-supplied ignore patterns that matched nothing.
+    pub unmatched_patterns: Vec<String>,
+    /// Only globs that matched nothing.
+    pub unmatched_only: Vec<String>,
+}
+
+/// Counts the files [`generate`](crate::generate) would pack, by language,
+/// without keeping their contents around. Options that transform contents
+/// apply before counting.
+pub async fn stats(options: &Options) -> Result<Stats> {
+    let (walk, paths) = walk::walk_files(options, false)?;
+    let pipeline = Pipeline::new(options)?;
+    let mut files = pipeline.spawn(paths, walk.archive.clone());
+    let walking = tokio::task::spawn_blocking(move || walk.run());
+
+    let mut stats = Stats {
+        total: LanguageStats { language: "total".to_string(), ..LanguageStats::default() },
+        ..Stats::default()
+    };
+    while let Some(file) = files.recv().await {
+        let file = match file {
+            Ok(file) => file,
+            Err(e) => {
+                stats.failures.push(e);
+                continue;
+            }
+        };
+        let language = match (&file.content, &file.fence_tag) {
+            (FileContent::Binary(_), _) => "binary",
+            (_, Some(tag)) => tag.as_str(),
+            (_, None) => "other",
+        };
+        let index = match stats.languages.iter().position(|entry| entry.language == language) {
+            Some(index) => index,
+            None => {
+                stats.languages.push(LanguageStats { language: language.to_string(), ..LanguageStats::default() });
+                stats.languages.len() - 1
+            }
+        };
+        stats.languages[index].add(&file);
+        stats.total.add(&file);
+    }
+
+    let walk = walking.await.expect("the walk panicked");
+    sort_failures(&mut stats.failures);
+    stats.failures.splice(0..0, walk.errors);
+    stats.languages.sort_by(|a, b| b.lines.cmp(&a.lines).then_with(|| a.language.cmp(&b.language)));
+    stats.failures.append(&mut pipeline.transformer.failures.lock().unwrap());
+    stats.detected_profiles = walk.detected;
+    stats.profiles = walk.profiles;
+    stats.unmatched_patterns = walk.unmatched_patterns;
+    stats.unmatched_only = walk.unmatched_only;
+    Ok(stats)
+}
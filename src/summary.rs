@@ -0,0 +1,112 @@
+//! Run statistics and size formatting.
+
+use std::path::PathBuf;
+use std::time::Duration;
+
+/// Statistics gathered while processing files, reported after the output is
+/// written.
+#[derive(Debug, Default)]
+pub struct Summary {
+    /// Files left out, with the reason, e.g. `generated: suffix .min.js`.
+    pub skipped: Vec<(PathBuf, String)>,
+    /// Files that had secrets redacted, with the count.
+    pub redactions: Vec<(PathBuf, usize)>,
+    /// Files selected by [`Options::grep`](crate::Options::grep), with the
+    /// number of matching lines.
+    pub grep_matches: Vec<(PathBuf, usize)>,
+    pub blank_lines_removed: usize,
+    /// Lines broken up by [`Options::wrap`](crate::Options::wrap).
+    pub lines_wrapped: usize,
+    /// Problems worth a warning that didn't stop a file, like a line range
+    /// past the end, as `path: message`.
+    pub warnings: Vec<String>,
+    pub license_headers_stripped: usize,
+    /// Files that had ANSI escape sequences removed.
+    pub ansi_stripped: usize,
+    /// Files that changed while they were read, packed with a warning on
+    /// their section.
+    pub changed: Vec<PathBuf>,
+    /// How long the walk and reads took, with
+    /// [`Options::timings`](crate::Options::timings).
+    pub timings: Option<Timings>,
+}
+
+/// How long a pack's walk and reads took.
+#[derive(Debug, Default)]
+pub struct Timings {
+    /// From the start of the walk to its end. Files are read meanwhile.
+    pub walk: Duration,
+    /// How many entries the walk reached, kept or not.
+    pub entries: usize,
+    /// From the start of the walk until the last file was read.
+    pub reading: Duration,
+    /// Every file read, in walk order.
+    pub reads: Vec<ReadTiming>,
+}
+
+impl Timings {
+    /// The read time `percent` of files took at most, by nearest rank:
+    /// `percentile(50)` is the median. Zero with no files read.
+    pub fn percentile(&self, percent: usize) -> Duration {
+        let mut durations = self.reads.iter().map(|read| read.duration).collect::<Vec<_>>();
+        durations.sort();
+        let rank = (percent * durations.len()).div_ceil(100).clamp(1, durations.len().max(1));
+        durations.get(rank - 1).copied().unwrap_or_default()
+    }
+
+    /// The `n` slowest reads, slowest first.
+    pub fn slowest(&self, n: usize) -> Vec<&ReadTiming> {
+        let mut reads = self.reads.iter().collect::<Vec<_>>();
+        reads.sort_by(|a, b| b.duration.cmp(&a.duration).then(a.path.cmp(&b.path)));
+        reads.truncate(n);
+        reads
+    }
+
+    /// The bytes of every file read.
+    pub fn bytes(&self) -> u64 {
+        self.reads.iter().map(|read| read.size).sum()
+    }
+}
+
+/// How long one file took to read, from opening it to having its content.
+#[derive(Debug)]
+pub struct ReadTiming {
+    pub path: PathBuf,
+    pub duration: Duration,
+    pub size: u64,
+}
+
+/// Formats a byte count the way people read file sizes: `512 B`, `6.2 KB`,
+/// `1.4 MB`.
+pub fn human_size(bytes: u64) -> String {
+    const UNITS: &[&str] = &["KB", "MB", "GB", "TB"];
+    if bytes < 1024 {
+        return format!("{} B", bytes);
+    }
+    let mut size = bytes as f64 / 1024.0;
+    let mut unit = 0;
+    while size >= 1024.0 && unit < UNITS.len() - 1 {
+        size /= 1024.0;
+        unit += 1;
+    }
+    format!("{:.1} {}", size, UNITS[unit])
+}
+
+/// Formats a count with thousands separators: `1,840`.
+pub fn thousands(n: u64) -> String {
+    let digits = n.to_string();
+    let mut out = String::new();
+    for (i, digit) in digits.chars().enumerate() {
+        if i > 0 && (digits.len() - i).is_multiple_of(3) {
+            out.push(',');
+        }
+        out.push(digit);
+    }
+    out
+}
+
+/// A rough token count for `bytes` of text: about four bytes per token for
+/// source code.
+pub fn estimated_tokens(bytes: u64) -> u64 {
+    bytes / 4
+}
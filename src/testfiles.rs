@@ -0,0 +1,61 @@
+//! Recognizing test code by the naming conventions of common ecosystems.
+
+use crate::Result;
+use ignore::gitignore::{Gitignore, GitignoreBuilder};
+use std::path::Path;
+
+/// Gitignore-style patterns for test files and directories, applied by
+/// [`Options::tests`](crate::Options::tests).
+pub const TEST_PATTERNS: &[&str] = &[
+    "tests/",
+    "test/",
+    "__tests__/",
+    "spec/",
+    "benches/",
+    "*_test.go",
+    "*.test.js",
+    "*.test.jsx",
+    "*.test.ts",
+    "*.test.tsx",
+    "*.spec.js",
+    "*.spec.jsx",
+    "*.spec.ts",
+    "*.spec.tsx",
+    "*Test.java",
+    "*Tests.java",
+    "test_*.py",
+    "*_test.py",
+    "*_spec.rb",
+];
+
+/// Which files to keep by whether they're test code.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum TestFiles {
+    /// Test code is packed like anything else.
+    #[default]
+    Include,
+    /// Test code is left out, unless an [`Options::only`](crate::Options::only)
+    /// glob names it.
+    Skip,
+    /// Only test code is packed.
+    Only,
+}
+
+/// Matches [`TEST_PATTERNS`] against paths under a root.
+pub(crate) struct TestMatcher(Gitignore);
+
+impl TestMatcher {
+    pub(crate) fn new(root: &Path) -> Result<Self> {
+        let mut builder = GitignoreBuilder::new(root);
+        for pattern in TEST_PATTERNS {
+            builder.add_line(None, pattern)?;
+        }
+        Ok(Self(builder.build()?))
+    }
+
+    /// Whether the file at `path`, as walked from the root, is test code or
+    /// lives in a test directory.
+    pub(crate) fn is_test(&self, path: &Path) -> bool {
+        self.0.matched_path_or_any_parents(path, false).is_ignore()
+    }
+}
@@ -0,0 +1,321 @@
+use unicode_width::{UnicodeWidthChar, UnicodeWidthStr};
+
+/// Collapses every run of more than `max` consecutive blank lines down to
+/// `max`, returning the new content and the number of lines removed.
+///
+/// Whitespace-only lines count as blank.
+pub fn squeeze_blank_lines(content: &str, max: usize) -> (String, usize) {
+    let mut out = String::with_capacity(content.len());
+    let mut blank_run = 0;
+    let mut removed = 0;
+
+    for line in content.split_inclusive('\n') {
+        if line.trim().is_empty() {
+            blank_run += 1;
+            if blank_run > max {
+                removed += 1;
+                continue;
+            }
+        } else {
+            blank_run = 0;
+        }
+        out.push_str(line);
+    }
+
+    (out, removed)
+}
+
+/// Strips trailing spaces and tabs from every line, keeping line endings.
+pub fn trim_trailing_whitespace(content: &str) -> String {
+    let mut out = String::with_capacity(content.len());
+
+    for line in content.split_inclusive('\n') {
+        let (body, ending) = split_line_ending(line);
+        out.push_str(body.trim_end_matches([' ', '\t']));
+        out.push_str(ending);
+    }
+
+    out
+}
+
+/// Replaces tabs in each line's leading indentation with spaces, advancing to
+/// the next multiple of `width` the way an editor's tab stops would.
+pub fn expand_leading_tabs(content: &str, width: usize) -> String {
+    let width = width.max(1);
+    let mut out = String::with_capacity(content.len());
+
+    for line in content.split_inclusive('\n') {
+        let indent_len = line.len() - line.trim_start_matches([' ', '\t']).len();
+        let mut column = 0;
+        for c in line[..indent_len].chars() {
+            if c == '\t' {
+                let stop = (column / width + 1) * width;
+                out.extend(std::iter::repeat_n(' ', stop - column));
+                column = stop;
+            } else {
+                out.push(c);
+                column += 1;
+            }
+        }
+        out.push_str(&line[indent_len..]);
+    }
+
+    out
+}
+
+/// Removes the longest leading whitespace prefix shared by every non-blank
+/// line. Blank lines lose as much of that prefix as they have.
+pub fn dedent(content: &str) -> String {
+    let common = content
+        .lines()
+        .filter(|line| !line.trim().is_empty())
+        .map(|line| &line[..line.len() - line.trim_start_matches([' ', '\t']).len()])
+        .reduce(|common, indent| {
+            let shared = common
+                .bytes()
+                .zip(indent.bytes())
+                .take_while(|(a, b)| a == b)
+                .count();
+            &common[..shared]
+        })
+        .unwrap_or("");
+
+    if common.is_empty() {
+        return content.to_string();
+    }
+
+    let mut out = String::with_capacity(content.len());
+    for line in content.split_inclusive('\n') {
+        match line.strip_prefix(common) {
+            Some(rest) => out.push_str(rest),
+            None => out.push_str(line.trim_start_matches([' ', '\t'])),
+        }
+    }
+    out
+}
+
+/// Breaks every line wider than `width` display columns into segments that
+/// fit, each but the last ending in `marker` and the line's own line ending.
+/// A break goes after the last whitespace that fits, past the indentation,
+/// or else at the width; the text itself is kept whole, so dropping the
+/// markers and breaks gives back the line. CJK characters and emoji count
+/// as two columns, and tabs advance to the next multiple of `tab`. Returns
+/// the new content and the number of lines wrapped.
+pub fn wrap_lines(content: &str, width: usize, marker: &str, tab: usize) -> (String, usize) {
+    let tab = tab.max(1);
+    let budget = width.saturating_sub(marker.width()).max(1);
+    let columns = |column: usize, c: char| match c {
+        '\t' => tab - column % tab,
+        c => c.width().unwrap_or(0),
+    };
+    let mut out = String::with_capacity(content.len());
+    let mut wrapped = 0;
+
+    for line in content.split_inclusive('\n') {
+        let (mut rest, ending) = split_line_ending(line);
+        let mut cut_any = false;
+        loop {
+            // Where the text passes `budget`, and whether all of it fits in
+            // `width` after all, needing no marker. The walk stops soon past
+            // `budget`, so each line is walked about once.
+            let (mut column, mut soft, mut hard, mut text) = (0, None, None, false);
+            for (index, c) in rest.char_indices() {
+                column += columns(column, c);
+                match (hard, c.is_whitespace()) {
+                    (Some(_), _) => {}
+                    (None, _) if column > budget => hard = Some(index),
+                    (None, true) if text => soft = Some(index + c.len_utf8()),
+                    (None, true) => {}
+                    (None, false) => text = true,
+                }
+                if column > width.max(budget) {
+                    break;
+                }
+            }
+            if column <= width {
+                break;
+            }
+            // At least one character per segment, however narrow the width.
+            let first = rest.chars().next().map_or(0, char::len_utf8);
+            let cut = soft.or(hard).unwrap_or(rest.len()).max(first);
+            out.push_str(&rest[..cut]);
+            out.push_str(marker);
+            out.push_str(if ending.is_empty() { "\n" } else { ending });
+            rest = &rest[cut..];
+            cut_any = true;
+        }
+        if cut_any {
+            wrapped += 1;
+        }
+        out.push_str(rest);
+        out.push_str(ending);
+    }
+
+    (out, wrapped)
+}
+
+/// Characters that start an escape sequence: ESC, and the 8-bit CSI, OSC,
+/// DCS, SOS, PM and APC, with the string terminator ST.
+const ESCAPE_STARTS: [char; 8] = ['\x1b', '\u{9b}', '\u{9d}', '\u{90}', '\u{98}', '\u{9e}', '\u{9f}', '\u{9c}'];
+
+/// Where [`strip_ansi`] is within an escape sequence.
+#[derive(Clone, Copy, PartialEq)]
+enum AnsiState {
+    /// Plain text.
+    Ground,
+    /// After ESC.
+    Start,
+    /// After ESC and intermediate bytes, waiting for the final one.
+    Intermediate,
+    /// In a control sequence's (CSI) parameters and intermediates.
+    Csi,
+    /// In an OSC string, which BEL also ends.
+    Osc,
+    /// In a DCS, SOS, PM or APC string.
+    Str,
+    /// After ESC in a string, which `\` turns into its terminator.
+    StrEscape,
+}
+
+/// The text without its ANSI escape sequences: CSI ones like SGR colors and
+/// cursor moves, OSC ones like window titles and hyperlinks, DCS, SOS, PM
+/// and APC strings, and two-byte escapes, in their 7-bit and 8-bit forms.
+/// Control characters inside a sequence, like a newline, are kept as a
+/// terminal would execute them, and a string left unterminated ends with
+/// its line, so a stray introducer can't swallow the rest of the file.
+/// Returns `None` when there's nothing to strip.
+pub fn strip_ansi(content: &str) -> Option<String> {
+    if !content.contains(ESCAPE_STARTS) {
+        return None;
+    }
+    let mut out = String::with_capacity(content.len());
+    let mut state = AnsiState::Ground;
+    for c in content.chars() {
+        let control = c.is_ascii_control() && c != '\x1b' && c != '\x7f';
+        state = match (state, c) {
+            (AnsiState::Ground, '\x1b') => AnsiState::Start,
+            (AnsiState::Ground, '\u{9b}') => AnsiState::Csi,
+            (AnsiState::Ground, '\u{9d}') => AnsiState::Osc,
+            (AnsiState::Ground, '\u{90}' | '\u{98}' | '\u{9e}' | '\u{9f}') => AnsiState::Str,
+            (AnsiState::Ground, '\u{9c}') => AnsiState::Ground,
+            (AnsiState::Ground, c) => {
+                out.push(c);
+                AnsiState::Ground
+            }
+            (AnsiState::Osc | AnsiState::Str, '\n') => {
+                out.push('\n');
+                AnsiState::Ground
+            }
+            (AnsiState::Osc, '\x07') | (AnsiState::Osc | AnsiState::Str, '\u{9c}') | (AnsiState::StrEscape, '\\') => {
+                AnsiState::Ground
+            }
+            (AnsiState::Osc | AnsiState::Str, '\x1b') => AnsiState::StrEscape,
+            (AnsiState::Osc | AnsiState::Str, _) => state,
+            // Any other ESC in a string ends it and starts a new sequence.
+            (AnsiState::StrEscape, c) => escape_start(c, &mut out),
+            (_, '\x1b') => AnsiState::Start,
+            (_, c) if control => {
+                out.push(c);
+                state
+            }
+            (AnsiState::Start, c) => escape_start(c, &mut out),
+            (AnsiState::Intermediate, ' '..='/') => AnsiState::Intermediate,
+            (AnsiState::Csi, ' '..='?' | '\x7f') => AnsiState::Csi,
+            (AnsiState::Intermediate | AnsiState::Csi, '@'..='~') => AnsiState::Ground,
+            // Anything else isn't part of a sequence; it's kept.
+            (_, c) => {
+                out.push(c);
+                AnsiState::Ground
+            }
+        };
+    }
+    Some(out)
+}
+
+/// The state after `c` follows ESC.
+fn escape_start(c: char, out: &mut String) -> AnsiState {
+    match c {
+        '[' => AnsiState::Csi,
+        ']' => AnsiState::Osc,
+        'P' | 'X' | '^' | '_' => AnsiState::Str,
+        '\x1b' => AnsiState::Start,
+        ' '..='/' => AnsiState::Intermediate,
+        '0'..='~' => AnsiState::Ground,
+        c => {
+            out.push(c);
+            AnsiState::Ground
+        }
+    }
+}
+
+/// Makefile recipes must be indented with real tabs, so they are exempt from
+/// tab expansion.
+pub fn is_makefile(fence_tag: Option<&str>) -> bool {
+    matches!(fence_tag, Some("makefile" | "mk"))
+}
+
+fn split_line_ending(line: &str) -> (&str, &str) {
+    if let Some(body) = line.strip_suffix("\r\n") {
+        (body, "\r\n")
+    } else if let Some(body) = line.strip_suffix('\n') {
+        (body, "\n")
+    } else {
+        (line, "")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn wraps_at_whitespace_or_the_width() {
+        for (text, width, expected, count) in [
+            ("short\n", 10, "short\n", 0),
+            ("exactly ten\n", 11, "exactly ten\n", 0),
+            ("one two three\n", 10, "one two ↩\nthree\n", 1),
+            ("abcdefghijkl", 5, "abcd↩\nefgh↩\nijkl", 1),
+            ("    indented words\r\n", 12, "    indente↩\r\nd words\r\n", 1),
+            ("界界界界界\n", 6, "界界↩\n界界界\n", 1),
+            ("\tab cd\n", 8, "\tab ↩\ncd\n", 1),
+            ("ab\n", 0, "a↩\nb↩\n\n", 1),
+        ] {
+            assert_eq!(wrap_lines(text, width, "↩", 4), (expected.to_string(), count), "{:?} at {}", text, width);
+        }
+    }
+
+    #[test]
+    fn long_lines_are_walked_once() {
+        let line = "word ".repeat(400_000);
+        let (wrapped, count) = wrap_lines(&line, 80, "↩", 4);
+        assert_eq!(count, 1);
+        // Fifteen words to a line of 79 columns and a marker.
+        assert_eq!(wrapped.lines().count(), 26_667);
+        assert_eq!(wrapped.replace("↩\n", ""), line);
+    }
+
+    #[test]
+    fn colored_cargo_output_becomes_clean_text() {
+        let colored = include_str!("../tests/fixtures/ansi/cargo-build.txt");
+        let clean = include_str!("../tests/fixtures/ansi/cargo-build.clean.txt");
+        assert_eq!(strip_ansi(colored).as_deref(), Some(clean));
+        assert_eq!(strip_ansi(clean), None);
+    }
+
+    #[test]
+    fn strips_every_kind_of_sequence() {
+        for (text, expected) in [
+            ("\x1b]0;title\x07text", "text"),
+            ("\x1b]8;;https://example.com\x1b\\link\x1b]8;;\x1b\\", "link"),
+            ("\u{9b}1mbold\u{9b}0m", "bold"),
+            ("\u{9d}0;title\u{9c}text", "text"),
+            ("\x1bPq#0\x1b\\after", "after"),
+            ("\x1b(Bplain\x1b7", "plain"),
+            ("\x1b[1\nm", "\n"),
+            ("\x1b]0;unterminated\nnext line\n", "\nnext line\n"),
+            ("a\x1b[", "a"),
+        ] {
+            assert_eq!(strip_ansi(text).as_deref(), Some(expected), "{:?}", text);
+        }
+    }
+}
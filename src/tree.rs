@@ -0,0 +1,380 @@
+//! `tree`-style and Mermaid renderings of a walk or a bundle.
+
+use crate::summary::{estimated_tokens, human_size, thousands};
+#[cfg(feature = "fs")]
+use crate::walk::Walk;
+use crate::{Bundle, FileContent};
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+
+/// The columns a tree's lines are annotated with; the default is none.
+/// Directories also show their file count, and add up their files' columns.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Annotations {
+    pub size: bool,
+    /// Estimated from the size, at about four bytes per token.
+    pub tokens: bool,
+    pub lines: bool,
+    /// Sizes as exact byte counts, like `6,342 B`, instead of `6.2 KB`.
+    pub bytes: bool,
+}
+
+impl Annotations {
+    /// Sizes alone.
+    pub fn sizes() -> Self {
+        Self { size: true, ..Self::default() }
+    }
+
+    fn any(&self) -> bool {
+        self.size || self.tokens || self.lines
+    }
+}
+
+/// A file's numbers, or for a directory the totals of the files below it.
+#[derive(Debug, Clone, Copy, Default)]
+struct Counts {
+    size: u64,
+    tokens: u64,
+    lines: u64,
+    files: usize,
+}
+
+impl Counts {
+    fn add(self, other: Counts) -> Counts {
+        Counts {
+            size: self.size + other.size,
+            tokens: self.tokens + other.tokens,
+            lines: self.lines + other.lines,
+            files: self.files + other.files,
+        }
+    }
+}
+
+#[derive(Default)]
+struct Node {
+    children: BTreeMap<String, Node>,
+    is_dir: bool,
+    counts: Counts,
+    /// Why a file was left out of the pack, like `lockfile, 11.0 KB`. It's
+    /// shown, but not counted.
+    skipped: Option<String>,
+}
+
+impl Node {
+    fn insert(&mut self, rel: &Path, is_dir: bool, counts: Counts) -> &mut Node {
+        let mut node = self;
+        for component in rel.iter() {
+            node.is_dir = true;
+            node = node.children.entry(component.to_string_lossy().into_owned()).or_default();
+        }
+        node.is_dir |= is_dir;
+        node.counts = counts;
+        node
+    }
+
+    /// Fills in directory totals, returning this node's.
+    fn aggregate(&mut self) -> Counts {
+        if !self.is_dir {
+            self.counts.files = usize::from(self.skipped.is_none());
+            return self.counts;
+        }
+        self.counts = self.children.values_mut().map(Node::aggregate).fold(Counts::default(), Counts::add);
+        self.counts
+    }
+
+    fn annotation(&self, annotations: Annotations) -> String {
+        if let Some(reason) = &self.skipped {
+            return format!("(skipped {})", reason);
+        }
+        let counts = &self.counts;
+        let mut columns = Vec::new();
+        if self.is_dir {
+            columns.push(plural(counts.files, "file", "files"));
+        }
+        if annotations.size {
+            columns.push(match annotations.bytes {
+                true => format!("{} B", thousands(counts.size)),
+                false => human_size(counts.size),
+            });
+        }
+        if annotations.tokens {
+            columns.push(format!("{} tokens", thousands(counts.tokens)));
+        }
+        if annotations.lines {
+            columns.push(format!("{} {}", thousands(counts.lines), if counts.lines == 1 { "line" } else { "lines" }));
+        }
+        format!("({})", columns.join(", "))
+    }
+
+    /// Appends a line per descendant: the branches and name, and the
+    /// annotation when there is one. Counts directories, files and skipped
+    /// files.
+    fn render(
+        &self,
+        prefix: &str,
+        annotations: Annotations,
+        lines: &mut Vec<(String, String)>,
+        counts: &mut (usize, usize, usize),
+    ) {
+        let last = self.children.len().saturating_sub(1);
+        for (i, (name, child)) in self.children.iter().enumerate() {
+            let (branch, indent) = if i == last { ("└── ", "    ") } else { ("├── ", "│   ") };
+            let annotation = match annotations.any() || child.skipped.is_some() {
+                true => child.annotation(annotations),
+                false => String::new(),
+            };
+            lines.push((format!("{}{}{}", prefix, branch, name), annotation));
+            if child.is_dir {
+                counts.0 += 1;
+                child.render(&format!("{}{}", prefix, indent), annotations, lines, counts);
+            } else if child.skipped.is_some() {
+                counts.2 += 1;
+            } else {
+                counts.1 += 1;
+            }
+        }
+    }
+
+    /// Emits this node's children as Mermaid nodes and edges from `id`, until
+    /// `budget` nodes have been emitted. Returns how many were left out.
+    fn mermaid(
+        &self,
+        id: &str,
+        annotations: Annotations,
+        next_id: &mut usize,
+        budget: &mut usize,
+        out: &mut String,
+    ) -> usize {
+        let mut omitted = 0;
+        for (name, child) in &self.children {
+            if *budget == 0 {
+                omitted += 1 + child.descendants();
+                continue;
+            }
+            *budget -= 1;
+            let child_id = format!("n{}", next_id);
+            *next_id += 1;
+            let label = match annotations.any() || child.skipped.is_some() {
+                true => format!("{} {}", name, child.annotation(annotations)),
+                false => name.clone(),
+            };
+            let class = if child.is_dir { "dir" } else { "file" };
+            out.push_str(&format!("    {} --> {}[\"{}\"]:::{}\n", id, child_id, mermaid_escape(&label), class));
+            omitted += child.mermaid(&child_id, annotations, next_id, budget, out);
+        }
+        omitted
+    }
+
+    fn descendants(&self) -> usize {
+        self.children.values().map(|child| 1 + child.descendants()).sum()
+    }
+}
+
+/// Mermaid labels are quoted, so only quotes and the entity syntax need escaping.
+fn mermaid_escape(label: &str) -> String {
+    label.replace('#', "#35;").replace('"', "#quot;")
+}
+
+fn plural(n: usize, one: &str, many: &str) -> String {
+    format!("{} {}", n, if n == 1 { one } else { many })
+}
+
+/// The skipped files a tree shows: lockfiles, so the reader knows dependency
+/// info exists but was left out.
+fn shown_skips(skipped: &[(PathBuf, String)]) -> impl Iterator<Item = (&Path, &str)> {
+    let lockfiles = skipped.iter().filter(|(_, reason)| reason.starts_with("lockfile"));
+    lockfiles.map(|(path, reason)| (path.as_path(), reason.as_str()))
+}
+
+/// Builds the tree below `root` from the walk's entries and its skipped
+/// lockfiles. Sizes come from metadata or the archive, so no file is opened.
+#[cfg(feature = "fs")]
+fn walked(root: &Path, walk: &Walk) -> Node {
+    let size = |size| Counts { size, ..Counts::default() };
+    let entries = walk.entries.iter().map(|entry| {
+        let is_dir = entry.file_type().is_some_and(|t| t.is_dir());
+        let len = if is_dir { 0 } else { entry.metadata().map(|m| m.len()).unwrap_or(0) };
+        (entry.path(), is_dir, size(len))
+    });
+    let archived = walk.archive.iter().flat_map(|archive| archive.entries());
+    let archived = archived.map(|(path, entry)| (path, entry.is_dir, size(entry.size())));
+    build(root, entries.chain(archived), shown_skips(&walk.skipped))
+}
+
+/// Builds the tree of a bundle's files and its skipped lockfiles, counting
+/// what each file holds as packed, after every transformation, so the totals
+/// match the bundle's.
+fn packed(bundle: &Bundle) -> Node {
+    let files = bundle.files.iter().map(|file| {
+        let counts = match &file.content {
+            FileContent::Text(text) => Counts {
+                size: text.len() as u64,
+                tokens: estimated_tokens(text.len() as u64),
+                lines: text.lines().count() as u64,
+                ..Counts::default()
+            },
+            FileContent::Binary(len) => Counts { size: *len as u64, ..Counts::default() },
+        };
+        (file.path.as_path(), false, counts)
+    });
+    build(&bundle.root, files, shown_skips(&bundle.summary.skipped))
+}
+
+/// Builds the tree below `root` from paths, whether each is a directory, and
+/// their numbers, and from skipped files with the reason.
+fn build<'a>(
+    root: &Path,
+    entries: impl Iterator<Item = (&'a Path, bool, Counts)>,
+    skipped: impl Iterator<Item = (&'a Path, &'a str)>,
+) -> Node {
+    let mut tree = Node { is_dir: true, ..Node::default() };
+    let below = |path: &'a Path| path.strip_prefix(root).ok().filter(|rel| !rel.as_os_str().is_empty());
+    for (path, is_dir, counts) in entries {
+        if let Some(rel) = below(path) {
+            tree.insert(rel, is_dir, counts);
+        }
+    }
+    for (path, reason) in skipped {
+        if let Some(rel) = below(path) {
+            tree.insert(rel, false, Counts::default()).skipped = Some(reason.to_string());
+        }
+    }
+    tree.aggregate();
+    tree
+}
+
+/// Renders the walk as a `tree`-style listing of `root`, followed by the
+/// directory and file counts. No file is read, so only the size annotation
+/// applies, as on disk.
+#[cfg(feature = "fs")]
+pub fn render(root: &Path, walk: &Walk, annotations: Annotations) -> String {
+    listing(root, walked(root, walk), Annotations { tokens: false, lines: false, ..annotations })
+}
+
+/// Like [`render`], for the files of a bundle, under its root, annotated
+/// with what each holds as packed.
+pub fn render_bundle(bundle: &Bundle, annotations: Annotations) -> String {
+    listing(&bundle.root, packed(bundle), annotations)
+}
+
+/// Annotations are right-aligned in a column after the longest name.
+fn listing(root: &Path, tree: Node, annotations: Annotations) -> String {
+    let root_annotation = if annotations.any() { tree.annotation(annotations) } else { String::new() };
+    let mut lines = vec![(root.display().to_string(), root_annotation)];
+    let mut counts = (0, 0, 0);
+    tree.render("", annotations, &mut lines, &mut counts);
+    let width = |text: &str| text.chars().count();
+    let names = lines.iter().map(|(name, _)| width(name)).max().unwrap_or(0);
+    let columns = lines.iter().map(|(_, annotation)| width(annotation)).max().unwrap_or(0);
+
+    let mut out = String::new();
+    for (name, annotation) in &lines {
+        out.push_str(name);
+        if !annotation.is_empty() {
+            let padding = names - width(name) + 2 + columns - width(annotation);
+            out.push_str(&" ".repeat(padding));
+            out.push_str(annotation);
+        }
+        out.push('\n');
+    }
+    out.push_str(&format!(
+        "\n{}, {}",
+        plural(counts.0, "directory", "directories"),
+        plural(counts.1, "file", "files")
+    ));
+    if counts.2 > 0 {
+        out.push_str(&format!(", {} skipped", counts.2));
+    }
+    out.push('\n');
+    out
+}
+
+/// Renders the walk as a fenced Mermaid `graph TD`, directories and files as
+/// differently styled nodes. Past `max_nodes` the rest is summarized in a
+/// single node and a warning is printed. As with [`render`], only sizes can
+/// annotate it.
+#[cfg(feature = "fs")]
+pub fn render_mermaid(root: &Path, walk: &Walk, annotations: Annotations, max_nodes: usize) -> String {
+    mermaid(root, walked(root, walk), Annotations { tokens: false, lines: false, ..annotations }, max_nodes)
+}
+
+/// Like [`render_mermaid`], for the files of a bundle, under its root.
+pub fn render_bundle_mermaid(bundle: &Bundle, annotations: Annotations, max_nodes: usize) -> String {
+    mermaid(&bundle.root, packed(bundle), annotations, max_nodes)
+}
+
+fn mermaid(root: &Path, tree: Node, annotations: Annotations, max_nodes: usize) -> String {
+    let mut label = root.display().to_string();
+    if annotations.any() {
+        label = format!("{} {}", label, tree.annotation(annotations));
+    }
+    let mut out = format!("```mermaid\ngraph TD\n    n0[\"{}\"]:::dir\n", mermaid_escape(&label));
+    let mut next_id = 1;
+    let mut budget = max_nodes.saturating_sub(1);
+    let omitted = tree.mermaid("n0", annotations, &mut next_id, &mut budget, &mut out);
+    if omitted > 0 {
+        eprintln!(
+            "warning: the tree has {} nodes; showing the first {} (raise with --max-nodes)",
+            next_id + omitted,
+            next_id
+        );
+        out.push_str(&format!("    n0 -.- more[\"... {} more\"]\n", omitted));
+    }
+    out.push_str("    classDef dir fill:#dbeafe,stroke:#1e40af\n");
+    out.push_str("    classDef file fill:#f3f4f6,stroke:#6b7280\n");
+    out.push_str("```\n");
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Options;
+
+    fn bundle(names: &[&str]) -> Bundle {
+        crate::bundle_files(names.iter().map(|name| (*name, "x\n")), &Options::new("repo")).unwrap()
+    }
+
+    /// The node and edge lines of a Mermaid tree, without the fence and the
+    /// class definitions.
+    fn graph(bundle: &Bundle, max_nodes: usize) -> Vec<String> {
+        let rendered = render_bundle_mermaid(bundle, Annotations::default(), max_nodes);
+        let lines = rendered.lines().filter(|line| line.starts_with("    n"));
+        lines.map(|line| line.trim_start().to_string()).collect()
+    }
+
+    #[test]
+    fn node_ids_never_come_from_paths() {
+        let names = ["my dir/a file.rs", "quote\"d.md", "[brackets] (1).txt", "hash#tag.md", "a-->b.md"];
+        assert_eq!(
+            graph(&bundle(&names), 100),
+            [
+                "n0[\"repo\"]:::dir",
+                "n0 --> n1[\"[brackets] (1).txt\"]:::file",
+                "n0 --> n2[\"a-->b.md\"]:::file",
+                "n0 --> n3[\"hash#35;tag.md\"]:::file",
+                "n0 --> n4[\"my dir\"]:::dir",
+                "n4 --> n5[\"a file.rs\"]:::file",
+                "n0 --> n6[\"quote#quot;d.md\"]:::file",
+            ]
+        );
+    }
+
+    #[test]
+    fn nodes_past_the_cap_are_summarized() {
+        let bundle = bundle(&["a/one.rs", "a/two.rs", "b/three.rs", "c.rs"]);
+        assert_eq!(
+            graph(&bundle, 3),
+            [
+                "n0[\"repo\"]:::dir",
+                "n0 --> n1[\"a\"]:::dir",
+                "n1 --> n2[\"one.rs\"]:::file",
+                "n0 -.- more[\"... 4 more\"]",
+            ]
+        );
+        assert_eq!(graph(&bundle, 7).len(), 7);
+        assert!(!graph(&bundle, 7).iter().any(|line| line.contains("more")));
+        // The root always shows, even with no room for anything else.
+        assert_eq!(graph(&bundle, 0), ["n0[\"repo\"]:::dir", "n0 -.- more[\"... 6 more\"]"]);
+    }
+}
@@ -0,0 +1,1155 @@
+//! Directory walking with owo's filters applied.
+
+#[cfg(feature = "fs")]
+use crate::archive::{self, Archive};
+#[cfg(feature = "fs")]
+use crate::paths;
+use crate::codeowners::CodeOwners;
+use crate::profiles::{self, Profile};
+use crate::sensitive::Sensitive;
+use crate::testfiles::TestMatcher;
+use crate::{
+    generated, lockfiles, split_line_ranges, summary, Error, Options, Result, TestFiles, DEFAULT_IGNORE, VCS_INTERNALS,
+};
+use globset::{Glob, GlobBuilder, GlobMatcher, GlobSet, GlobSetBuilder};
+use ignore::gitignore::{Gitignore, GitignoreBuilder};
+#[cfg(feature = "fs")]
+use ignore::{DirEntry, WalkBuilder, WalkState};
+use std::path::{Component, Path, PathBuf};
+#[cfg(feature = "fs")]
+use std::collections::HashMap;
+#[cfg(feature = "fs")]
+use std::fs::{FileType, Metadata};
+#[cfg(feature = "fs")]
+use std::sync::atomic::{AtomicUsize, Ordering};
+#[cfg(feature = "fs")]
+use std::sync::{Arc, Mutex};
+#[cfg(feature = "fs")]
+use std::time::SystemTime;
+#[cfg(feature = "fs")]
+use tokio::sync::mpsc;
+
+/// The ignore file only owo reads, in .gitignore syntax, in any directory.
+/// It beats `.ignore` and `.gitignore` files.
+pub const OWOIGNORE: &str = ".owoignore";
+
+/// The result of walking a directory with the common filters applied.
+#[cfg(feature = "fs")]
+pub struct Walk {
+    /// Every directory and file that survived filtering, in sorted walk order.
+    /// Empty when the root is an archive.
+    pub entries: Vec<DirEntry>,
+    /// The archive being packed when the root is one, holding only the
+    /// entries that survived filtering.
+    pub archive: Option<Arc<Archive>>,
+    /// Files left out for a reason worth reporting, e.g. `lockfile, 11.0 KB`.
+    pub skipped: Vec<(PathBuf, String)>,
+    /// Profiles detected from marker files, with the marker that identified
+    /// each. Empty when profiles were given explicitly.
+    pub detected: Vec<(&'static Profile, String)>,
+    /// The profiles whose patterns were applied.
+    pub profiles: Vec<&'static Profile>,
+    /// Directories or entries that couldn't be listed. The walk carries on
+    /// past them.
+    pub errors: Vec<Error>,
+    /// User-supplied ignore patterns that matched no file or directory, as
+    /// written. Default and profile patterns are never reported, and paths
+    /// .gitignore already excludes aren't seen by the patterns.
+    pub unmatched_patterns: Vec<String>,
+    /// [`Options::only`] globs that matched no path the walk reached, as
+    /// written.
+    pub unmatched_only: Vec<String>,
+    /// Each file's size and modification time as the walk saw them. Empty
+    /// for an archive, which is read whole up front.
+    pub stamps: HashMap<PathBuf, Stamp>,
+    /// How many entries the walk reached, kept or not. Those inside pruned
+    /// directories are never reached.
+    pub reached: usize,
+}
+
+/// A file's size and modification time, to tell whether it changed between
+/// the walk and the read, or while it was read.
+#[cfg(feature = "fs")]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Stamp {
+    pub size: u64,
+    pub modified: Option<SystemTime>,
+}
+
+#[cfg(feature = "fs")]
+impl Stamp {
+    pub fn of(metadata: &Metadata) -> Self {
+        Self { size: metadata.len(), modified: metadata.modified().ok() }
+    }
+}
+
+#[cfg(feature = "fs")]
+impl Walk {
+    /// The files to pack, in walk order. Everything that decides which files
+    /// are packed without reading them has already been applied.
+    pub fn files(&self) -> impl Iterator<Item = &Path> {
+        let archived = self.archive.iter().flat_map(|archive| archive.entries());
+        self.entries
+            .iter()
+            .map(DirEntry::path)
+            .filter(|path| path.is_file())
+            .chain(archived.filter(|(_, entry)| !entry.is_dir).map(|(path, _)| path))
+    }
+
+    /// The size of a file the walk found, from its metadata or archive entry.
+    pub fn size(&self, path: &Path) -> u64 {
+        match self.archive.as_ref().and_then(|archive| archive.get(path)) {
+            Some(entry) => entry.size(),
+            None => path.metadata().map(|m| m.len()).unwrap_or(0),
+        }
+    }
+}
+
+/// Profiles detected from marker files, with the marker that identified each.
+type Detected = Vec<(&'static Profile, String)>;
+
+/// Resolves the profiles to apply: the ones given, or else the ones `detect`
+/// finds.
+pub(crate) fn resolve_profiles(
+    options: &Options,
+    detect: impl FnOnce() -> Detected,
+) -> Result<(Detected, Vec<&'static Profile>)> {
+    if !options.profiles.is_empty() {
+        let profiles = options
+            .profiles
+            .iter()
+            .map(|name| profiles::find(name).ok_or_else(|| Error::UnknownProfile(name.clone())))
+            .collect::<Result<Vec<_>>>()?;
+        return Ok((Vec::new(), profiles));
+    }
+    let detected = if options.auto_profile { detect() } else { Vec::new() };
+    let profiles = detected.iter().map(|(profile, _)| *profile).collect();
+    Ok((detected, profiles))
+}
+
+/// The ignore patterns and profile patterns in effect, with where each came
+/// from: `default`, `--ignore` or `profile <name>`.
+pub(crate) struct Patterns {
+    excludes: Gitignore,
+    /// Consulted only for paths an exclude has already matched.
+    reincludes: Gitignore,
+    compiled: Vec<CompiledPattern>,
+}
+
+/// An ignore pattern that matched a path, as written, with its source.
+#[cfg(feature = "fs")]
+#[derive(Debug, Clone)]
+pub(crate) struct PatternMatch {
+    pub(crate) pattern: String,
+    pub(crate) source: String,
+}
+
+/// An ignore pattern as it's matched.
+#[derive(Debug, Clone)]
+pub struct CompiledPattern {
+    /// As written, with any leading `!`.
+    pub pattern: String,
+    /// `default`, `--ignore` or `profile <name>`.
+    pub source: String,
+    /// Where it is among its source's patterns, from 1.
+    pub position: usize,
+    /// The glob paths relative to the root are matched against, like
+    /// `**/build` for `build`.
+    pub glob: String,
+    /// Whether it's a `!` pattern, re-including what others excluded.
+    pub reinclude: bool,
+    /// Whether it has a trailing `/`, so only directories match.
+    pub only_dirs: bool,
+}
+
+impl CompiledPattern {
+    /// Rewrites `pattern` the way gitignore matching reads it: a leading `/`
+    /// anchors it and is dropped, a trailing `/` limits it to directories, and
+    /// a name without a `/` matches at any depth.
+    fn new(pattern: &str, source: &str, position: usize) -> Self {
+        let reinclude = pattern.starts_with('!');
+        let rest = pattern.strip_prefix('!').unwrap_or(pattern);
+        let (anchored, rest) = match rest.strip_prefix('/') {
+            Some(rest) => (true, rest),
+            None => (false, rest),
+        };
+        let (only_dirs, rest) = match rest.strip_suffix('/') {
+            Some(rest) => (true, rest),
+            None => (false, rest),
+        };
+        let mut glob = match anchored || rest.contains('/') || rest.starts_with("**/") {
+            true => rest.to_string(),
+            false => format!("**/{}", rest),
+        };
+        // `dir/**` matches what's inside, not the directory itself.
+        if glob.ends_with("/**") {
+            glob.push_str("/*");
+        }
+        Self { pattern: pattern.to_string(), source: source.to_string(), position, glob, reinclude, only_dirs }
+    }
+}
+
+/// Every ignore pattern the walk applies, in order, with where each came from
+/// and the glob it's matched as. Profiles are resolved as [`walk`] resolves
+/// them, detecting them in the root directory. Each bad pattern is an
+/// [`Error::Pattern`] naming its source and position.
+pub fn compile_patterns(options: &Options) -> Result<Vec<CompiledPattern>> {
+    let (_, profiles) = resolve_profiles(options, || profiles::detect(&options.root))?;
+    Ok(Patterns::new(options, &profiles)?.compiled)
+}
+
+impl Patterns {
+    pub(crate) fn new(options: &Options, profiles: &[&'static Profile]) -> Result<Self> {
+        let source = if options.default_ignore { "default" } else { "--ignore" };
+        let patterns = options.ignore.iter().map(|pattern| pattern.trim()).filter(|pattern| !pattern.is_empty());
+        let mut compiled = patterns
+            .enumerate()
+            .map(|(i, pattern)| CompiledPattern::new(pattern, source, i + 1))
+            .collect::<Vec<_>>();
+        for profile in profiles {
+            let source = format!("profile {}", profile.name);
+            let patterns = profile.patterns.iter().enumerate();
+            compiled.extend(patterns.map(|(i, pattern)| CompiledPattern::new(pattern, &source, i + 1)));
+        }
+
+        let mut exclude_builder = GitignoreBuilder::new(&options.root);
+        let mut reinclude_builder = GitignoreBuilder::new(&options.root);
+        exclude_builder.allow_unclosed_class(false);
+        reinclude_builder.allow_unclosed_class(false);
+        for pattern in &compiled {
+            let added = match pattern.pattern.strip_prefix('!') {
+                Some(reinclude) => reinclude_builder.add_line(None, reinclude),
+                None => exclude_builder.add_line(None, &pattern.pattern),
+            };
+            added.map_err(|source| Error::Pattern {
+                pattern: pattern.pattern.clone(),
+                position: pattern.position,
+                origin: pattern.source.clone(),
+                source,
+            })?;
+        }
+        Ok(Self {
+            excludes: exclude_builder.build()?,
+            reincludes: reinclude_builder.build()?,
+            compiled,
+        })
+    }
+
+    /// Whether the patterns leave `path` out. Parents aren't consulted; the
+    /// walk never descends into an excluded directory.
+    pub(crate) fn excludes(&self, path: &Path, is_dir: bool) -> bool {
+        self.excludes.matched(path, is_dir).is_ignore() && !self.reincludes.matched(path, is_dir).is_ignore()
+    }
+
+    /// The last exclude matching `path` and the last re-include that
+    /// overrides it, if any.
+    #[cfg(feature = "fs")]
+    pub(crate) fn matched(&self, path: &Path, is_dir: bool) -> (Option<PatternMatch>, Option<PatternMatch>) {
+        let exclude = self.excludes.matched(path, is_dir);
+        let Some(exclude) = exclude.is_ignore().then(|| exclude.inner()).flatten() else {
+            return (None, None);
+        };
+        let reinclude = self.reincludes.matched(path, is_dir);
+        let reinclude = reinclude.is_ignore().then(|| reinclude.inner()).flatten();
+        (
+            Some(self.source(exclude.original())),
+            reinclude.map(|glob| self.source(&format!("!{}", glob.original()))),
+        )
+    }
+
+    #[cfg(feature = "fs")]
+    fn source(&self, pattern: &str) -> PatternMatch {
+        let source = self
+            .compiled
+            .iter()
+            .rev()
+            .find(|compiled| compiled.pattern == pattern)
+            .map(|compiled| compiled.source.clone())
+            .unwrap_or_default();
+        PatternMatch { pattern: pattern.to_string(), source }
+    }
+}
+
+/// Each user-supplied ignore pattern and only glob that hasn't matched a
+/// path yet, on its own so a path matching several counts for all of them.
+#[derive(Default)]
+struct Unmatched {
+    root: PathBuf,
+    ignore: Vec<(String, Gitignore)>,
+    only: Vec<(String, GlobMatcher)>,
+}
+
+impl Unmatched {
+    fn new(options: &Options) -> Result<Self> {
+        let defaults = DEFAULT_IGNORE.split('|').collect::<Vec<_>>();
+        let mut patterns = Vec::new();
+        let written = options.ignore.iter().map(|pattern| pattern.trim()).filter(|pattern| !pattern.is_empty());
+        for (i, pattern) in written.enumerate() {
+            if defaults.contains(&pattern) {
+                continue;
+            }
+            let mut builder = GitignoreBuilder::new(&options.root);
+            builder.allow_unclosed_class(false);
+            builder.add_line(None, pattern.strip_prefix('!').unwrap_or(pattern)).map_err(|source| Error::Pattern {
+                pattern: pattern.to_string(),
+                position: i + 1,
+                origin: "--ignore".to_string(),
+                source,
+            })?;
+            patterns.push((pattern.to_string(), builder.build()?));
+        }
+        let mut only = Vec::new();
+        for spec in &options.only {
+            let (pattern, _) = split_line_ranges(spec)?;
+            let glob = Glob::new(pattern).map_err(|source| Error::Only { pattern: pattern.to_string(), source })?;
+            only.push((spec.clone(), glob.compile_matcher()));
+        }
+        Ok(Self { root: options.root.clone(), ignore: patterns, only })
+    }
+
+    fn visit(&mut self, path: &Path, is_dir: bool) {
+        self.ignore.retain(|(_, gitignore)| gitignore.matched(path, is_dir).is_none());
+        let rel = path.strip_prefix(&self.root).unwrap_or(path);
+        self.only.retain(|(_, glob)| !glob.is_match(rel));
+    }
+
+    /// The ignore patterns and the only globs left unmatched.
+    fn into_patterns(self) -> (Vec<String>, Vec<String>) {
+        let ignore = self.ignore.into_iter().map(|(pattern, _)| pattern).collect();
+        (ignore, self.only.into_iter().map(|(glob, _)| glob).collect())
+    }
+}
+
+/// Whether `path` names one of the [`VCS_INTERNALS`].
+pub(crate) fn is_vcs_internals(path: &Path) -> bool {
+    path.file_name().is_some_and(|name| VCS_INTERNALS.iter().any(|vcs| name == *vcs))
+}
+
+/// A [`Options::keep_dotfiles`] pattern. One without a `/` names a hidden
+/// file or directory at any depth; one with a `/` is matched against paths
+/// from the root, and keeps every hidden directory it descends through.
+struct KeepPattern {
+    pattern: String,
+    anchored: bool,
+    glob: GlobMatcher,
+}
+
+impl KeepPattern {
+    fn new(pattern: &str) -> Result<Self> {
+        let trimmed = pattern.trim_end_matches('/');
+        let anchored = trimmed.contains('/');
+        let glob = GlobBuilder::new(trimmed.trim_start_matches('/'))
+            .literal_separator(true)
+            .build()
+            .map_err(|source| Error::KeepDotfile { pattern: pattern.to_string(), source })?;
+        Ok(Self { pattern: pattern.to_string(), anchored, glob: glob.compile_matcher() })
+    }
+
+    /// Whether the pattern keeps the hidden component `names[index]` of a
+    /// path split into its components.
+    fn covers(&self, names: &[String], index: usize) -> bool {
+        match self.anchored {
+            false => self.glob.is_match(&names[index]),
+            true => (index..names.len()).any(|end| self.glob.is_match(names[..=end].join("/"))),
+        }
+    }
+}
+
+/// The dotfile rule: hidden files and directories are left out, unless
+/// dotfiles are included or the keep-list covers each hidden component.
+pub(crate) struct Dotfiles {
+    included: bool,
+    keep: Vec<KeepPattern>,
+}
+
+impl Dotfiles {
+    pub(crate) fn new(options: &Options) -> Result<Self> {
+        Ok(Self {
+            included: options.with_dotfiles,
+            keep: options.keep_dotfiles.iter().map(|pattern| KeepPattern::new(pattern)).collect::<Result<_>>()?,
+        })
+    }
+
+    /// Each hidden component of `rel`, a path below the root, with the
+    /// keep pattern that covers it, if any.
+    pub(crate) fn hidden(&self, rel: &Path) -> Vec<(String, Option<&str>)> {
+        let names = names(rel);
+        (0..names.len())
+            .filter(|&index| names[index].starts_with('.'))
+            .map(|index| {
+                let kept = self.keep.iter().find(|keep| keep.covers(&names, index));
+                (names[index].clone(), kept.map(|keep| keep.pattern.as_str()))
+            })
+            .collect()
+    }
+
+    /// Whether the rule leaves out the entry at `rel`.
+    pub(crate) fn excludes(&self, rel: &Path) -> bool {
+        !self.included && self.hidden(rel).iter().any(|(_, kept)| kept.is_none())
+    }
+
+    /// The keep pattern that covers the entry at `rel` itself, for one
+    /// [`marked_hidden`] whatever its name.
+    #[cfg(feature = "fs")]
+    pub(crate) fn keeps_marked(&self, rel: &Path) -> Option<&str> {
+        let names = names(rel);
+        let last = names.len().checked_sub(1)?;
+        self.keep.iter().find(|keep| keep.covers(&names, last)).map(|keep| keep.pattern.as_str())
+    }
+
+    /// Whether the rule leaves out the entry at `rel`, [`marked_hidden`].
+    #[cfg(feature = "fs")]
+    pub(crate) fn excludes_marked(&self, rel: &Path) -> bool {
+        !self.included && self.keeps_marked(rel).is_none()
+    }
+}
+
+/// The components of `rel`, a path below the root.
+fn names(rel: &Path) -> Vec<String> {
+    rel.components()
+        .filter_map(|component| match component {
+            Component::Normal(name) => Some(name.to_string_lossy().into_owned()),
+            _ => None,
+        })
+        .collect()
+}
+
+/// `FILE_ATTRIBUTE_HIDDEN` and `FILE_ATTRIBUTE_SYSTEM`.
+#[cfg(all(feature = "fs", windows))]
+const HIDDEN_ATTRIBUTES: u32 = 0x2 | 0x4;
+
+/// Whether Windows marks an entry hidden, or as a system file like
+/// `desktop.ini` or `Thumbs.db`, which the dotfile rule treats like a leading
+/// dot. `metadata` is only consulted on Windows; elsewhere nothing is marked.
+#[cfg(feature = "fs")]
+pub(crate) fn marked_hidden(metadata: impl FnOnce() -> Option<Metadata>) -> bool {
+    #[cfg(windows)]
+    {
+        use std::os::windows::fs::MetadataExt;
+        metadata().is_some_and(|metadata| metadata.file_attributes() & HIDDEN_ATTRIBUTES != 0)
+    }
+    #[cfg(not(windows))]
+    {
+        let _ = metadata;
+        false
+    }
+}
+
+/// Why a file the walk reached is skipped anyway, decided by name alone.
+/// A file an only glob names, `named`, is never judged generated.
+pub(crate) fn skip_reason(options: &Options, path: &Path, named: bool, size: impl FnOnce() -> u64) -> Option<String> {
+    if options.skips_lockfiles() && lockfiles::is_lockfile(path) {
+        return Some(format!("lockfile, {}", summary::human_size(size())));
+    }
+    if options.skip_generated && !named && let Some(reason) = generated::detect_by_name(path) {
+        return Some(format!("generated: {}", reason));
+    }
+    None
+}
+
+/// Header and footer files by canonical path, which the walk leaves out.
+pub(crate) fn insert_files(options: &Options) -> Vec<PathBuf> {
+    options.insert_files().filter_map(|path| path.canonicalize().ok()).collect()
+}
+
+/// Whether `path` is one of the `inserts`. Only candidates with the same file
+/// name pay for canonicalizing.
+pub(crate) fn is_insert(inserts: &[PathBuf], path: &Path) -> bool {
+    inserts.iter().any(|insert| {
+        insert.file_name() == path.file_name() && path.canonicalize().is_ok_and(|path| &path == insert)
+    })
+}
+
+/// The [`Options::only`] globs, or `None` when every file may be packed.
+pub(crate) fn only_globs(options: &Options) -> Result<Option<GlobSet>> {
+    if options.only.is_empty() {
+        return Ok(None);
+    }
+    let mut builder = GlobSetBuilder::new();
+    for spec in &options.only {
+        let (pattern, _) = split_line_ranges(spec)?;
+        let glob = Glob::new(pattern).map_err(|source| Error::Only { pattern: pattern.to_string(), source })?;
+        builder.add(glob);
+    }
+    let set = builder.build().map_err(|source| Error::Only { pattern: options.only.join(", "), source })?;
+    Ok(Some(set))
+}
+
+/// The `CODEOWNERS` file to filter by, or `None` when no owners were asked for.
+/// One beside an archive doesn't describe its entries, so there's none for
+/// an archive.
+pub(crate) fn code_owners(options: &Options) -> Result<Option<CodeOwners>> {
+    if options.owners.is_empty() && !options.include_unowned {
+        return Ok(None);
+    }
+    #[cfg(feature = "fs")]
+    if archive::is_archive(&options.root) {
+        return Err(Error::NoCodeOwners(options.root.clone()));
+    }
+    CodeOwners::find(&options.root).map(Some)
+}
+
+/// The checks a file has to pass once the walk reaches it, decided without
+/// reading it.
+struct FileChecks {
+    options: Options,
+    inserts: Vec<PathBuf>,
+    only: Option<GlobSet>,
+    tests: Option<TestMatcher>,
+    codeowners: Option<CodeOwners>,
+    sensitive: Sensitive,
+}
+
+impl FileChecks {
+    fn new(options: &Options) -> Result<Self> {
+        let tests = match options.tests {
+            TestFiles::Include => None,
+            TestFiles::Skip | TestFiles::Only => Some(TestMatcher::new(&options.root)?),
+        };
+        Ok(Self {
+            options: options.clone(),
+            inserts: insert_files(options),
+            only: only_globs(options)?,
+            tests,
+            codeowners: code_owners(options)?,
+            sensitive: Sensitive::new(options)?,
+        })
+    }
+
+    /// Whether the file at `path`, `rel` below the root, is packed. Files
+    /// skipped for a reason worth reporting are added to `skipped`. `read`
+    /// gives the file's bytes, for the few checks that need them.
+    fn keeps(
+        &self,
+        path: &Path,
+        rel: &Path,
+        size: impl FnOnce() -> u64,
+        read: impl FnOnce() -> Option<Vec<u8>>,
+        skipped: &mut Vec<(PathBuf, String)>,
+    ) -> bool {
+        let options = &self.options;
+        if is_insert(&self.inserts, path) {
+            return false;
+        }
+        // Files an only glob names are kept whether or not they're tests or
+        // look generated.
+        let named = match &self.only {
+            Some(only) if !only.is_match(rel) => return false,
+            Some(_) => true,
+            None => false,
+        };
+        if let Some(tests) = &self.tests
+            && !named
+            && tests.is_test(path) != (options.tests == TestFiles::Only)
+        {
+            return false;
+        }
+        if let Some(codeowners) = &self.codeowners
+            && !codeowners.selects(rel, &options.owners, options.include_unowned)
+        {
+            return false;
+        }
+        // Its own layer, so replacing the default ignore list can't let
+        // keys in.
+        if let Some(pattern) = self.sensitive.withholds(rel, read) {
+            skipped.push((path.to_path_buf(), format!("sensitive: {}", pattern)));
+            return false;
+        }
+        if let Some(reason) = skip_reason(options, path, named, size) {
+            skipped.push((path.to_path_buf(), reason));
+            return false;
+        }
+        true
+    }
+}
+
+/// Walks the options' root, honoring .gitignore and .owoignore files, the
+/// ignore patterns and profiles, the dotfile setting, [`Options::only`],
+/// [`Options::tests`], [`Options::owners`], lockfile skipping and, with
+/// [`Options::skip_generated`], generated-file suffixes.
+#[cfg(feature = "fs")]
+pub fn walk(options: &Options) -> Result<Walk> {
+    let directory = options.root.as_path();
+    if !directory.exists() {
+        return Err(Error::RootNotFound(directory.to_path_buf()));
+    }
+    if archive::is_archive(directory) {
+        return walk_archive(options, Archive::open(directory)?);
+    }
+    let threads = walk_threads(options);
+    let walker = Walker::new(options, threads)?;
+    let found = Mutex::new(Found::default());
+    let walk = walker.run(|visited| {
+        found.lock().unwrap().add(visited);
+        true
+    });
+    let mut found = found.into_inner().unwrap();
+    if threads > 1 {
+        found.sort();
+    }
+
+    let Found { entries, skipped, stamps, errors } = found;
+    let errors = errors.into_iter().map(Error::Walk).collect();
+    Ok(Walk { entries, skipped, errors, stamps, ..walk })
+}
+
+/// How many threads list directories: the options' count, or one per CPU
+/// up to 12.
+#[cfg(feature = "fs")]
+fn walk_threads(options: &Options) -> usize {
+    options.walk_threads.unwrap_or_else(|| num_cpus::get().min(12))
+}
+
+/// How many files [`walk_files`] finds ahead of the reads before it waits.
+#[cfg(feature = "fs")]
+const WALK_AHEAD: usize = 1024;
+
+/// A file the walk kept, with its size and modification time as it saw them.
+#[cfg(feature = "fs")]
+pub(crate) type FoundFile = (PathBuf, Option<Stamp>);
+
+/// A walk whose files go to a channel as they're found, set up by
+/// [`walk_files`] and run with [`FileWalk::run`].
+#[cfg(feature = "fs")]
+pub(crate) struct FileWalk {
+    /// The archive being packed when the root is one, read whole already.
+    pub(crate) archive: Option<Arc<Archive>>,
+    source: Source,
+    tx: mpsc::Sender<FoundFile>,
+}
+
+#[cfg(feature = "fs")]
+enum Source {
+    Directory(Box<Walker>, usize),
+    /// An archive's walk is done up front; only sending its files is left.
+    Archive(Box<Walk>),
+}
+
+/// Walks like [`walk`], but hands over each file as soon as it passes the
+/// filters, so reads can start while the walk goes on, and never keeps the
+/// entries. Everything that can fail before the walk starts, like a bad
+/// pattern, fails here; the files come from the receiver once the returned
+/// walk is run. They come in walk order when `ordered`, which walks on one
+/// thread, and as they're found otherwise.
+#[cfg(feature = "fs")]
+pub(crate) fn walk_files(options: &Options, ordered: bool) -> Result<(FileWalk, mpsc::Receiver<FoundFile>)> {
+    let directory = options.root.as_path();
+    if !directory.exists() {
+        return Err(Error::RootNotFound(directory.to_path_buf()));
+    }
+    let (tx, rx) = mpsc::channel(WALK_AHEAD);
+    let walk = match archive::is_archive(directory) {
+        true => {
+            let walk = walk_archive(options, Archive::open(directory)?)?;
+            FileWalk { archive: walk.archive.clone(), source: Source::Archive(Box::new(walk)), tx }
+        }
+        false => {
+            let threads = if ordered { 1 } else { walk_threads(options) };
+            let walker = Walker::new(options, threads)?;
+            FileWalk { archive: None, source: Source::Directory(Box::new(walker), threads), tx }
+        }
+    };
+    Ok((walk, rx))
+}
+
+#[cfg(feature = "fs")]
+impl FileWalk {
+    /// Runs the walk, blocking until it's done or the receiver is dropped,
+    /// and returns the rest of it: the skips, errors, profiles and unmatched
+    /// patterns. Its entries and stamps are left empty.
+    pub(crate) fn run(self) -> Walk {
+        let (walker, threads) = match self.source {
+            Source::Directory(walker, threads) => (walker, threads),
+            Source::Archive(walk) => {
+                for path in walk.files() {
+                    if self.tx.blocking_send((path.to_path_buf(), None)).is_err() {
+                        break;
+                    }
+                }
+                return *walk;
+            }
+        };
+        let found = Mutex::new(Found::default());
+        let walk = walker.run(|visited| match visited {
+            Visit::Kept(entry, stamp) if entry.path().is_file() => {
+                self.tx.blocking_send((entry.into_path(), stamp)).is_ok()
+            }
+            Visit::Kept(..) => true,
+            visited => {
+                found.lock().unwrap().add(visited);
+                true
+            }
+        });
+        let mut found = found.into_inner().unwrap();
+        if threads > 1 {
+            found.sort();
+        }
+        let Found { skipped, errors, .. } = found;
+        let errors = errors.into_iter().map(Error::Walk).collect();
+        Walk { skipped, errors, ..walk }
+    }
+}
+
+/// A directory walk with its profiles resolved and its filters built, ready
+/// to run.
+#[cfg(feature = "fs")]
+struct Walker {
+    directory: PathBuf,
+    builder: WalkBuilder,
+    threads: usize,
+    checks: FileChecks,
+    dotfiles: Arc<Dotfiles>,
+    pseudo: bool,
+    unmatched: Arc<Mutex<Unmatched>>,
+    detected: Detected,
+    profiles: Vec<&'static Profile>,
+}
+
+#[cfg(feature = "fs")]
+impl Walker {
+    fn new(options: &Options, threads: usize) -> Result<Self> {
+        let directory = options.root.clone();
+        let (detected, profiles) = resolve_profiles(options, || profiles::detect(&directory))?;
+        let patterns = Patterns::new(options, &profiles)?;
+        let skips_vcs = !options.include_vcs_internals;
+        let unmatched = Arc::new(Mutex::new(Unmatched::new(options)?));
+        let tracker = Arc::clone(&unmatched);
+        let dotfiles = Arc::new(Dotfiles::new(options)?);
+        let marks = Arc::clone(&dotfiles);
+        let root = directory.clone();
+
+        let mut builder = WalkBuilder::new(&directory);
+        builder
+            .hidden(false)
+            .git_ignore(true)
+            .require_git(false)
+            .add_custom_ignore_filename(OWOIGNORE)
+            .threads(threads)
+            .filter_entry(move |entry| {
+                let is_dir = entry.file_type().is_some_and(|t| t.is_dir());
+                tracker.lock().unwrap().visit(entry.path(), is_dir);
+                let vcs = skips_vcs && is_vcs_internals(entry.path());
+                // A marked directory is pruned here, as its entries' names
+                // don't show it.
+                let rel = entry.path().strip_prefix(&root).unwrap_or(entry.path());
+                let marked = entry.depth() > 0
+                    && marked_hidden(|| entry.metadata().ok())
+                    && marks.excludes_marked(rel);
+                !vcs && !marked && !patterns.excludes(entry.path(), is_dir)
+            });
+        if threads == 1 {
+            builder.sort_by_file_name(|a, b| a.cmp(b));
+        }
+        Ok(Self {
+            pseudo: paths::pseudo_filesystem(&directory),
+            directory,
+            builder,
+            threads,
+            checks: FileChecks::new(options)?,
+            dotfiles,
+            unmatched,
+            detected,
+            profiles,
+        })
+    }
+
+    /// Walks, handing what it made of each entry to `found` on whichever
+    /// thread found it, and stopping once `found` returns false. On one
+    /// thread the entries come in walk order; on several the threads list
+    /// directories in no particular order. Returns the walk with the
+    /// profiles, the ignore patterns that matched nothing and the number of
+    /// entries reached, and nothing else.
+    fn run(self, found: impl Fn(Visit) -> bool + Sync) -> Walk {
+        let reached = AtomicUsize::new(0);
+        let visit = |entry| {
+            reached.fetch_add(1, Ordering::Relaxed);
+            found(self.visit(entry))
+        };
+        if self.threads == 1 {
+            for entry in self.builder.build() {
+                if !visit(entry) {
+                    break;
+                }
+            }
+        } else {
+            self.builder.build_parallel().run(|| {
+                let visit = &visit;
+                Box::new(move |entry| match visit(entry) {
+                    true => WalkState::Continue,
+                    false => WalkState::Quit,
+                })
+            });
+        }
+        let (unmatched_patterns, unmatched_only) = std::mem::take(&mut *self.unmatched.lock().unwrap()).into_patterns();
+        Walk {
+            entries: Vec::new(),
+            archive: None,
+            skipped: Vec::new(),
+            detected: self.detected,
+            profiles: self.profiles,
+            errors: Vec::new(),
+            unmatched_patterns,
+            unmatched_only,
+            stamps: HashMap::new(),
+            reached: reached.into_inner(),
+        }
+    }
+
+    fn visit(&self, entry: std::result::Result<DirEntry, ignore::Error>) -> Visit {
+        let entry = match entry {
+            Ok(entry) => entry,
+            Err(e) => return Visit::Failed(e),
+        };
+        let path = entry.path();
+
+        let rel = path.strip_prefix(&self.directory).unwrap_or(path);
+        if self.dotfiles.excludes(rel) {
+            return Visit::Dropped;
+        }
+
+        if path.is_dir() {
+            return Visit::Kept(entry, None);
+        }
+
+        let metadata = entry.metadata().ok();
+        let size = || metadata.as_ref().map_or(0, Metadata::len);
+        let mut skipped = Vec::new();
+        let special = special_file(path, entry.file_type(), self.pseudo && size() == 0);
+        // Only a regular file is read for its tokens; a FIFO would block.
+        let read = || special.is_none().then(|| std::fs::read(crate::long_path(path)).ok()).flatten();
+        if !self.checks.keeps(path, rel, size, read, &mut skipped) {
+            return skipped.pop().map_or(Visit::Dropped, |(path, reason)| Visit::Skipped(path, reason));
+        }
+        if let Some(kind) = special {
+            return Visit::Skipped(path.to_path_buf(), format!("special file: {}", kind));
+        }
+        let stamp = metadata.as_ref().map(Stamp::of);
+        Visit::Kept(entry, stamp)
+    }
+}
+
+/// What the walk made of one entry.
+#[cfg(feature = "fs")]
+enum Visit {
+    Kept(DirEntry, Option<Stamp>),
+    /// Left out for a reason worth reporting.
+    Skipped(PathBuf, String),
+    Dropped,
+    Failed(ignore::Error),
+}
+
+/// Everything the walk kept, skipped or couldn't list.
+#[cfg(feature = "fs")]
+#[derive(Default)]
+struct Found {
+    entries: Vec<DirEntry>,
+    skipped: Vec<(PathBuf, String)>,
+    stamps: HashMap<PathBuf, Stamp>,
+    errors: Vec<ignore::Error>,
+}
+
+#[cfg(feature = "fs")]
+impl Found {
+    fn add(&mut self, visited: Visit) {
+        match visited {
+            Visit::Kept(entry, stamp) => {
+                if let Some(stamp) = stamp {
+                    self.stamps.insert(entry.path().to_path_buf(), stamp);
+                }
+                self.entries.push(entry);
+            }
+            Visit::Skipped(path, reason) => self.skipped.push((path, reason)),
+            Visit::Dropped => {}
+            Visit::Failed(e) => self.errors.push(e),
+        }
+    }
+
+    /// Puts everything in walk order: comparing paths component by component
+    /// sorts by name within each directory, a directory's entries right
+    /// after it.
+    fn sort(&mut self) {
+        self.entries.sort_by(|a, b| a.path().cmp(b.path()));
+        self.skipped.sort_by(|a, b| a.0.cmp(&b.0));
+        self.errors.sort_by_cached_key(|e| e.to_string());
+    }
+}
+
+/// What kind of special file `path` is, when it isn't a regular file or a
+/// link to one: reading a FIFO blocks until something writes to it, and
+/// sockets and devices aren't files to pack. `empty_pseudo` marks an empty
+/// file on a pseudo file system like procfs, which might block as well.
+#[cfg(feature = "fs")]
+fn special_file(path: &Path, file_type: Option<FileType>, empty_pseudo: bool) -> Option<&'static str> {
+    let file_type = match file_type {
+        Some(file_type) if !file_type.is_symlink() => file_type,
+        // A broken link fails when it's read, as before.
+        _ => std::fs::metadata(path).ok()?.file_type(),
+    };
+    if file_type.is_file() {
+        return empty_pseudo.then_some("empty file on a pseudo file system");
+    }
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::FileTypeExt;
+        if file_type.is_fifo() {
+            return Some("FIFO");
+        } else if file_type.is_socket() {
+            return Some("socket");
+        } else if file_type.is_block_device() {
+            return Some("block device");
+        } else if file_type.is_char_device() {
+            return Some("character device");
+        }
+    }
+    Some("not a regular file")
+}
+
+/// Filters an archive's entries as [`walk`] filters a directory's. Profiles
+/// are detected from the entries at the top of the archive.
+#[cfg(feature = "fs")]
+fn walk_archive(options: &Options, mut archive: Archive) -> Result<Walk> {
+    let names = archive
+        .entries()
+        .filter_map(|(path, _)| path.strip_prefix(&archive.path).ok())
+        .filter(|rel| rel.components().count() == 1)
+        .map(|rel| rel.to_string_lossy().into_owned())
+        .collect::<Vec<_>>();
+    let (detected, profiles) = resolve_profiles(options, || profiles::detect_names(&names))?;
+    let mut filter = ListFilter::new(options, &profiles)?;
+    let reached = archive.entries().count();
+    archive.retain(|path, entry| filter.keeps(path, entry.is_dir, entry.head(u64::MAX)));
+    let mut skipped = std::mem::take(&mut archive.skipped);
+    skipped.append(&mut filter.skipped);
+    let (unmatched_patterns, unmatched_only) = filter.unmatched_patterns();
+    Ok(Walk {
+        entries: Vec::new(),
+        archive: Some(Arc::new(archive)),
+        skipped,
+        detected,
+        profiles,
+        errors: Vec::new(),
+        unmatched_patterns,
+        unmatched_only,
+        stamps: HashMap::new(),
+        reached,
+    })
+}
+
+/// The walk's filters for entries that come as a list of paths under the
+/// root, like an archive's, rather than from walking a directory. There's no
+/// directory to prune, so an entry goes when the patterns exclude it or any
+/// directory above it, and there are no .gitignore files to read.
+pub(crate) struct ListFilter<'a> {
+    options: &'a Options,
+    patterns: Patterns,
+    checks: FileChecks,
+    dotfiles: Dotfiles,
+    unmatched: Unmatched,
+    /// Files left out for a reason worth reporting.
+    pub(crate) skipped: Vec<(PathBuf, String)>,
+}
+
+impl<'a> ListFilter<'a> {
+    pub(crate) fn new(options: &'a Options, profiles: &[&'static Profile]) -> Result<Self> {
+        Ok(Self {
+            options,
+            patterns: Patterns::new(options, profiles)?,
+            checks: FileChecks::new(options)?,
+            dotfiles: Dotfiles::new(options)?,
+            unmatched: Unmatched::new(options)?,
+            skipped: Vec::new(),
+        })
+    }
+
+    /// Whether the entry at `path`, which starts with the root, with the
+    /// file's `bytes`, is kept.
+    pub(crate) fn keeps(&mut self, path: &Path, is_dir: bool, bytes: &[u8]) -> bool {
+        let rel = path.strip_prefix(&self.options.root).unwrap_or(path);
+        let depth = rel.components().count();
+        let skips_vcs = !self.options.include_vcs_internals;
+        let mut above = self.options.root.clone();
+        for (index, component) in rel.components().enumerate() {
+            above.push(component);
+            let is_dir = is_dir || index + 1 < depth;
+            self.unmatched.visit(&above, is_dir);
+            if (skips_vcs && is_vcs_internals(&above)) || self.patterns.excludes(&above, is_dir) {
+                return false;
+            }
+        }
+        if self.dotfiles.excludes(rel) {
+            return false;
+        }
+        is_dir || self.checks.keeps(path, rel, || bytes.len() as u64, || Some(bytes.to_vec()), &mut self.skipped)
+    }
+
+    /// User-supplied ignore patterns and only globs that matched none of
+    /// the entries.
+    pub(crate) fn unmatched_patterns(self) -> (Vec<String>, Vec<String>) {
+        self.unmatched.into_patterns()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Whether `patterns` leave out `path`, a directory when it ends in `/`.
+    fn excluded(patterns: &[&str], path: &str) -> bool {
+        let patterns = Patterns::new(&Options::new("/repo").ignore(patterns.iter().copied()), &[]).unwrap();
+        patterns.excludes(Path::new(path.trim_end_matches('/')), path.ends_with('/'))
+    }
+
+    #[test]
+    fn ignore_patterns_follow_gitignore_rules() {
+        // The rows of the README's table, then a few more.
+        let cases: &[(&[&str], &[&str], &[&str])] = &[
+            (&["build"], &["build", "packages/a/build/", "docs/build"], &["builds", "src/build.rs"]),
+            (&["/build"], &["build", "build/"], &["packages/a/build/", "docs/build"]),
+            (&["build/"], &["build/", "packages/a/build/"], &["docs/build", "build"]),
+            (&["*.rs"], &["a.rs", "src/deep/z.rs"], &["a.rsx", "src/"]),
+            (&["b/src"], &["b/src/"], &["packages/b/src/", "src/"]),
+            (&["packages/*/build"], &["packages/a/build/"], &["packages/a/b/build/", "build/"]),
+            (&["packages/**/deep"], &["packages/deep/", "packages/b/src/deep/"], &["deep/", "other/packages/deep/"]),
+            (&["**/cache"], &["cache/", "a/b/cache"], &["cached"]),
+            (&["docs/**"], &["docs/a.md", "docs/deep/b.md"], &["docs/", "a/docs/b.md"]),
+            (&["packages/a/*", "!packages/a/build"], &["packages/a/src/"], &["packages/a/build/"]),
+            (&["*.log", "!keep.log"], &["a.log", "logs/b.log"], &["keep.log", "logs/keep.log"]),
+            (&["!keep.log"], &[], &["keep.log", "other.log"]),
+        ];
+        for (patterns, excluded_paths, kept_paths) in cases {
+            for path in *excluded_paths {
+                assert!(excluded(patterns, path), "{:?} should exclude {}", patterns, path);
+            }
+            for path in *kept_paths {
+                assert!(!excluded(patterns, path), "{:?} should keep {}", patterns, path);
+            }
+        }
+    }
+
+    #[test]
+    fn keep_patterns_cover_hidden_components() {
+        let options = Options::new("/repo").keep_dotfiles([".gitignore", ".github/workflows", ".config/*.toml"]);
+        let dotfiles = Dotfiles::new(&options).unwrap();
+        let hidden = |rel: &str| dotfiles.hidden(Path::new(rel));
+        assert_eq!(hidden("src/main.rs"), []);
+        assert_eq!(hidden("a/b/.gitignore"), [(".gitignore".to_string(), Some(".gitignore"))]);
+        let workflow = [(".github".to_string(), Some(".github/workflows"))];
+        assert_eq!(hidden(".github/workflows/ci.yml"), workflow);
+        assert_eq!(hidden(".github/CODEOWNERS"), [(".github".to_string(), None)]);
+        assert_eq!(hidden("a/.github/workflows/ci.yml"), [(".github".to_string(), None)]);
+        assert_eq!(hidden(".config/owo.toml"), [(".config".to_string(), Some(".config/*.toml"))]);
+        assert_eq!(hidden(".config/sub/.env"), [(".config".to_string(), None), (".env".to_string(), None)]);
+
+        for (rel, excluded) in [
+            ("src/main.rs", false),
+            ("a/b/.gitignore", false),
+            (".github/workflows/ci.yml", false),
+            (".github/workflows/.draft.yml", true),
+            (".env", true),
+            (".config/owo.json", true),
+        ] {
+            assert_eq!(dotfiles.excludes(Path::new(rel)), excluded, "{}", rel);
+        }
+        let included = Dotfiles::new(&options.with_dotfiles(true)).unwrap();
+        assert!(!included.excludes(Path::new(".env")));
+    }
+
+    #[test]
+    fn vcs_internals_are_named_at_any_depth() {
+        for path in [".git", "vendor/lib/.git", "old/.hg", "a/b/.svn"] {
+            assert!(is_vcs_internals(Path::new(path)), "{}", path);
+        }
+        for path in [".github", ".gitignore", "git", "src/.git/HEAD", ".hgignore"] {
+            assert!(!is_vcs_internals(Path::new(path)), "{}", path);
+        }
+    }
+
+    #[cfg(all(unix, feature = "fs"))]
+    #[test]
+    fn special_files_are_named() {
+        let dir = tempfile::tempdir().unwrap();
+        let file = dir.path().join("a.txt");
+        std::fs::write(&file, "a\n").unwrap();
+        let socket = dir.path().join("sock");
+        let _listener = std::os::unix::net::UnixListener::bind(&socket).unwrap();
+        let kind = |path: &Path, empty_pseudo| {
+            special_file(path, std::fs::metadata(path).ok().map(|metadata| metadata.file_type()), empty_pseudo)
+        };
+        assert_eq!(kind(&file, false), None);
+        assert_eq!(kind(&file, true), Some("empty file on a pseudo file system"));
+        assert_eq!(kind(&socket, false), Some("socket"));
+        assert_eq!(kind(Path::new("/dev/null"), false), Some("character device"));
+    }
+
+    #[test]
+    fn bad_patterns_name_their_position() {
+        let options = Options::new("/repo").ignore(["a", "[b"]);
+        match Patterns::new(&options, &[]) {
+            Err(Error::Pattern { position, origin, .. }) => assert_eq!((position, origin.as_str()), (2, "--ignore")),
+            Err(error) => panic!("unexpected error: {}", error),
+            Ok(_) => panic!("`[b` compiled"),
+        }
+    }
+
+    /// Entries Windows marks hidden or system, whatever their names.
+    #[cfg(all(windows, feature = "fs"))]
+    mod hidden_attributes {
+        use super::*;
+        use std::process::Command;
+
+        /// Sets `flags`, like `+h` or `+s`, on `path` with `attrib`.
+        fn attrib(flags: &[&str], path: &Path) {
+            let status = Command::new("attrib").args(flags).arg(path).status().unwrap();
+            assert!(status.success(), "attrib {:?} {} failed", flags, path.display());
+        }
+
+        /// A root with a plain file, a hidden one, a system one and a hidden
+        /// directory holding a plain file.
+        fn root() -> tempfile::TempDir {
+            let root = tempfile::tempdir().unwrap();
+            for path in ["plain.txt", "marked.txt", "desktop.ini", "cache/data.txt"] {
+                let path = root.path().join(path);
+                std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+                std::fs::write(path, "x\n").unwrap();
+            }
+            attrib(&["+h"], &root.path().join("marked.txt"));
+            attrib(&["+s", "+h"], &root.path().join("desktop.ini"));
+            attrib(&["+h"], &root.path().join("cache"));
+            root
+        }
+
+        fn walked(options: Options) -> Vec<String> {
+            let root = options.root.clone();
+            let mut files = walk(&options.ignore(Vec::<String>::new()))
+                .unwrap()
+                .entries
+                .iter()
+                .filter(|entry| entry.file_type().is_some_and(|file_type| file_type.is_file()))
+                .map(|entry| entry.path().strip_prefix(&root).unwrap().to_string_lossy().replace('\\', "/"))
+                .collect::<Vec<_>>();
+            files.sort();
+            files
+        }
+
+        #[test]
+        fn attributes_mark_entries_hidden() {
+            let root = root();
+            let marked = |name: &str| marked_hidden(|| std::fs::metadata(root.path().join(name)).ok());
+            assert!(!marked("plain.txt"));
+            assert!(marked("marked.txt"));
+            assert!(marked("desktop.ini"));
+            assert!(marked("cache"));
+            assert!(!marked("cache/data.txt"));
+        }
+
+        #[test]
+        fn marked_entries_follow_the_dotfile_rule() {
+            let root = root();
+            assert_eq!(walked(Options::new(root.path())), ["plain.txt"]);
+            let all = ["cache/data.txt", "desktop.ini", "marked.txt", "plain.txt"];
+            assert_eq!(walked(Options::new(root.path()).with_dotfiles(true)), all);
+            let kept = Options::new(root.path()).keep_dotfiles(["desktop.ini", "cache"]);
+            assert_eq!(walked(kept), ["cache/data.txt", "desktop.ini", "plain.txt"]);
+        }
+    }
+}
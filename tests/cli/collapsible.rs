@@ -0,0 +1,23 @@
+//! `--collapsible` output against rendered fixtures, blank lines included:
+//! a fence inside `<details>` only renders with blank lines around it.
+
+use crate::{fixture, owo, stdout, write};
+
+fn packed(mode: &str) -> String {
+    let root = fixture(&[("src/main.rs", "fn main() {\n    println!(\"hi\");\n}\n"), ("a&b.txt", "one line\n")]);
+    write(root.path(), "logo.png", b"\x89PNG\r\n\x1a\n\0\0\0\rIHDR\0\0\0\x01");
+    let out = tempfile::tempdir().unwrap();
+    let path = out.path().join("out.md");
+    stdout(owo(root.path(), &["-o", path.to_str().unwrap(), mode, "."]));
+    std::fs::read_to_string(path).unwrap()
+}
+
+#[test]
+fn details_below_the_heading() {
+    assert_eq!(packed("--collapsible"), include_str!("../fixtures/collapsible/heading.md"));
+}
+
+#[test]
+fn summary_in_place_of_the_heading() {
+    assert_eq!(packed("--collapsible=summary"), include_str!("../fixtures/collapsible/summary.md"));
+}
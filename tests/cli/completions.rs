@@ -0,0 +1,25 @@
+//! `owo completions` for every shell clap_complete supports.
+
+use crate::{owo, stdout};
+
+#[test]
+fn every_shell_completes_the_flags() {
+    let dir = tempfile::tempdir().unwrap();
+    for shell in ["bash", "zsh", "fish", "powershell", "elvish"] {
+        let script = stdout(owo(dir.path(), &["completions", shell]));
+        // fish writes `-l with-dotfiles` rather than `--with-dotfiles`.
+        for flag in ["with-dotfiles", "keep-dotfile", "skip-generated", "fail-on-secret", "max-output-size"] {
+            assert!(script.contains(flag), "{} completions lack {}", shell, flag);
+        }
+        for subcommand in ["pack", "tree", "stats", "explain"] {
+            assert!(script.contains(subcommand), "{} completions lack {}", shell, subcommand);
+        }
+    }
+}
+
+#[test]
+fn unknown_shells_are_rejected() {
+    let dir = tempfile::tempdir().unwrap();
+    let output = owo(dir.path(), &["completions", "tcsh"]);
+    assert_eq!(output.status.code(), Some(2));
+}
@@ -0,0 +1,83 @@
+//! The dotfile rule and `--keep-dotfile`, after `-I` patterns and
+//! `.gitignore` files, with `--with-dotfiles` lifting it whole.
+
+use crate::{fixture, listed};
+use tempfile::TempDir;
+
+fn root() -> TempDir {
+    fixture(&[
+        ("src/main.rs", "x\n"),
+        (".gitignore", "*.log\n.cache/\n"),
+        (".env", "SECRET=1\n"),
+        (".github/workflows/ci.yml", "on: push\n"),
+        (".github/CODEOWNERS", "* @a\n"),
+        (".github/workflows/.draft.yml", "on: push\n"),
+        (".cache/data", "x\n"),
+        (".editorconfig", "root = true\n"),
+        ("docs/.gitignore", "draft.md\n"),
+        ("docs/draft.md", "x\n"),
+        ("debug.log", "x\n"),
+        (".hidden/.debug.log", "x\n"),
+    ])
+}
+
+fn packed(root: &TempDir, args: &[&str]) -> Vec<String> {
+    let mut files = listed(root.path(), args);
+    files.sort();
+    files
+}
+
+#[test]
+fn hidden_paths_stay_out_by_default() {
+    assert_eq!(packed(&root(), &[]), ["src/main.rs"]);
+}
+
+#[test]
+fn names_are_kept_at_any_depth_and_paths_from_the_root() {
+    let root = root();
+    let kept = packed(&root, &["--keep-dotfile", ".gitignore", "--keep-dotfile", ".github/workflows"]);
+    assert_eq!(kept, [".github/workflows/ci.yml", ".gitignore", "docs/.gitignore", "src/main.rs"]);
+}
+
+#[test]
+fn keeping_a_directory_keeps_only_whats_not_hidden_inside_it() {
+    let root = root();
+    let kept = packed(&root, &["--keep-dotfile", ".github"]);
+    assert_eq!(kept, [".github/CODEOWNERS", ".github/workflows/ci.yml", "src/main.rs"]);
+    let kept = packed(&root, &["--keep-dotfile", ".github", "--keep-dotfile", ".draft.yml"]);
+    assert!(kept.contains(&".github/workflows/.draft.yml".to_string()), "{:?}", kept);
+}
+
+#[test]
+fn ignore_patterns_win_over_keeping() {
+    let root = root();
+    // .env is on the default list, which -I replaces.
+    assert_eq!(packed(&root, &["--keep-dotfile", ".env"]), ["src/main.rs"]);
+    assert_eq!(packed(&root, &["-I", "ci.yml", "--keep-dotfile", ".github"]), [".github/CODEOWNERS", "src/main.rs"]);
+    assert_eq!(packed(&root, &["-I", "dist", "--keep-dotfile", ".env"]), [".env", "src/main.rs"]);
+}
+
+#[test]
+fn gitignore_files_win_over_keeping() {
+    let root = root();
+    assert_eq!(packed(&root, &["--keep-dotfile", ".cache"]), ["src/main.rs"]);
+    assert_eq!(packed(&root, &["--keep-dotfile", ".hidden"]), ["src/main.rs"]);
+    assert_eq!(packed(&root, &["--keep-dotfile", ".hidden", "--keep-dotfile", ".debug.log"]), ["src/main.rs"]);
+}
+
+#[test]
+fn with_dotfiles_keeps_every_hidden_path_the_ignores_leave() {
+    let root = root();
+    let all = [
+        ".editorconfig",
+        ".github/CODEOWNERS",
+        ".github/workflows/.draft.yml",
+        ".github/workflows/ci.yml",
+        ".gitignore",
+        "docs/.gitignore",
+        "src/main.rs",
+    ];
+    assert_eq!(packed(&root, &["-w"]), all);
+    assert_eq!(packed(&root, &["-w", "--keep-dotfile", ".github"]), all);
+    assert_eq!(packed(&root, &["-w", "-I", ".github|.env"]), [&all[..1], &all[4..]].concat());
+}
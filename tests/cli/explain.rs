@@ -0,0 +1,62 @@
+//! `owo explain`, one reason at a time.
+
+use crate::{fixture, owo, stdout};
+
+const FILES: &[(&str, &str)] = &[
+    ("src/main.rs", "fn main() {}\n"),
+    ("tests/cli.rs", "#[test]\nfn t() {}\n"),
+    ("target/debug/out.txt", "built\n"),
+    ("notes/draft.md", "draft\n"),
+    (".gitignore", "target/\n"),
+    (".owoignore", "notes/\n"),
+    (".config/app.toml", "x = 1\n"),
+    ("id_rsa", "key\n"),
+    ("Cargo.lock", "[[package]]\nname = \"a\"\n"),
+];
+
+/// The reason lines and the verdict `owo explain` prints for `args`.
+fn explained(args: &[&str]) -> (Vec<String>, String) {
+    let root = fixture(FILES);
+    let output = stdout(owo(root.path(), &[&["explain"], args].concat()));
+    let mut lines = output.lines().skip(1).map(str::to_string).collect::<Vec<_>>();
+    let verdict = lines.pop().unwrap();
+    (lines, verdict)
+}
+
+#[test]
+fn each_reason_is_named() {
+    for (args, reason, verdict) in [
+        (&["target/debug/out.txt"][..], "  gitignore: `target/` (.gitignore:1) excludes via target", "excluded"),
+        (&["notes/draft.md"], "  gitignore: `notes/` (.owoignore:1) excludes via notes", "excluded"),
+        (&["-I", "src", "src/main.rs"], "  ignore pattern: `src` (--ignore) excludes via src", "excluded"),
+        (
+            &[".config/app.toml"],
+            "  dotfiles: (hidden; pass --with-dotfiles or --keep-dotfile) excludes via .config",
+            "excluded",
+        ),
+        (&["-w", ".config/app.toml"], "  dotfiles: (--with-dotfiles) includes via .config", "included"),
+        (&["id_rsa"], "  sensitive: `id_rsa` (key or credentials file; pass --allow-sensitive) excludes", "excluded"),
+        (&["Cargo.lock"], "  lockfile: (lockfile, 23 B; pass --include-lockfiles) excludes", "excluded"),
+        (&["--only", "src/*.rs", "Cargo.lock"], "  only: (no --only glob matches) excludes", "excluded"),
+        (&["--only", "src/*.rs", "src/main.rs"], "  only: `src/*.rs` (--only) includes", "included"),
+        (&["--no-tests", "tests/cli.rs"], "  tests: (test code; --no-tests) excludes", "excluded"),
+    ] {
+        let (reasons, printed) = explained(args);
+        assert!(reasons.iter().any(|line| line == reason), "{:?}: {:?}", args, reasons);
+        assert_eq!(printed, format!("verdict: {}", verdict), "{:?}", args);
+    }
+}
+
+#[test]
+fn an_only_glob_keeps_a_test_file() {
+    let (reasons, verdict) = explained(&["--no-tests", "--only", "tests/cli.rs", "tests/cli.rs"]);
+    assert_eq!(reasons, ["  only: `tests/cli.rs` (--only) includes"]);
+    assert_eq!(verdict, "verdict: included");
+}
+
+#[test]
+fn a_file_no_rule_matches_is_included() {
+    let (reasons, verdict) = explained(&["src/main.rs"]);
+    assert_eq!(reasons, ["  no rule matches"]);
+    assert_eq!(verdict, "verdict: included");
+}
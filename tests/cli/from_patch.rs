@@ -0,0 +1,75 @@
+//! `--from-patch`, alone and narrowed by `--only`.
+
+use crate::{fixture, listed, owo, pack, stdout};
+use tempfile::TempDir;
+
+const PATCH: &str = "\
+--- a/src/a.rs
++++ b/src/a.rs
+@@ -1 +1 @@
+-x
++1
+--- a/b.txt
++++ b/b.txt
+@@ -1 +1 @@
+-x
++b
+--- a/gone.txt
++++ /dev/null
+@@ -1 +0,0 @@
+-x
+";
+
+/// A root with the files the patch touches, one it doesn't, and the patch
+/// in a directory of its own.
+fn root() -> (TempDir, TempDir) {
+    let root = fixture(&[("src/a.rs", "1\n2\n3\n"), ("b.txt", "b\n"), ("c.txt", "c\n")]);
+    let patch = fixture(&[("change.diff", PATCH)]);
+    (root, patch)
+}
+
+fn patched(root: &TempDir, patch: &TempDir, args: &[&str]) -> Vec<String> {
+    let patch = patch.path().join("change.diff");
+    let mut files = listed(root.path(), &[&["--from-patch", patch.to_str().unwrap()], args].concat());
+    files.sort();
+    files
+}
+
+#[test]
+fn packs_the_files_the_patch_touches() {
+    let (root, patch) = root();
+    assert_eq!(patched(&root, &patch, &[]), ["b.txt", "src/a.rs"]);
+    let path = patch.path().join("change.diff");
+    let (output, _) = pack(root.path(), &["--from-patch", path.to_str().unwrap()]);
+    assert!(output.status.success());
+    let stderr = String::from_utf8(output.stderr).unwrap();
+    assert!(stderr.contains("Missing 1 path(s) the patch touches:\n  gone.txt"), "{}", stderr);
+}
+
+#[test]
+fn only_narrows_the_patch() {
+    let (root, patch) = root();
+    assert_eq!(patched(&root, &patch, &["--only", "src/**"]), ["src/a.rs"]);
+    assert_eq!(patched(&root, &patch, &["--only", "src/**", "--only", "*.txt"]), ["b.txt", "src/a.rs"]);
+}
+
+#[test]
+fn only_ranges_apply_to_patched_files() {
+    let (root, patch) = root();
+    let path = patch.path().join("change.diff");
+    let (output, bundle) = pack(root.path(), &["--from-patch", path.to_str().unwrap(), "--only", "src/*.rs:2-2"]);
+    stdout(output);
+    assert!(bundle.contains("## File: `./src/a.rs` (lines 2–2)\n```rs\n2\n```"), "{}", bundle);
+    assert!(!bundle.contains("b.txt"), "{}", bundle);
+}
+
+#[test]
+fn only_matching_nothing_the_patch_touches_fails() {
+    let (root, patch) = root();
+    let path = patch.path().join("change.diff");
+    let output = owo(root.path(), &["--from-patch", path.to_str().unwrap(), "--only", "c.txt", "--list"]);
+    assert!(!output.status.success());
+    let stderr = String::from_utf8(output.stderr).unwrap();
+    assert!(stderr.contains("None of the 3 path(s) the patch touches matches --only"), "{}", stderr);
+}
+
@@ -0,0 +1,34 @@
+//! `--skip-generated`, and the files an `--only` glob names anyway.
+
+use crate::{fixture, pack, stdout};
+
+const FILES: &[(&str, &str)] = &[
+    ("app.min.js", "console.log(1);\n"),
+    ("vendor.min.js", "console.log(2);\n"),
+    ("x.go", "// Code generated by protoc-gen-go. DO NOT EDIT.\npackage x\n"),
+    ("y.go", "// Code generated by protoc-gen-go. DO NOT EDIT.\npackage y\n"),
+    ("main.go", "package main\n"),
+];
+
+/// The files a bundle holds, by their headings.
+fn packed(args: &[&str]) -> Vec<String> {
+    let root = fixture(FILES);
+    let (output, bundle) = pack(root.path(), args);
+    stdout(output);
+    let headings = bundle.lines().filter_map(|line| line.strip_prefix("## File: `./"));
+    let mut files = headings.map(|name| name.trim_end_matches('`').to_string()).collect::<Vec<_>>();
+    files.sort();
+    files
+}
+
+#[test]
+fn generated_files_are_skipped_by_suffix_and_marker() {
+    assert_eq!(packed(&["--skip-generated"]), ["main.go"]);
+}
+
+#[test]
+fn only_globs_override_skip_generated() {
+    let args = ["--skip-generated", "--only", "app.min.js", "--only", "x.go"];
+    assert_eq!(packed(&args), ["app.min.js", "x.go"]);
+    assert_eq!(packed(&["--skip-generated", "--only", "*.go"]), ["main.go", "x.go", "y.go"]);
+}
@@ -0,0 +1,58 @@
+//! `--group-by` against rendered fixtures: three languages and files with no
+//! extension, and the directories they sit in.
+
+use crate::{fixture, owo, pack, stdout};
+use tempfile::TempDir;
+
+fn root() -> TempDir {
+    fixture(&[
+        ("src/main.rs", "fn main() {}\n"),
+        ("src/db/query.sql", "select 1;\n"),
+        ("ci/build.yml", "on: push\n"),
+        ("src/lib.rs", "pub fn f() {}\n"),
+        ("Makefile", "all:\n\ttrue\n"),
+        ("LICENSE", "MIT\n"),
+        ("ci/run", "#!/bin/sh\necho hi\n"),
+    ])
+}
+
+fn grouped(root: &TempDir, by: &str) -> String {
+    let (output, bundle) = pack(root.path(), &["--group-by", by]);
+    stdout(output);
+    bundle
+}
+
+#[test]
+fn by_language() {
+    assert_eq!(grouped(&root(), "language"), include_str!("../fixtures/group_by/language.md"));
+}
+
+#[test]
+fn by_directory() {
+    assert_eq!(grouped(&root(), "dir"), include_str!("../fixtures/group_by/dir.md"));
+}
+
+#[test]
+fn json_follows_the_groups() {
+    let root = root();
+    let out = tempfile::tempdir().unwrap();
+    let path = out.path().join("out.json");
+    stdout(owo(root.path(), &["--group-by", "language", "-o", path.to_str().unwrap(), "."]));
+    let json: serde_json::Value = serde_json::from_str(&std::fs::read_to_string(path).unwrap()).unwrap();
+    let paths = json["files"].as_array().unwrap().iter().map(|file| file["path"].as_str().unwrap());
+    let expected = ["./Makefile", "./src/lib.rs", "./src/main.rs", "./src/db/query.sql", "./ci/build.yml", "./LICENSE"];
+    assert_eq!(paths.collect::<Vec<_>>()[..6], expected);
+}
+
+#[test]
+fn grouped_bundles_read_back() {
+    let root = root();
+    let out = tempfile::tempdir().unwrap();
+    std::fs::write(out.path().join("language.md"), grouped(&root, "language")).unwrap();
+    std::fs::write(out.path().join("dir.md"), grouped(&root, "dir")).unwrap();
+    std::fs::write(out.path().join("flat.md"), grouped(&root, "none")).unwrap();
+    for (old, new) in [("flat.md", "language.md"), ("flat.md", "dir.md"), ("language.md", "dir.md")] {
+        let diff = stdout(owo(out.path(), &["diff", old, new]));
+        assert_eq!(diff.trim(), "0 added, 0 removed, 0 modified", "{} {}", old, new);
+    }
+}
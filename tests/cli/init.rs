@@ -0,0 +1,32 @@
+//! `owo init`, whose files the next run reads back.
+
+use crate::{fixture, listed, owo, stdout};
+
+#[test]
+fn init_writes_what_the_next_run_reads() {
+    let root = fixture(&[
+        ("Cargo.toml", "[package]\n"),
+        ("src/main.rs", "fn main() {}\n"),
+        ("target/debug/x.d", "x\n"),
+    ]);
+    stdout(owo(root.path(), &["init", "--yes"]));
+    assert!(root.path().join("owo.toml").is_file());
+    assert!(root.path().join(".owoignore").is_file());
+
+    // The profile is off in owo.toml; .owoignore still leaves out target/.
+    let verbose = String::from_utf8(owo(root.path(), &["--list", "-v"]).stderr).unwrap();
+    assert!(!verbose.contains("warning"), "{}", verbose);
+    assert_eq!(listed(root.path(), &[]), ["Cargo.toml", "owo.toml", "src/main.rs"]);
+
+    stdout(owo(root.path(), &[]));
+    let bundle = std::fs::read_to_string(root.path().join("context.md")).unwrap();
+    assert!(bundle.contains("fn main() {}"), "{}", bundle);
+    assert!(!bundle.contains("x.d"), "{}", bundle);
+}
+
+#[test]
+fn init_keeps_existing_files() {
+    let root = fixture(&[("owo.toml", "output = \"mine.md\"\n")]);
+    assert!(!owo(root.path(), &["init", "--yes"]).status.success());
+    assert_eq!(std::fs::read_to_string(root.path().join("owo.toml")).unwrap(), "output = \"mine.md\"\n");
+}
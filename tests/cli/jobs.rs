@@ -0,0 +1,25 @@
+//! Concurrent reads and walks, which must not change a byte of the bundle.
+
+use crate::{pack, stdout, write};
+
+#[test]
+fn concurrent_runs_match_a_sequential_one() {
+    let root = tempfile::tempdir().unwrap();
+    for dir in 0..8 {
+        for file in 0..25 {
+            let contents = format!("// {} {}\n{}", dir, file, "fn f() {}\n".repeat(file * 40));
+            write(root.path(), &format!("d{}/sub{}/f{:02}.rs", dir, file % 3, file), contents.as_bytes());
+        }
+    }
+    write(root.path(), "README.md", b"# Fixture\n");
+    write(root.path(), "data.bin", &[0, 159, 146, 150]);
+
+    let (output, sequential) = pack(root.path(), &["--sequential"]);
+    stdout(output);
+    assert_eq!(sequential.matches("## File:").count(), 202);
+    for args in [&["--jobs", "64"][..], &["--jobs", "3", "--walk-threads", "8"], &[]] {
+        let (output, concurrent) = pack(root.path(), args);
+        stdout(output);
+        assert!(concurrent == sequential, "{:?} changed the bundle", args);
+    }
+}
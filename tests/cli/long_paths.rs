@@ -0,0 +1,22 @@
+//! Files past Windows' `MAX_PATH` and files with reserved device names, which
+//! must be packed like any other rather than vanish.
+
+use crate::{fixture, owo, stdout};
+
+#[test]
+fn deep_files_and_device_names_are_packed() {
+    // Seven components of 40 characters put the file well past 260.
+    let deep = format!("{}/deep.txt", vec!["d".repeat(40); 7].join("/"));
+    let root = fixture(&[(&deep, "from deep down\n"), ("con.txt", "console\n"), ("logs/aux.log", "auxiliary\n")]);
+    assert!(root.path().join(&deep).to_string_lossy().len() > 260);
+    let out = tempfile::tempdir().unwrap();
+    let path = out.path().join("out.md");
+    let run = owo(root.path(), &["-o", path.to_str().unwrap(), "--strict", "."]);
+    let stderr = String::from_utf8_lossy(&run.stderr).into_owned();
+    stdout(run);
+    let bundle = std::fs::read_to_string(path).unwrap();
+    for text in ["from deep down", "console", "auxiliary"] {
+        assert!(bundle.contains(text), "{} missing: {}", text, stderr);
+    }
+    assert!(stderr.contains("Packed 3 files"), "{}", stderr);
+}
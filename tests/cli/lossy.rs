@@ -0,0 +1,55 @@
+//! `--lossy`, which packs almost-UTF-8 files as text, on both sides of its
+//! threshold of one invalid sequence per 16 bytes.
+
+use crate::{pack, stdout, write};
+
+/// A 32-byte line of `a`s with its first `invalid` bytes at multiples of 4
+/// replaced by a lone `0xff`, each its own invalid sequence.
+fn line(invalid: usize) -> Vec<u8> {
+    let mut bytes = [vec![b'a'; 31], vec![b'\n']].concat();
+    for i in 0..invalid {
+        bytes[i * 4] = 0xff;
+    }
+    bytes
+}
+
+fn packed(name: &str, contents: &[u8], args: &[&str]) -> String {
+    let root = tempfile::tempdir().unwrap();
+    write(root.path(), name, contents);
+    let (output, bundle) = pack(root.path(), args);
+    stdout(output);
+    bundle
+}
+
+#[test]
+fn two_invalid_sequences_in_32_bytes_are_text() {
+    let bundle = packed("notes.txt", &line(2), &["--lossy"]);
+    assert!(bundle.contains("\u{FFFD}aaa\u{FFFD}aaaa"), "{}", bundle);
+    assert!(bundle.contains("(contained 2 invalid UTF-8 sequences, shown as \u{FFFD})"), "{}", bundle);
+}
+
+#[test]
+fn three_invalid_sequences_in_32_bytes_are_binary() {
+    let bundle = packed("notes.txt", &line(3), &["--lossy"]);
+    assert!(bundle.contains("[Binary file: 32 bytes]"), "{}", bundle);
+    assert!(!bundle.contains("aaaa"), "{}", bundle);
+}
+
+#[test]
+fn short_files_get_one_invalid_sequence() {
+    assert!(packed("a.txt", b"caf\xff\n", &["--lossy"]).contains("(contained 1 invalid UTF-8 sequence,"));
+    assert!(packed("a.txt", b"\xffa\xff\n", &["--lossy"]).contains("[Binary file: 4 bytes]"));
+}
+
+#[test]
+fn nul_bytes_stay_binary() {
+    let mut contents = line(1);
+    contents[31] = 0;
+    assert!(packed("notes.txt", &contents, &["--lossy"]).contains("[Binary file: 32 bytes]"));
+}
+
+#[test]
+fn without_lossy_any_invalid_sequence_is_binary() {
+    let bundle = packed("notes.txt", &line(1), &[]);
+    assert!(bundle.contains("[Binary file: 32 bytes]"), "{}", bundle);
+}
@@ -0,0 +1,81 @@
+//! Runs of the owo binary against fixture roots in temporary directories.
+
+mod collapsible;
+mod completions;
+mod dotfiles;
+mod explain;
+mod from_patch;
+mod generated;
+mod group_by;
+mod init;
+mod jobs;
+mod long_paths;
+mod lossy;
+mod names;
+mod profiles;
+mod ranges;
+mod special_files;
+mod split;
+mod strip_ansi;
+mod subcommands;
+mod unmatched;
+mod vcs;
+
+use std::path::Path;
+use std::process::{Command, Output};
+use tempfile::TempDir;
+
+/// A temporary root holding `files`, each a path relative to the root and
+/// its contents. Parent directories are created as needed.
+pub fn fixture(files: &[(&str, &str)]) -> TempDir {
+    let root = tempfile::tempdir().unwrap();
+    for (path, contents) in files {
+        write(root.path(), path, contents.as_bytes());
+    }
+    root
+}
+
+/// Writes `contents` to `path` under `root`, creating its parent directories.
+pub fn write(root: &Path, path: &str, contents: &[u8]) {
+    let path = root.join(path);
+    std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+    std::fs::write(path, contents).unwrap();
+}
+
+/// Runs owo with `args` from `dir`, with no config but what `dir` holds.
+pub fn owo(dir: &Path, args: &[&str]) -> Output {
+    let mut command = Command::new(env!("CARGO_BIN_EXE_owo"));
+    for (name, _) in std::env::vars_os() {
+        if name.to_string_lossy().starts_with("OWO_") {
+            command.env_remove(name);
+        }
+    }
+    command
+        .args(args)
+        .current_dir(dir)
+        .env("HOME", dir)
+        .env("XDG_CONFIG_HOME", dir.join(".config"))
+        .output()
+        .unwrap()
+}
+
+/// The stdout of a run that must succeed.
+pub fn stdout(output: Output) -> String {
+    assert!(output.status.success(), "owo failed: {}", String::from_utf8_lossy(&output.stderr));
+    String::from_utf8(output.stdout).unwrap()
+}
+
+/// Runs owo with `args` from `dir`, writing the bundle outside it, and returns
+/// the run with the bundle written, or an empty one when none was.
+pub fn pack(dir: &Path, args: &[&str]) -> (Output, String) {
+    let out = tempfile::tempdir().unwrap();
+    let path = out.path().join("out.md");
+    let output = owo(dir, &[args, &["-o", path.to_str().unwrap(), "."]].concat());
+    (output, std::fs::read_to_string(path).unwrap_or_default())
+}
+
+/// The files `owo --list` would pack from `dir` with `args`.
+pub fn listed(dir: &Path, args: &[&str]) -> Vec<String> {
+    let output = stdout(owo(dir, &[&["--list"], args].concat()));
+    output.lines().map(|line| line.trim_start_matches("./").to_string()).collect()
+}
@@ -0,0 +1,45 @@
+//! File names that mean something in Markdown, read back exactly by
+//! `owo diff` and `owo merge`.
+
+use crate::{owo, pack, stdout, write};
+
+const NAMES: &[&str] = &["a`b.txt", "``tick", "x | y.md", "#notes.md", "a # b", " lead.txt", "trail.txt ", " both "];
+
+#[test]
+fn names_round_trip_through_diff_and_merge() {
+    let root = tempfile::tempdir().unwrap();
+    for name in NAMES {
+        write(root.path(), &format!("dir/{}", name), b"one\n");
+    }
+    let dir = root.path().join("dir");
+    let (output, one) = pack(&dir, &[]);
+    stdout(output);
+    for name in NAMES {
+        write(root.path(), &format!("dir/{}", name), b"two\n");
+    }
+    let (output, two) = pack(&dir, &[]);
+    stdout(output);
+
+    let out = tempfile::tempdir().unwrap();
+    std::fs::write(out.path().join("one.md"), one).unwrap();
+    std::fs::write(out.path().join("two.md"), two).unwrap();
+    let (one_path, two_path, merged_path) = ("one.md", "two.md", "merged.md");
+
+    let diff = stdout(owo(out.path(), &["diff", "--json", one_path, two_path]));
+    let diff: serde_json::Value = serde_json::from_str(&diff).unwrap();
+    assert_eq!(diff["added"], serde_json::json!([]), "{}", diff);
+    assert_eq!(diff["removed"], serde_json::json!([]), "{}", diff);
+    let modified = diff["modified"].as_array().unwrap().iter();
+    let mut modified = modified.map(|file| file["path"].as_str().unwrap()).collect::<Vec<_>>();
+    let mut names = NAMES.to_vec();
+    modified.sort();
+    names.sort();
+    assert_eq!(modified, names);
+
+    stdout(owo(out.path(), &["merge", one_path, two_path, "-o", merged_path]));
+    let merged = stdout(owo(out.path(), &["diff", "--json", two_path, merged_path]));
+    let merged: serde_json::Value = serde_json::from_str(&merged).unwrap();
+    for key in ["added", "removed", "modified"] {
+        assert_eq!(merged[key], serde_json::json!([]), "{}", merged);
+    }
+}
@@ -0,0 +1,69 @@
+//! Ignore profiles detected from marker files at the root.
+
+use crate::{fixture, listed, owo};
+use tempfile::TempDir;
+
+/// Each ecosystem's marker, a source file that stays, and build output its
+/// profile leaves out.
+const ECOSYSTEMS: &[(&str, &str, &[&str])] = &[
+    ("Cargo.toml", "src/main.rs", &["target/debug/main.d", "src/lib.rs.bk"]),
+    ("package.json", "index.js", &["node_modules/left-pad/index.js", "coverage/lcov.info", ".next/cache.json"]),
+    ("pyproject.toml", "app.py", &["__pycache__/app.pyc", "venv/lib/site.py", "app.egg-info/PKG-INFO"]),
+    ("setup.py", "app.py", &["__pycache__/app.pyc", ".pytest_cache/README.md"]),
+    ("go.mod", "main.go", &["vendor/github.com/pkg/errors/errors.go"]),
+    ("App.csproj", "Program.cs", &["bin/Debug/App.dll", "obj/App.AssemblyInfo.cs"]),
+    ("App.sln", "Program.cs", &["obj/project.assets.json", "TestResults/run.trx"]),
+    ("pom.xml", "src/Main.java", &["target/classes/Main.class", "build/report.txt"]),
+];
+
+/// A root with every path in `paths`.
+fn root(paths: &[&str]) -> TempDir {
+    fixture(&paths.iter().map(|path| (*path, "x\n")).collect::<Vec<_>>())
+}
+
+/// The files packed from `root` with `args`, sorted. Dotfiles are packed, so
+/// only the profiles leave out `.next/` and the like.
+fn packed(root: &TempDir, args: &[&str]) -> Vec<String> {
+    let mut files = listed(root.path(), &[&["--with-dotfiles"], args].concat());
+    files.sort();
+    files
+}
+
+fn sorted(paths: &[&str]) -> Vec<String> {
+    let mut paths = paths.iter().map(|path| path.to_string()).collect::<Vec<_>>();
+    paths.sort();
+    paths
+}
+
+#[test]
+fn each_marker_drops_its_build_output() {
+    for (marker, source, output) in ECOSYSTEMS {
+        let root = root(&[&[*marker, *source], *output].concat());
+        assert_eq!(packed(&root, &[]), sorted(&[marker, source]), "{}", marker);
+    }
+}
+
+#[test]
+fn detection_is_reported() {
+    let root = root(&["Cargo.toml", "src/main.rs"]);
+    let stderr = String::from_utf8(owo(root.path(), &["--list"]).stderr).unwrap();
+    assert!(stderr.contains("Detected rust (Cargo.toml)"), "{}", stderr);
+}
+
+#[test]
+fn polyglot_roots_apply_every_profile() {
+    let root = root(&["Cargo.toml", "package.json", "go.mod", "target/a.o", "node_modules/x.js", "vendor/y.go"]);
+    assert_eq!(packed(&root, &[]), sorted(&["Cargo.toml", "go.mod", "package.json"]));
+}
+
+#[test]
+fn no_auto_profile_keeps_build_output() {
+    let root = root(&["Cargo.toml", "target/a.o"]);
+    assert_eq!(packed(&root, &["--no-auto-profile"]), sorted(&["Cargo.toml", "target/a.o"]));
+}
+
+#[test]
+fn explicit_profiles_replace_detection() {
+    let root = root(&["Cargo.toml", "target/a.o", "node_modules/x.js"]);
+    assert_eq!(packed(&root, &["--profile", "node"]), sorted(&["Cargo.toml", "target/a.o"]));
+}
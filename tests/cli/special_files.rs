@@ -0,0 +1,75 @@
+//! FIFOs and sockets, skipped before they're read so they can't hang a run.
+
+#![cfg(unix)]
+
+use crate::fixture;
+use std::os::unix::net::UnixListener;
+use std::path::Path;
+use std::process::{Command, Output, Stdio};
+use std::time::{Duration, Instant};
+
+fn mkfifo(path: &Path) {
+    assert!(Command::new("mkfifo").arg(path).status().unwrap().success());
+}
+
+/// Runs owo with `args` from `dir`, failing the test if it hasn't finished
+/// in 20 seconds. Nothing ever writes to the FIFOs, so reading one would
+/// block for good.
+fn owo_within_time(dir: &Path, args: &[&str]) -> Output {
+    let mut child = Command::new(env!("CARGO_BIN_EXE_owo"))
+        .args(args)
+        .current_dir(dir)
+        .env("HOME", dir)
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .unwrap();
+    let started = Instant::now();
+    while child.try_wait().unwrap().is_none() {
+        if started.elapsed() > Duration::from_secs(20) {
+            child.kill().unwrap();
+            panic!("owo {:?} hung on a FIFO", args);
+        }
+        std::thread::sleep(Duration::from_millis(20));
+    }
+    child.wait_with_output().unwrap()
+}
+
+#[test]
+fn fifos_and_sockets_are_listed_as_skipped() {
+    let root = fixture(&[("a.txt", "a\n")]);
+    mkfifo(&root.path().join("pipe"));
+    let _socket = UnixListener::bind(root.path().join("sock")).unwrap();
+    let out = tempfile::tempdir().unwrap();
+    let bundle = out.path().join("out.md");
+
+    let output = owo_within_time(root.path(), &["-v", "-o", bundle.to_str().unwrap(), "."]);
+    let stderr = String::from_utf8(output.stderr).unwrap();
+    assert!(output.status.success(), "{}", stderr);
+    assert!(stderr.contains("pipe") && stderr.contains("special file: FIFO"), "{}", stderr);
+    assert!(stderr.contains("sock") && stderr.contains("special file: socket"), "{}", stderr);
+    let bundle = std::fs::read_to_string(bundle).unwrap();
+    assert_eq!(bundle.matches("## File: ").count(), 1, "{}", bundle);
+
+    let listed = String::from_utf8(owo_within_time(root.path(), &["--list"]).stdout).unwrap();
+    assert_eq!(listed.lines().collect::<Vec<_>>(), ["./a.txt"]);
+}
+
+#[test]
+fn a_fifo_named_like_a_token_file_is_not_read() {
+    let root = fixture(&[("a.txt", "a\n")]);
+    mkfifo(&root.path().join(".npmrc"));
+    let output = owo_within_time(root.path(), &["--list", "-w", "."]);
+    assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+    assert_eq!(String::from_utf8(output.stdout).unwrap().lines().collect::<Vec<_>>(), ["./a.txt"]);
+
+    let out = tempfile::tempdir().unwrap();
+    let bundle = out.path().join("out.md");
+    let output = owo_within_time(root.path(), &["-w", "-v", "-o", bundle.to_str().unwrap(), "."]);
+    let stderr = String::from_utf8(output.stderr).unwrap();
+    assert!(output.status.success(), "{}", stderr);
+    assert!(stderr.contains(".npmrc") && stderr.contains("special file: FIFO"), "{}", stderr);
+
+    let output = owo_within_time(root.path(), &["explain", "-w", ".npmrc"]);
+    assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+}
@@ -0,0 +1,51 @@
+//! `--split-by-dir` bundles, whose names must never collide.
+
+use crate::{fixture, owo, stdout};
+
+#[test]
+fn colliding_names_get_suffixes() {
+    let root = fixture(&[
+        ("a/b/one.txt", "one\n"),
+        ("a-b/two.txt", "two\n"),
+        ("top.txt", "top\n"),
+        ("root/three.txt", "three\n"),
+        ("index/four.txt", "four\n"),
+    ]);
+    let out = tempfile::tempdir().unwrap();
+    let template = out.path().join("{dir}.md");
+    stdout(owo(root.path(), &["--split-by-dir=2", "-o", template.to_str().unwrap(), "."]));
+    let read = |name: &str| std::fs::read_to_string(out.path().join(format!("{}.md", name))).unwrap();
+    for (name, text) in [("a-b", "one"), ("a-b-2", "two"), ("root", "top"), ("root-2", "three"), ("index-2", "four")] {
+        let bundle = read(name);
+        assert!(bundle.contains(text), "{}.md lacks {}: {}", name, text, bundle);
+        assert_eq!(bundle.matches("## File:").count(), 1, "{}", bundle);
+    }
+    let mut written = std::fs::read_dir(out.path())
+        .unwrap()
+        .map(|entry| entry.unwrap().file_name().into_string().unwrap())
+        .collect::<Vec<_>>();
+    written.sort();
+    assert_eq!(written, ["a-b-2.md", "a-b.md", "index-2.md", "index.md", "root-2.md", "root.md"]);
+    let index = read("index");
+    for row in ["| `a/b` | [a-b.md]", "| `a-b` | [a-b-2.md]", "| `.` | [root.md]", "| `root` | [root-2.md]"] {
+        assert!(index.contains(row), "index lacks {}: {}", row, index);
+    }
+}
+
+#[test]
+fn a_directory_that_cannot_be_made_skips_only_its_bundle() {
+    let root = fixture(&[("a/one.txt", "one\n"), ("b/two.txt", "two\n"), ("c/three.txt", "three\n")]);
+    let out = tempfile::tempdir().unwrap();
+    // A file where the bundle for `b` needs a directory.
+    std::fs::write(out.path().join("b"), "in the way\n").unwrap();
+    let template = out.path().join("{dir}/bundle.md");
+    let output = owo(root.path(), &["--split-by-dir=1", "-o", template.to_str().unwrap(), "."]);
+    assert!(!output.status.success());
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(stderr.contains(&out.path().join("b").display().to_string()), "{}", stderr);
+    for (name, text) in [("a", "one"), ("c", "three")] {
+        let bundle = std::fs::read_to_string(out.path().join(name).join("bundle.md")).unwrap();
+        assert!(bundle.contains(text), "{} lacks {}: {}", name, text, bundle);
+    }
+    assert!(out.path().join("index/bundle.md").is_file());
+}
@@ -0,0 +1,18 @@
+//! `--strip-ansi` on a log captured from a colored `cargo build`.
+
+use crate::{pack, stdout, write};
+
+#[test]
+fn colored_cargo_output_is_packed_as_clean_text() {
+    let root = tempfile::tempdir().unwrap();
+    write(root.path(), "build.log", include_bytes!("../fixtures/ansi/cargo-build.txt"));
+    let (output, bundle) = pack(root.path(), &["--strip-ansi"]);
+    stdout(output);
+    let clean = include_str!("../fixtures/ansi/cargo-build.clean.txt");
+    assert!(bundle.contains(&format!("```log\n{}```", clean)), "{}", bundle);
+    assert!(!bundle.contains('\x1b'));
+
+    let (output, bundle) = pack(root.path(), &[]);
+    stdout(output);
+    assert!(bundle.contains("\x1b[1m\x1b[91merror[E0308]"), "{}", bundle);
+}
@@ -0,0 +1,33 @@
+//! `owo pack` as the default subcommand, so invocations from before
+//! subcommands keep working.
+
+use crate::{fixture, owo, stdout};
+
+#[test]
+fn flat_invocations_run_pack() {
+    let root = fixture(&[("src/main.rs", "fn main() {}\n"), ("README.md", "# demo\n")]);
+    let out = tempfile::tempdir().unwrap();
+    let (flat, pack) = (out.path().join("flat.md"), out.path().join("pack.md"));
+    stdout(owo(root.path(), &["-o", flat.to_str().unwrap(), "."]));
+    stdout(owo(root.path(), &["pack", "-o", pack.to_str().unwrap(), "."]));
+    let flat = std::fs::read_to_string(flat).unwrap();
+    let pack = std::fs::read_to_string(pack).unwrap();
+    assert!(flat.contains("fn main() {}"), "{}", flat);
+    assert!(flat.contains("# demo"), "{}", flat);
+    assert_eq!(flat, pack);
+}
+
+#[test]
+fn flags_before_the_path_and_a_bare_path() {
+    let root = fixture(&[("src/lib.rs", "pub fn f() {}\n")]);
+    stdout(owo(root.path(), &["--with-dotfiles", "-o", "out.md", "src"]));
+    let out = std::fs::read_to_string(root.path().join("out.md")).unwrap();
+    assert!(out.contains("pub fn f() {}"), "{}", out);
+}
+
+#[test]
+fn subcommand_names_still_route() {
+    let root = fixture(&[("a.txt", "a\n")]);
+    let tree = stdout(owo(root.path(), &["tree"]));
+    assert!(tree.contains("a.txt"), "{}", tree);
+}
@@ -0,0 +1,44 @@
+//! Ignore patterns and `--only` globs that match nothing: a warning each,
+//! and exit status 2 under `--strict-patterns`.
+
+use crate::{fixture, owo, pack};
+
+const FILES: &[(&str, &str)] = &[("src/main.rs", "fn main() {}\n"), ("docs/guide.md", "# Guide\n")];
+
+fn stderr(output: &std::process::Output) -> String {
+    String::from_utf8_lossy(&output.stderr).into_owned()
+}
+
+#[test]
+fn patterns_that_match_nothing_are_warned_about() {
+    let root = fixture(FILES);
+    let (output, bundle) = pack(root.path(), &["--ignore", "build/|docs/", "--only", "nope/**"]);
+    assert!(output.status.success(), "{}", stderr(&output));
+    let stderr = stderr(&output);
+    assert!(stderr.contains("ignore pattern 'build/' did not match any files or directories"), "{}", stderr);
+    assert!(stderr.contains("--only glob 'nope/**' did not match any files or directories"), "{}", stderr);
+    assert!(!stderr.contains("'docs/'"), "{}", stderr);
+    assert!(!bundle.contains("main.rs"), "{}", bundle);
+}
+
+#[test]
+fn matched_only_globs_are_not_warned_about() {
+    let root = fixture(FILES);
+    let (output, bundle) = pack(root.path(), &["--only", "src/**", "--only", "docs/guide.md:1-1", "--strict-patterns"]);
+    assert!(output.status.success(), "{}", stderr(&output));
+    assert!(!stderr(&output).contains("did not match"), "{}", stderr(&output));
+    assert!(bundle.contains("main.rs") && bundle.contains("guide.md"), "{}", bundle);
+}
+
+#[test]
+fn strict_patterns_fails_the_run() {
+    let root = fixture(FILES);
+    for args in [&["--ignore", "build/"][..], &["--only", "nope/**"], &["--only", "src/**", "--only", "*.py"]] {
+        let (output, bundle) = pack(root.path(), &[args, &["--strict-patterns"]].concat());
+        assert_eq!(output.status.code(), Some(2), "{:?}: {}", args, stderr(&output));
+        assert!(stderr(&output).contains("1 pattern(s) did not match"), "{:?}: {}", args, stderr(&output));
+        assert!(bundle.is_empty(), "{:?}", args);
+    }
+    let output = owo(root.path(), &["--list", "--only", "nope/**", "--strict-patterns", "."]);
+    assert_eq!(output.status.code(), Some(2), "{}", stderr(&output));
+}
@@ -0,0 +1,60 @@
+//! Version control metadata, left out at any depth whatever `-I` says.
+
+use crate::{fixture, listed, pack, stdout, write};
+use std::process::Command;
+use tempfile::TempDir;
+
+/// A git repository with a commit, a submodule-style `.git` file and `.hg`
+/// and `.svn` directories below it.
+fn repository() -> TempDir {
+    let root = fixture(&[
+        ("src/main.rs", "fn main() {}\n"),
+        ("vendor/lib/.git", "gitdir: ../../.git/modules/lib\n"),
+        ("vendor/lib/lib.rs", "pub fn f() {}\n"),
+        ("old/.hg/store/data", "x\n"),
+        ("old/.svn/entries", "x\n"),
+    ]);
+    let git = |args: &[&str]| {
+        let status = Command::new("git")
+            .args(["-c", "user.name=owo", "-c", "user.email=owo@example.com"])
+            .args(args)
+            .current_dir(root.path())
+            .output()
+            .unwrap()
+            .status;
+        assert!(status.success(), "git {:?} failed", args);
+    };
+    git(&["init", "--quiet"]);
+    git(&["add", "src"]);
+    git(&["commit", "--quiet", "-m", "first"]);
+    write(root.path(), ".git/modules/lib/HEAD", b"ref: refs/heads/main\n");
+    root
+}
+
+fn sections(bundle: &str) -> Vec<&str> {
+    bundle.lines().filter(|line| line.starts_with("## File: ")).collect()
+}
+
+#[test]
+fn nothing_inside_vcs_metadata_is_packed() {
+    let root = repository();
+    for args in [&[][..], &["-I", "dist"], &["-w"], &["-w", "-I", "dist"], &["--keep-dotfile", ".git"]] {
+        let (output, bundle) = pack(root.path(), args);
+        stdout(output);
+        let sections = sections(&bundle);
+        assert_eq!(sections.len(), 2, "{:?}: {:?}", args, sections);
+        for name in [".git", ".hg", ".svn"] {
+            assert!(!bundle.contains(name), "{:?} packed from {}: {:?}", args, name, sections);
+        }
+    }
+}
+
+#[test]
+fn include_vcs_internals_leaves_them_to_the_dotfile_rule() {
+    let root = repository();
+    assert_eq!(listed(root.path(), &["--include-vcs-internals"]).len(), 2);
+    let all = listed(root.path(), &["--include-vcs-internals", "-w"]);
+    for path in ["vendor/lib/.git", "old/.hg/store/data", "old/.svn/entries", ".git/HEAD", ".git/modules/lib/HEAD"] {
+        assert!(all.iter().any(|listed| listed == path), "no {} in {:?}", path, all);
+    }
+}
@@ -0,0 +1,46 @@
+//! Cancelling [`owo::stream`] by dropping it.
+
+use std::time::{Duration, Instant};
+use tokio_stream::StreamExt;
+
+/// A root with `a.txt`, read at once, and three slow files after it whose
+/// filter sleeps for `seconds`, then appends to `ran` in `marks` and passes
+/// the content through.
+fn slow_root(marks: &std::path::Path, seconds: u32) -> (tempfile::TempDir, owo::Options) {
+    let root = tempfile::tempdir().unwrap();
+    std::fs::write(root.path().join("a.txt"), "first\n").unwrap();
+    for i in 0..3 {
+        std::fs::write(root.path().join(format!("b{}.slow", i)), "slow\n").unwrap();
+    }
+    let filter = format!("*.slow:sleep {}; echo ran >> '{}'; cat", seconds, marks.join("ran").display());
+    let options = owo::Options::new(root.path()).filter_cmds(vec![filter]).jobs(Some(4));
+    (root, options)
+}
+
+#[cfg(unix)]
+#[tokio::test(flavor = "multi_thread")]
+async fn dropping_the_stream_stops_outstanding_reads() {
+    let marks = tempfile::tempdir().unwrap();
+    let (_root, options) = slow_root(marks.path(), 2);
+    let started = Instant::now();
+    let mut files = owo::stream(&options).unwrap();
+    let first = files.next().await.unwrap().unwrap();
+    assert!(first.path.ends_with("a.txt"));
+    drop(files);
+    assert!(started.elapsed() < Duration::from_millis(1500), "the first file waited on the slow ones");
+
+    // Outstanding filters are killed with their reads; none gets to finish.
+    tokio::time::sleep(Duration::from_secs(3)).await;
+    assert!(!marks.path().join("ran").exists(), "a filter ran on after the stream was dropped");
+}
+
+#[cfg(unix)]
+#[tokio::test(flavor = "multi_thread")]
+async fn reading_to_the_end_runs_every_filter() {
+    let marks = tempfile::tempdir().unwrap();
+    let (_root, options) = slow_root(marks.path(), 0);
+    let files = owo::stream(&options).unwrap().collect::<Vec<_>>().await;
+    assert_eq!(files.len(), 4);
+    let ran = std::fs::read_to_string(marks.path().join("ran")).unwrap();
+    assert_eq!(ran.lines().count(), 3);
+}